@@ -9,8 +9,12 @@ use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::SystemTime;
 
+use crate::policy::{CupcakeSpan, TelemetrySource};
+use crate::project_cache::{default_cache_path, mtime_key, CachedProject, ProjectCache};
+
 /// Represents an agent type supported by the collector.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
@@ -110,20 +114,28 @@ fn system_time_to_iso(time: SystemTime) -> String {
 }
 
 /// Discover all Claude Code projects and their sessions.
+///
+/// Project directories are scanned in parallel, and per-directory metadata is
+/// persisted in the project cache so unchanged projects aren't re-read.
 pub fn discover_projects() -> Vec<Project> {
     let projects_dir = match get_claude_projects_dir() {
         Some(p) if p.exists() => p,
         _ => return Vec::new(),
     };
 
-    let mut projects: HashMap<String, Project> = HashMap::new();
+    discover_projects_in(&projects_dir, default_cache_path().as_deref())
+}
 
+/// Discover projects under `projects_dir`, using (and refreshing) the cache
+/// file at `cache_path` if given.
+fn discover_projects_in(projects_dir: &Path, cache_path: Option<&Path>) -> Vec<Project> {
     // Iterate through project directories
-    let entries = match fs::read_dir(&projects_dir) {
+    let entries = match fs::read_dir(projects_dir) {
         Ok(e) => e,
         Err(_) => return Vec::new(),
     };
 
+    let mut project_dirs: Vec<(String, PathBuf)> = Vec::new();
     for entry in entries.flatten() {
         let path = entry.path();
         if !path.is_dir() {
@@ -140,11 +152,49 @@ pub fn discover_projects() -> Vec<Project> {
             continue;
         }
 
-        // Process project directory
-        if let Some(project) = process_project_dir(&path) {
+        project_dirs.push((dir_name, path));
+    }
+
+    let cache = cache_path.map(ProjectCache::load).unwrap_or_default();
+
+    // Process project directories, reusing cached metadata when the
+    // directory hasn't changed
+    let scanned = map_in_parallel(&project_dirs, |(dir_name, path)| {
+        let dir_mtime = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map(mtime_key)
+            .ok()?;
+        let cached = match cache.get(dir_name, dir_mtime) {
+            Some(cached) => cached.clone(),
+            None => scan_project_dir(path, dir_mtime)?,
+        };
+        let project = build_project(path, &cached);
+        Some((dir_name.clone(), cached, project))
+    });
+
+    let mut projects: HashMap<String, Project> = HashMap::new();
+    let mut new_cache = ProjectCache::default();
+    for (dir_name, cached, project) in scanned.into_iter().flatten() {
+        if let Some(project) = project {
             let key = project.project_path.clone();
             projects.insert(key, project);
         }
+        // A directory whose cwd wasn't found yet (e.g. its only session is
+        // still empty) is rescanned next time: appending to a session doesn't
+        // change the directory mtime, so caching it would hide the project
+        if cached.project_path.is_some() {
+            new_cache.insert(dir_name, cached);
+        }
+    }
+
+    // Only rewrite the cache when something changed (this also drops
+    // entries for directories that no longer exist)
+    if let Some(cache_path) = cache_path {
+        if new_cache != cache {
+            if let Err(e) = new_cache.save(cache_path) {
+                eprintln!("[projects] {}", e);
+            }
+        }
     }
 
     // Convert to sorted vec (by last activity, descending)
@@ -153,15 +203,52 @@ pub fn discover_projects() -> Vec<Project> {
     result
 }
 
-/// Process a single project directory (lightweight - no file content parsing).
-/// Only counts files and uses mtimes for the list view.
-fn process_project_dir(dir_path: &Path) -> Option<Project> {
+/// Apply `f` to every item using a pool of scoped worker threads,
+/// preserving input order.
+pub(crate) fn map_in_parallel<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(items.len());
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut out = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match items.get(i) {
+                            Some(item) => out.push((i, f(item))),
+                            None => return out,
+                        }
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_default())
+            .collect()
+    });
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+/// Scan a project directory: list its session files and find the project
+/// path from session content. This is the part worth caching.
+fn scan_project_dir(dir_path: &Path, dir_mtime: u64) -> Option<CachedProject> {
     let entries = fs::read_dir(dir_path).ok()?;
 
-    let mut session_files: Vec<PathBuf> = Vec::new();
+    let mut session_files: Vec<String> = Vec::new();
     let mut subagent_count = 0u32;
-    let mut project_path: Option<String> = None;
-    let mut latest_mtime: Option<SystemTime> = None;
 
     for entry in entries.flatten() {
         let path = entry.path();
@@ -187,30 +274,37 @@ fn process_project_dir(dir_path: &Path) -> Option<Project> {
             continue;
         }
 
-        // Track file mtime (much faster than parsing content)
-        if let Ok(metadata) = fs::metadata(&path) {
-            if let Ok(mtime) = metadata.modified() {
-                if latest_mtime.map_or(true, |latest| mtime > latest) {
-                    latest_mtime = Some(mtime);
-                }
-            }
-        }
-
-        session_files.push(path);
+        session_files.push(entry.file_name().to_string_lossy().to_string());
     }
+    session_files.sort();
 
-    // Try to extract project path from the first session file only
-    for path in &session_files {
-        if project_path.is_none() {
-            project_path = extract_project_path_from_content(path);
-            if project_path.is_some() {
-                break;
-            }
-        }
-    }
+    // Try to extract project path from the first session file that has one
+    let project_path = session_files
+        .iter()
+        .find_map(|name| extract_project_path_from_content(&dir_path.join(name)));
+
+    Some(CachedProject {
+        dir_mtime,
+        project_path,
+        session_files,
+        subagent_count,
+    })
+}
 
+/// Build the list-view Project for a scanned directory (lightweight - no file
+/// content parsing). Only uses session file mtimes, which change on every
+/// append and so are never cached.
+fn build_project(dir_path: &Path, cached: &CachedProject) -> Option<Project> {
     // If we couldn't find the project path from content, skip this project
-    let project_path = project_path?;
+    let project_path = cached.project_path.clone()?;
+
+    // Track file mtime (much faster than parsing content)
+    let latest_mtime = cached
+        .session_files
+        .iter()
+        .filter_map(|name| fs::metadata(dir_path.join(name)).ok())
+        .filter_map(|metadata| metadata.modified().ok())
+        .max();
 
     // Extract project name from path
     let project_name = Path::new(&project_path)
@@ -232,8 +326,8 @@ fn process_project_dir(dir_path: &Path) -> Option<Project> {
         agent_type: AgentType::ClaudeCode,
         project_path,
         project_name,
-        session_count: session_files.len() as u32,
-        subagent_count,
+        session_count: cached.session_files.len() as u32,
+        subagent_count: cached.subagent_count,
         last_activity,
         sessions: Vec::new(), // Empty for list view - load on demand via get_project_sessions
     })
@@ -310,7 +404,7 @@ pub fn get_sessions_for_project(project_path: &str) -> Vec<Session> {
 }
 
 /// Check if a string looks like a UUID (8-4-4-4-12 format).
-fn is_uuid_format(s: &str) -> bool {
+pub(crate) fn is_uuid_format(s: &str) -> bool {
     let parts: Vec<&str> = s.split('-').collect();
     if parts.len() != 5 {
         return false;
@@ -351,6 +445,10 @@ pub struct FileEdit {
     pub edit_type: FileEditType,
     /// Timestamp of the last edit to this file (ISO 8601)
     pub last_edited_at: Option<String>,
+    /// Lines added over all edits (a Write counts as all added)
+    pub lines_added: u64,
+    /// Lines removed over all edits
+    pub lines_removed: u64,
 }
 
 /// A single diff operation on a file.
@@ -418,6 +516,12 @@ pub fn get_session_file_path(project_path: &str, session_id: &str) -> Option<Pat
     }
 }
 
+/// Get the directory holding a project's session files.
+pub(crate) fn get_project_dir(project_path: &str) -> Option<PathBuf> {
+    let project_dir = get_claude_projects_dir()?.join(encode_project_path(project_path));
+    project_dir.is_dir().then_some(project_dir)
+}
+
 /// Get the sub-agent session file path for a project and agent ID.
 pub fn get_subagent_file_path(project_path: &str, agent_id: &str) -> Option<PathBuf> {
     let projects_dir = get_claude_projects_dir()?;
@@ -451,6 +555,14 @@ pub fn get_session_file_edits(project_path: &str, session_id: &str) -> Vec<FileE
     let mut file_operations: HashMap<String, FileEditType> = HashMap::new();
     let mut files_with_prior_content: HashSet<String> = HashSet::new();
     let mut file_timestamps: HashMap<String, String> = HashMap::new();
+    // (lines removed, lines added) per file
+    let mut file_line_changes: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut add_line_changes = |path: &str, old: &str, new: &str| {
+        let (removed, added) = crate::session_index::changed_lines(old, new);
+        let totals = file_line_changes.entry(path.to_string()).or_default();
+        totals.0 += removed;
+        totals.1 += added;
+    };
 
     for line in reader.lines() {
         let line = match line {
@@ -507,6 +619,18 @@ pub fn get_session_file_edits(project_path: &str, session_id: &str) -> Vec<FileE
                             }
                         }
 
+                        add_line_changes(
+                            &rel_path,
+                            input
+                                .get("old_string")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or(""),
+                            input
+                                .get("new_string")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or(""),
+                        );
+
                         // Mark as modified (we'll determine added/modified later)
                         file_operations.insert(rel_path.clone(), FileEditType::Modified);
 
@@ -526,6 +650,9 @@ pub fn get_session_file_edits(project_path: &str, session_id: &str) -> Vec<FileE
                             file_operations.insert(rel_path.clone(), FileEditType::Added);
                         }
 
+                        let content = input.get("content").and_then(|v| v.as_str());
+                        add_line_changes(&rel_path, "", content.unwrap_or(""));
+
                         // Track timestamp (always update to get the latest)
                         if let Some(ts) = timestamp {
                             file_timestamps.insert(rel_path, ts);
@@ -548,10 +675,14 @@ pub fn get_session_file_edits(project_path: &str, session_id: &str) -> Vec<FileE
                 edit_type = FileEditType::Added;
             }
             let last_edited_at = file_timestamps.get(&path).cloned();
+            let (lines_removed, lines_added) =
+                file_line_changes.get(&path).copied().unwrap_or_default();
             FileEdit {
                 path,
                 edit_type,
                 last_edited_at,
+                lines_added,
+                lines_removed,
             }
         })
         .collect();
@@ -924,7 +1055,7 @@ fn extract_tool_names(content: &Value) -> Option<String> {
 }
 
 /// Truncate string to max length with ellipsis (UTF-8 safe).
-fn truncate_string(s: &str, max_chars: usize) -> String {
+pub(crate) fn truncate_string(s: &str, max_chars: usize) -> String {
     let char_count = s.chars().count();
     if char_count <= max_chars {
         s.to_string()
@@ -1185,6 +1316,7 @@ pub fn get_session_events_with_index(
     index: &crate::session_index::SessionIndex,
     offset: Option<u32>,
     limit: Option<u32>,
+    branch_leaf: Option<&str>,
 ) -> SessionEventsResponse {
     let empty_response = SessionEventsResponse {
         events: Vec::new(),
@@ -1203,9 +1335,19 @@ pub fn get_session_events_with_index(
         Err(_) => return empty_response,
     };
 
+    // Restrict paging to a single branch if requested
+    let branch_lines = match branch_leaf {
+        Some(leaf) => match crate::session_index::get_branch_lines(index, leaf) {
+            Some(lines) => Some(lines),
+            None => return empty_response,
+        },
+        None => None,
+    };
+
     // Use pre-built line index from the session index
-    let line_index = &index.line_offsets;
-    let total_count = line_index.len() as u32;
+    let total_count = branch_lines
+        .as_ref()
+        .map_or(index.total_events(), |lines| lines.len() as u32);
     let offset = offset.unwrap_or(0);
     let limit = limit.unwrap_or(200);
 
@@ -1230,10 +1372,13 @@ pub fn get_session_events_with_index(
     let mut events = Vec::with_capacity(take_count);
 
     for idx in (end_idx..=start_idx).rev() {
-        let (byte_offset, line_len) = line_index[idx];
+        let seq = branch_lines.as_ref().map_or(idx as u32, |lines| lines[idx]);
+        let Some((byte_offset, line_len)) = index.line_offset(seq) else {
+            continue;
+        };
 
         if let Ok(line) = read_line_at_offset(&mut file, byte_offset, line_len) {
-            if let Some(event) = parse_session_event(&line, idx as u32, byte_offset) {
+            if let Some(event) = parse_session_event(&line, seq, byte_offset) {
                 events.push(event);
             }
         }
@@ -1407,7 +1552,7 @@ pub fn get_subagent_raw_json(project_path: &str, agent_id: &str, byte_offset: u6
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PolicyEvaluation {
-    /// Filename of the telemetry file
+    /// Filename of the telemetry file ("<file>#<line>" for spans in a JSONL stream)
     pub filename: String,
     /// Timestamp (ISO 8601)
     pub timestamp: String,
@@ -1421,137 +1566,70 @@ pub struct PolicyEvaluation {
     pub duration_ms: u64,
     /// Trace ID
     pub trace_id: String,
+    /// Claude Code session that triggered the evaluation
+    pub session_id: Option<String>,
+    /// tool_use id of the tool call being evaluated
+    pub tool_use_id: Option<String>,
+    /// Reason attached to the final decision (e.g., why a call was blocked)
+    pub reason: Option<String>,
+    /// Kind of telemetry location the span was read from
+    pub source: TelemetrySource,
+    /// Path of the file holding the span
+    pub path: String,
+    /// Line of the span within a JSONL stream (1-based; None for `.json` files)
+    pub line: Option<u32>,
+    /// Byte offset of that line, so the span can be read without scanning
+    /// the stream
+    #[serde(skip)]
+    pub offset: Option<u64>,
 }
 
-/// Get the policy telemetry directory for a project.
-fn get_telemetry_dir(project_path: &str) -> PathBuf {
+/// Get the project-level policy telemetry directory.
+pub fn get_telemetry_dir(project_path: &str) -> PathBuf {
     PathBuf::from(project_path)
         .join(".cupcake")
         .join("telemetry")
 }
 
-/// Get list of policy evaluations for a project.
-pub fn get_policy_evaluations(project_path: &str) -> Vec<PolicyEvaluation> {
-    let telemetry_dir = get_telemetry_dir(project_path);
-
-    if !telemetry_dir.exists() {
-        return Vec::new();
-    }
-
-    let entries = match fs::read_dir(&telemetry_dir) {
-        Ok(e) => e,
-        Err(_) => return Vec::new(),
-    };
-
-    let mut evaluations: Vec<PolicyEvaluation> = Vec::new();
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-
-        // Only process .json files
-        if path.extension().map(|e| e != "json").unwrap_or(true) {
-            continue;
-        }
-
-        let filename = match path.file_name() {
-            Some(n) => n.to_string_lossy().to_string(),
-            None => continue,
-        };
-
-        // Parse the JSON file to extract summary info
-        let content = match fs::read_to_string(&path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
-
-        let span: Value = match serde_json::from_str(&content) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
-        // Extract fields from the CupcakeSpan
-        let timestamp = span
-            .get("timestamp")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        let trace_id = span
-            .get("trace_id")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        let raw_event = span.get("raw_event");
-        let event_type = raw_event
-            .and_then(|e| e.get("hook_event_name"))
-            .and_then(|v| v.as_str())
-            .map(String::from);
-
-        let tool_name = raw_event
-            .and_then(|e| e.get("tool_name"))
-            .and_then(|v| v.as_str())
-            .map(String::from);
-
-        // Extract decision from response or phases
-        // final_decision is a tagged union like {"Allow": {...}} or {"Deny": {...}}
-        let decision = span
-            .get("response")
-            .and_then(|r| r.get("decision"))
-            .and_then(|d| {
-                // Tagged union - get the first key
-                d.as_object().and_then(|obj| obj.keys().next().cloned())
-            })
-            .or_else(|| {
-                // Try to get from last phase's final_decision
-                span.get("phases")
-                    .and_then(|p| p.as_array())
-                    .and_then(|arr| arr.last())
-                    .and_then(|phase| phase.get("evaluation"))
-                    .and_then(|eval| eval.get("final_decision"))
-                    .and_then(|d| {
-                        // Tagged union - get the first key
-                        d.as_object().and_then(|obj| obj.keys().next().cloned())
-                    })
-            });
-
-        let duration_ms = span
-            .get("total_duration_ms")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-
-        evaluations.push(PolicyEvaluation {
-            filename,
-            timestamp,
-            event_type,
-            tool_name,
-            decision,
-            duration_ms,
-            trace_id,
-        });
-    }
-
-    // Sort by timestamp descending (newest first)
-    evaluations.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    evaluations
-}
-
-/// Get the raw JSON content of a specific policy evaluation.
-pub fn get_policy_evaluation(project_path: &str, filename: &str) -> Option<String> {
-    let telemetry_dir = get_telemetry_dir(project_path);
-    let file_path = telemetry_dir.join(filename);
-
-    if !file_path.exists() {
-        return None;
+/// Extract the list-display summary from a parsed CupcakeSpan.
+pub fn parse_policy_evaluation(filename: String, span: &CupcakeSpan) -> PolicyEvaluation {
+    let event_field = |key: &str| span.event_field(key).map(String::from);
+    let final_decision = span.final_decision();
+
+    PolicyEvaluation {
+        filename,
+        timestamp: span.timestamp.clone(),
+        event_type: event_field("hook_event_name"),
+        tool_name: event_field("tool_name"),
+        decision: final_decision.as_ref().map(|d| d.name().to_string()),
+        duration_ms: span.total_duration_ms,
+        trace_id: span.trace_id.clone(),
+        session_id: event_field("session_id"),
+        tool_use_id: event_field("tool_use_id"),
+        reason: final_decision
+            .as_ref()
+            .and_then(|d| d.reason())
+            .map(String::from),
+        source: TelemetrySource::default(),
+        path: String::new(),
+        line: None,
+        offset: None,
     }
-
-    fs::read_to_string(&file_path).ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Instant;
+    use std::time::{Duration, Instant};
+
+    /// Set a directory's mtime explicitly, so tests don't depend on the
+    /// filesystem's timestamp resolution.
+    fn set_dir_mtime(dir: &Path, secs: u64) {
+        File::open(dir)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
 
     #[test]
     fn test_is_uuid_format() {
@@ -1569,17 +1647,155 @@ mod tests {
         assert!(!is_temp_project("-Users-ramos-cupcake-cupcake-rego-cupcake-rewrite"));
     }
 
+    /// Create a fake `~/.claude/projects` tree with `projects` projects of
+    /// `sessions` sessions each.
+    fn write_synthetic_projects(root: &Path, projects: usize, sessions: usize) {
+        for p in 0..projects {
+            let dir = root.join(format!("-Users-bench-project-{}", p));
+            fs::create_dir_all(&dir).unwrap();
+            for s in 0..sessions {
+                let name = format!("{:08x}-0000-4000-8000-{:012x}.jsonl", p, s);
+                let mut content = String::new();
+                for _ in 0..50 {
+                    content.push_str("{\"type\":\"summary\",\"summary\":\"padding\"}\n");
+                }
+                content.push_str(&format!("{{\"cwd\":\"/Users/bench/project-{}\"}}\n", p));
+                fs::write(dir.join(name), content).unwrap();
+            }
+            fs::write(dir.join("agent-01cdb344.jsonl"), "").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_discover_projects_uses_cache() {
+        let root =
+            std::env::temp_dir().join(format!("agent-console-discover-{}", std::process::id()));
+        let projects_dir = root.join("projects");
+        let cache_path = root.join("cache").join("projects.json");
+        write_synthetic_projects(&projects_dir, 3, 2);
+
+        let cold = discover_projects_in(&projects_dir, Some(&cache_path));
+        assert_eq!(cold.len(), 3);
+        assert!(cache_path.exists());
+        let project = cold
+            .iter()
+            .find(|p| p.project_path == "/Users/bench/project-1")
+            .unwrap();
+        assert_eq!(project.project_name, "project-1");
+        assert_eq!(project.session_count, 2);
+        assert_eq!(project.subagent_count, 1);
+
+        // A cached project must not be re-read: blank out its session content
+        // without touching the directory, and the cwd still comes from the cache
+        let dir = projects_dir.join("-Users-bench-project-0");
+        for entry in fs::read_dir(&dir).unwrap().flatten() {
+            fs::write(entry.path(), "").unwrap();
+        }
+        let warm = discover_projects_in(&projects_dir, Some(&cache_path));
+        assert!(warm
+            .iter()
+            .any(|p| p.project_path == "/Users/bench/project-0"));
+
+        // Adding a session changes the directory mtime and invalidates the entry
+        let dir = projects_dir.join("-Users-bench-project-2");
+        fs::write(dir.join("11111111-0000-4000-8000-000000000000.jsonl"), "").unwrap();
+        set_dir_mtime(&dir, 1);
+        let refreshed = discover_projects_in(&projects_dir, Some(&cache_path));
+        let project = refreshed
+            .iter()
+            .find(|p| p.project_path == "/Users/bench/project-2")
+            .unwrap();
+        assert_eq!(project.session_count, 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_projects_rescans_until_cwd_found() {
+        let root = std::env::temp_dir().join(format!(
+            "agent-console-discover-empty-{}",
+            std::process::id()
+        ));
+        let projects_dir = root.join("projects");
+        let cache_path = root.join("cache").join("projects.json");
+        let dir = projects_dir.join("-Users-bench-new");
+        fs::create_dir_all(&dir).unwrap();
+        let session = dir.join("22222222-0000-4000-8000-000000000000.jsonl");
+        fs::write(&session, "").unwrap();
+        set_dir_mtime(&dir, 1);
+
+        // The session is still empty, so there is no cwd yet
+        assert!(discover_projects_in(&projects_dir, Some(&cache_path)).is_empty());
+
+        // Appending to the session leaves the directory mtime alone
+        fs::write(&session, "{\"cwd\":\"/Users/bench/new\"}\n").unwrap();
+        set_dir_mtime(&dir, 1);
+        let projects = discover_projects_in(&projects_dir, Some(&cache_path));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].project_path, "/Users/bench/new");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn bench_discover_projects() {
+        let projects_dir = match get_claude_projects_dir() {
+            Some(p) if p.exists() => p,
+            _ => return,
+        };
+        let cache_path = std::env::temp_dir()
+            .join(format!("agent-console-bench-cache-{}", std::process::id()))
+            .join("projects.json");
+
+        for run in ["cold", "warm"] {
+            let start = Instant::now();
+            let projects = discover_projects_in(&projects_dir, Some(&cache_path));
+            let elapsed = start.elapsed();
+            println!(
+                "discover_projects ({}): {} projects in {:?}",
+                run,
+                projects.len(),
+                elapsed
+            );
+            // Should complete in under 500ms with optimizations
+            assert!(elapsed.as_millis() < 2000, "Too slow: {:?}", elapsed);
+        }
+
+        let _ = fs::remove_dir_all(cache_path.parent().unwrap());
+    }
+
+    /// Cold (no cache) vs warm (cached) discovery over a synthetic tree.
+    ///
+    /// Run with `cargo test --release bench_ -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_discover_projects_cold_and_warm() {
+        let root = std::env::temp_dir().join(format!("agent-console-bench-{}", std::process::id()));
+        let projects_dir = root.join("projects");
+        let cache_path = root.join("cache").join("projects.json");
+        write_synthetic_projects(&projects_dir, 500, 20);
+
+        let start = Instant::now();
+        let cold = discover_projects_in(&projects_dir, Some(&cache_path));
+        let cold_elapsed = start.elapsed();
+
         let start = Instant::now();
-        let projects = discover_projects();
-        let elapsed = start.elapsed();
+        let warm = discover_projects_in(&projects_dir, Some(&cache_path));
+        let warm_elapsed = start.elapsed();
+
+        assert_eq!(cold.len(), 500);
+        assert_eq!(warm.len(), cold.len());
         println!(
-            "discover_projects: {} projects in {:?}",
-            projects.len(),
-            elapsed
+            "discover_projects (cold): {} projects in {:?}",
+            cold.len(),
+            cold_elapsed
         );
-        // Should complete in under 500ms with optimizations
-        assert!(elapsed.as_millis() < 2000, "Too slow: {:?}", elapsed);
+        println!(
+            "discover_projects (warm): {} projects in {:?}",
+            warm.len(),
+            warm_elapsed
+        );
+
+        fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -0,0 +1,225 @@
+//! Per-session activity, bucketed by hour and cached on disk.
+//!
+//! Scanning every session of every project is too slow to do on each
+//! dashboard refresh, so each session file's activity is remembered keyed by
+//! its size and mtime. Only sessions that grew since the last refresh (usually
+//! the few that are running) are read again.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::project_cache::mtime_key;
+use crate::session_index::{ActiveTime, AssistantTurns, TokenUsage};
+
+/// Bump when the cached shape changes so stale caches are discarded.
+const CACHE_VERSION: u32 = 2;
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+/// Activity within one hour of a session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HourActivity {
+    /// Start of the hour (seconds since the epoch)
+    pub hour: i64,
+    /// Time between events, excluding idle gaps
+    pub active_ms: u64,
+    /// Token usage of the assistant turns started in this hour
+    pub tokens: TokenUsage,
+    /// Files edited or written (absolute paths, deduplicated)
+    pub files_edited: Vec<String>,
+}
+
+/// Activity of one session file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionActivity {
+    /// File size when scanned
+    pub size: u64,
+    /// File mtime (nanoseconds since the epoch) when scanned
+    pub modified: u64,
+    /// First event (seconds since the epoch)
+    pub started_at: Option<i64>,
+    /// Last event (seconds since the epoch)
+    pub ended_at: Option<i64>,
+    /// Hours with any activity, in order
+    pub hours: Vec<HourActivity>,
+}
+
+/// Session activity keyed by session file path.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityCache {
+    version: u32,
+    sessions: HashMap<String, SessionActivity>,
+}
+
+/// Default location of the cache file.
+pub fn default_cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("io.eqtylab.agent-console").join("activity.json"))
+}
+
+impl ActivityCache {
+    /// Load the cache, returning an empty one if it is missing, unreadable,
+    /// or from an older version.
+    pub fn load(path: &Path) -> Self {
+        let cache = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ActivityCache>(&bytes).ok());
+        match cache {
+            Some(cache) if cache.version == CACHE_VERSION => cache,
+            _ => Self::default(),
+        }
+    }
+
+    /// Write the cache atomically (temp file + rename).
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        }
+        let json = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize activity cache: {}", e))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json).map_err(|e| format!("Failed to write activity cache: {}", e))?;
+        fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write activity cache: {}", e))
+    }
+
+    /// Get the cached activity of a session file if it hasn't changed.
+    pub fn get(&self, path: &str, size: u64, modified: u64) -> Option<&SessionActivity> {
+        self.sessions
+            .get(path)
+            .filter(|a| a.size == size && a.modified == modified)
+    }
+
+    pub fn insert(&mut self, path: String, activity: SessionActivity) {
+        self.version = CACHE_VERSION;
+        self.sessions.insert(path, activity);
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Drop entries for files that are not in `paths` (deleted sessions).
+    pub fn retain(&mut self, paths: &HashSet<String>) {
+        self.sessions.retain(|path, _| paths.contains(path));
+    }
+}
+
+/// Activity of a session file, from the cache or by scanning it.
+///
+/// Returns the activity and whether it had to be scanned.
+pub fn session_activity(cache: &ActivityCache, path: &Path) -> Option<(SessionActivity, bool)> {
+    let metadata = fs::metadata(path).ok()?;
+    let size = metadata.len();
+    let modified = metadata.modified().map(mtime_key).ok()?;
+
+    if let Some(cached) = cache.get(&path.to_string_lossy(), size, modified) {
+        return Some((cached.clone(), false));
+    }
+    let mut activity = scan_session_activity(path).ok()?;
+    activity.size = size;
+    activity.modified = modified;
+    Some((activity, true))
+}
+
+/// Read a session file and bucket its activity by hour.
+fn scan_session_activity(path: &Path) -> Result<SessionActivity, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let mut hours: BTreeMap<i64, HourActivity> = BTreeMap::new();
+    let mut activity = SessionActivity::default();
+    // Counted the same way as session stats, so both report the same totals
+    let mut active = ActiveTime::default();
+    let mut turns = AssistantTurns::default();
+
+    for line in BufReader::new(file).lines() {
+        // Skip lines that can't be read (e.g. invalid UTF-8)
+        let Ok(line) = line else {
+            continue;
+        };
+        let Ok(entry) = serde_json::from_str::<ActivityEntry>(&line) else {
+            continue;
+        };
+        let Some(time) = entry
+            .timestamp
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        else {
+            continue;
+        };
+        let seconds = time.timestamp();
+        let hour = hours
+            .entry(seconds.div_euclid(SECONDS_PER_HOUR) * SECONDS_PER_HOUR)
+            .or_default();
+
+        activity.started_at = Some(activity.started_at.map_or(seconds, |s| s.min(seconds)));
+        activity.ended_at = Some(activity.ended_at.map_or(seconds, |e| e.max(seconds)));
+
+        hour.active_ms += active.record(time);
+
+        if entry.entry_type.as_deref() != Some("assistant") {
+            continue;
+        }
+        let Some(message) = entry.message else {
+            continue;
+        };
+        if turns.record(message.id.as_deref()) {
+            if let Some(usage) = &message.usage {
+                hour.tokens.add(usage);
+            }
+        }
+        if let Some(Value::Array(items)) = &message.content {
+            for path in items.iter().filter_map(edited_file) {
+                if !hour.files_edited.iter().any(|f| f == path) {
+                    hour.files_edited.push(path.to_string());
+                }
+            }
+        }
+    }
+
+    activity.hours = hours
+        .into_iter()
+        .map(|(start, mut hour)| {
+            hour.hour = start;
+            hour
+        })
+        .collect();
+    Ok(activity)
+}
+
+/// File path of an Edit, MultiEdit, Write or NotebookEdit tool_use.
+fn edited_file(item: &Value) -> Option<&str> {
+    if item.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
+        return None;
+    }
+    let key = match item.get("name").and_then(|v| v.as_str())? {
+        "Edit" | "MultiEdit" | "Write" => "file_path",
+        "NotebookEdit" => "notebook_path",
+        _ => return None,
+    };
+    item.pointer(&format!("/input/{}", key))?.as_str()
+}
+
+// === JSON Parsing Structures ===
+
+#[derive(Deserialize)]
+struct ActivityEntry {
+    #[serde(rename = "type")]
+    entry_type: Option<String>,
+    timestamp: Option<String>,
+    message: Option<ActivityMessage>,
+}
+
+#[derive(Deserialize)]
+struct ActivityMessage {
+    id: Option<String>,
+    usage: Option<Value>,
+    content: Option<Value>,
+}
@@ -0,0 +1,558 @@
+//! Cross-project activity dashboard.
+//!
+//! Aggregates sessions started, active time, tokens, files edited, commits
+//! and policy denials per project and per day over a date range, in a given
+//! timezone. Session activity comes from the on-disk cache in [`activity`],
+//! so only sessions that changed since the last refresh are read.
+
+mod activity;
+
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, TimeZone};
+use git2::{Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::claude_code::{get_project_dir, map_in_parallel, Project};
+use crate::policy::TelemetryStore;
+use crate::project_cache::mtime_key;
+
+use activity::{default_cache_path, session_activity, ActivityCache, SessionActivity};
+
+/// Longest range a dashboard can cover.
+const MAX_DAYS: i64 = 366;
+
+/// Final decisions that count as a policy denial.
+const DENIAL_DECISIONS: [&str; 3] = ["Deny", "Block", "Halt"];
+
+/// Range and timezone options for `compute_dashboard`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardQuery {
+    /// First day (YYYY-MM-DD, defaults to six days before `until`)
+    pub since: Option<String>,
+    /// Last day, inclusive (YYYY-MM-DD, defaults to today)
+    pub until: Option<String>,
+    /// "local" (the default, follows daylight saving), "UTC", or a fixed
+    /// offset like "+05:30"
+    pub timezone: Option<String>,
+}
+
+/// Activity counters for a project (or all projects) over a period.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityCounts {
+    pub sessions_started: u32,
+    /// Time between events, excluding idle gaps (main sessions only, since
+    /// sub-agents run while their session is active)
+    pub active_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Distinct files edited or written
+    pub files_edited: u32,
+    /// Commits reachable from HEAD, by author time
+    pub commits: u32,
+    /// Policy evaluations that denied, blocked or halted
+    pub policy_denials: u32,
+}
+
+impl ActivityCounts {
+    fn add(&mut self, other: &ActivityCounts) {
+        self.sessions_started += other.sessions_started;
+        self.active_ms += other.active_ms;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.files_edited += other.files_edited;
+        self.commits += other.commits;
+        self.policy_denials += other.policy_denials;
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Activity of one project.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectActivity {
+    pub project_path: String,
+    pub project_name: String,
+    /// Totals over the range (files are counted once across days)
+    pub totals: ActivityCounts,
+    /// One entry per day of the range
+    pub days: Vec<ActivityCounts>,
+}
+
+/// Dashboard time series.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dashboard {
+    /// Timezone the days are in
+    pub timezone: String,
+    /// Days of the range (YYYY-MM-DD)
+    pub days: Vec<String>,
+    /// All projects, one entry per day
+    pub totals: Vec<ActivityCounts>,
+    /// Projects with activity in the range, most active first
+    pub projects: Vec<ProjectActivity>,
+}
+
+/// Timezone that days are counted in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    fn parse(value: Option<&str>) -> Result<Self, String> {
+        let value = match value.map(str::trim) {
+            None | Some("") => return Ok(Zone::Local),
+            Some(value) if value.eq_ignore_ascii_case("local") => return Ok(Zone::Local),
+            Some(value) if value.eq_ignore_ascii_case("utc") || value == "Z" => "+00:00",
+            Some(value) => value,
+        };
+
+        let invalid = || format!("Invalid timezone: {}", value);
+        let (sign, rest) = if let Some(rest) = value.strip_prefix('+') {
+            (1, rest)
+        } else if let Some(rest) = value.strip_prefix('-') {
+            (-1, rest)
+        } else {
+            return Err(invalid());
+        };
+        let digits: String = rest.chars().filter(|c| *c != ':').collect();
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+        let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(Zone::Fixed)
+            .ok_or_else(invalid)
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Zone::Local => "local".to_string(),
+            Zone::Fixed(offset) => offset.to_string(),
+        }
+    }
+
+    /// The day a point in time (seconds since the epoch) falls on.
+    fn date_of(&self, seconds: i64) -> Option<NaiveDate> {
+        let time = DateTime::from_timestamp(seconds, 0)?;
+        Some(match self {
+            Zone::Local => time.with_timezone(&Local).date_naive(),
+            Zone::Fixed(offset) => time.with_timezone(offset).date_naive(),
+        })
+    }
+
+    /// Start of a day, in seconds since the epoch.
+    fn day_start(&self, date: NaiveDate) -> Option<i64> {
+        let midnight = date.and_hms_opt(0, 0, 0)?;
+        let start = match self {
+            Zone::Local => Local.from_local_datetime(&midnight).earliest()?.timestamp(),
+            Zone::Fixed(offset) => offset
+                .from_local_datetime(&midnight)
+                .earliest()?
+                .timestamp(),
+        };
+        Some(start)
+    }
+
+    fn today(&self) -> NaiveDate {
+        match self {
+            Zone::Local => Local::now().date_naive(),
+            Zone::Fixed(offset) => chrono::Utc::now().with_timezone(offset).date_naive(),
+        }
+    }
+}
+
+/// The days covered by a query.
+struct DayRange {
+    zone: Zone,
+    first: NaiveDate,
+    len: usize,
+    /// Start of the first day (seconds since the epoch)
+    start: i64,
+    /// Start of the day after the last (seconds since the epoch)
+    end: i64,
+}
+
+impl DayRange {
+    fn new(query: &DashboardQuery) -> Result<Self, String> {
+        let zone = Zone::parse(query.timezone.as_deref())?;
+        let parse_date = |value: &str| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", value))
+        };
+        let last = match &query.until {
+            Some(until) => parse_date(until)?,
+            None => zone.today(),
+        };
+        let first = match &query.since {
+            Some(since) => parse_date(since)?,
+            None => last - Duration::days(6),
+        };
+
+        let days = (last - first).num_days() + 1;
+        if days < 1 {
+            return Err("Range start is after its end".to_string());
+        }
+        if days > MAX_DAYS {
+            return Err(format!("Range is longer than {} days", MAX_DAYS));
+        }
+
+        let invalid = || "Invalid date range".to_string();
+        Ok(Self {
+            zone,
+            first,
+            len: days as usize,
+            start: zone.day_start(first).ok_or_else(invalid)?,
+            end: zone
+                .day_start(last + Duration::days(1))
+                .ok_or_else(invalid)?,
+        })
+    }
+
+    /// Index of the day a point in time falls on, if it's in the range.
+    fn day_index(&self, seconds: i64) -> Option<usize> {
+        if seconds < self.start || seconds >= self.end {
+            return None;
+        }
+        let days = (self.zone.date_of(seconds)? - self.first).num_days();
+        usize::try_from(days).ok().filter(|&i| i < self.len)
+    }
+
+    fn labels(&self) -> Vec<String> {
+        (0..self.len)
+            .map(|i| {
+                (self.first + Duration::days(i as i64))
+                    .format("%Y-%m-%d")
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+/// Session activity of one project directory.
+struct ProjectScan {
+    days: Vec<ActivityCounts>,
+    /// Distinct files edited over the whole range
+    files_edited: u32,
+    /// Every session file in the directory (for pruning the cache)
+    seen: Vec<String>,
+    /// Activity of session files that had to be read
+    scanned: Vec<(String, SessionActivity)>,
+}
+
+/// Aggregate the sessions (and sub-agent files) of a project directory.
+///
+/// Activity is bucketed by hour, so in timezones offset by a fraction of an
+/// hour an hour straddling midnight counts toward the day it starts on.
+fn scan_project(project_dir: &Path, range: &DayRange, cache: &ActivityCache) -> ProjectScan {
+    let mut scan = ProjectScan {
+        days: vec![ActivityCounts::default(); range.len],
+        files_edited: 0,
+        seen: Vec::new(),
+        scanned: Vec::new(),
+    };
+    let mut day_files: Vec<HashSet<String>> = vec![HashSet::new(); range.len];
+    let mut all_files: HashSet<String> = HashSet::new();
+
+    let entries = fs::read_dir(project_dir).into_iter().flatten().flatten();
+    for entry in entries {
+        let path = entry.path();
+        if path.extension().map(|e| e != "jsonl").unwrap_or(true) {
+            continue;
+        }
+        scan.seen.push(path.to_string_lossy().to_string());
+
+        // A file last written before the range has no activity in it
+        let modified = entry.metadata().and_then(|m| m.modified()).map(mtime_key);
+        if modified.is_ok_and(|m| (m / 1_000_000_000) < range.start as u64) {
+            continue;
+        }
+
+        let Some((activity, scanned)) = session_activity(cache, &path) else {
+            continue;
+        };
+        let is_agent = path
+            .file_stem()
+            .is_some_and(|s| s.to_string_lossy().starts_with("agent-"));
+
+        if !is_agent {
+            if let Some(day) = activity.started_at.and_then(|s| range.day_index(s)) {
+                scan.days[day].sessions_started += 1;
+            }
+        }
+        for hour in &activity.hours {
+            let Some(day) = range.day_index(hour.hour) else {
+                continue;
+            };
+            let counts = &mut scan.days[day];
+            if !is_agent {
+                counts.active_ms += hour.active_ms;
+            }
+            counts.input_tokens += hour.tokens.input_tokens;
+            counts.output_tokens += hour.tokens.output_tokens;
+            counts.cache_read_tokens += hour.tokens.cache_read_tokens;
+            counts.cache_creation_tokens += hour.tokens.cache_creation_tokens;
+            day_files[day].extend(hour.files_edited.iter().cloned());
+            all_files.extend(hour.files_edited.iter().cloned());
+        }
+
+        if scanned {
+            scan.scanned
+                .push((path.to_string_lossy().to_string(), activity));
+        }
+    }
+
+    for (counts, files) in scan.days.iter_mut().zip(&day_files) {
+        counts.files_edited = files.len() as u32;
+    }
+    scan.files_edited = all_files.len() as u32;
+    scan
+}
+
+/// Count commits reachable from HEAD per day, by author time.
+fn count_commits(project_path: &str, range: &DayRange, days: &mut [ActivityCounts]) {
+    let Ok(repo) = Repository::open(project_path) else {
+        return;
+    };
+    let Ok(mut walk) = repo.revwalk() else {
+        return;
+    };
+    if walk.set_sorting(Sort::TIME).is_err() || walk.push_head().is_err() {
+        return;
+    }
+
+    for oid in walk.flatten() {
+        let Ok(commit) = repo.find_commit(oid) else {
+            continue;
+        };
+        // Newest first, so stop once commits predate the range
+        if commit.time().seconds() < range.start {
+            break;
+        }
+        let authored_at = commit.author().when().seconds();
+        if let Some(day) = range.day_index(authored_at) {
+            days[day].commits += 1;
+        }
+    }
+}
+
+/// Count policy denials per day from a project's telemetry.
+fn count_denials(
+    telemetry: &TelemetryStore,
+    project_path: &str,
+    range: &DayRange,
+    days: &mut [ActivityCounts],
+) -> Result<(), String> {
+    telemetry.with_index(project_path, |index| {
+        for evaluation in index.summaries().map(|s| &s.evaluation) {
+            let denied = evaluation
+                .decision
+                .as_deref()
+                .is_some_and(|d| DENIAL_DECISIONS.contains(&d));
+            if !denied {
+                continue;
+            }
+            let day = DateTime::parse_from_rfc3339(&evaluation.timestamp)
+                .ok()
+                .and_then(|t| range.day_index(t.timestamp()));
+            if let Some(day) = day {
+                days[day].policy_denials += 1;
+            }
+        }
+    })
+}
+
+/// Aggregate activity across projects for the dashboard.
+pub fn compute_dashboard(
+    projects: &[Project],
+    telemetry: &TelemetryStore,
+    query: &DashboardQuery,
+) -> Result<Dashboard, String> {
+    let range = DayRange::new(query)?;
+    let cache_path = default_cache_path();
+    let mut cache = cache_path
+        .as_deref()
+        .map(ActivityCache::load)
+        .unwrap_or_default();
+
+    let scans = map_in_parallel(projects, |project| {
+        let project_dir = get_project_dir(&project.project_path)?;
+        let mut scan = scan_project(&project_dir, &range, &cache);
+        count_commits(&project.project_path, &range, &mut scan.days);
+        Some(scan)
+    });
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut changed = false;
+    let mut totals = vec![ActivityCounts::default(); range.len];
+    let mut project_activity: Vec<ProjectActivity> = Vec::new();
+
+    for (project, scan) in projects.iter().zip(scans) {
+        let Some(mut scan) = scan else {
+            continue;
+        };
+        seen.extend(scan.seen);
+        for (path, activity) in scan.scanned {
+            cache.insert(path, activity);
+            changed = true;
+        }
+        count_denials(telemetry, &project.project_path, &range, &mut scan.days)?;
+
+        let mut project_totals = ActivityCounts::default();
+        for (day, counts) in scan.days.iter().enumerate() {
+            project_totals.add(counts);
+            totals[day].add(counts);
+        }
+        project_totals.files_edited = scan.files_edited;
+        if project_totals.is_empty() {
+            continue;
+        }
+        project_activity.push(ProjectActivity {
+            project_path: project.project_path.clone(),
+            project_name: project.project_name.clone(),
+            totals: project_totals,
+            days: scan.days,
+        });
+    }
+
+    // Save what was read, and forget sessions that no longer exist
+    if let Some(cache_path) = cache_path {
+        let before = cache.len();
+        cache.retain(&seen);
+        if changed || cache.len() != before {
+            if let Err(e) = cache.save(&cache_path) {
+                eprintln!("[dashboard] {}", e);
+            }
+        }
+    }
+
+    project_activity.sort_by(|a, b| {
+        b.totals
+            .active_ms
+            .cmp(&a.totals.active_ms)
+            .then_with(|| b.totals.output_tokens.cmp(&a.totals.output_tokens))
+            .then_with(|| a.project_path.cmp(&b.project_path))
+    });
+
+    Ok(Dashboard {
+        timezone: range.zone.name(),
+        days: range.labels(),
+        totals,
+        projects: project_activity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::jsonl;
+    use serde_json::json;
+
+    fn utc_range(since: &str, until: &str) -> DayRange {
+        DayRange::new(&DashboardQuery {
+            since: Some(since.to_string()),
+            until: Some(until.to_string()),
+            timezone: Some("UTC".to_string()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_day_range_and_timezones() {
+        let range = utc_range("2025-01-01", "2025-01-07");
+        assert_eq!(range.len, 7);
+        assert_eq!(range.labels()[6], "2025-01-07");
+        let jan_2_2330 = DateTime::parse_from_rfc3339("2025-01-02T23:30:00Z")
+            .unwrap()
+            .timestamp();
+        assert_eq!(range.day_index(jan_2_2330), Some(1));
+        assert_eq!(range.day_index(range.end), None);
+
+        // 23:30 UTC is already the next day in India
+        let india = DayRange::new(&DashboardQuery {
+            since: Some("2025-01-01".to_string()),
+            until: Some("2025-01-07".to_string()),
+            timezone: Some("+05:30".to_string()),
+        })
+        .unwrap();
+        assert_eq!(india.zone.name(), "+05:30");
+        assert_eq!(india.day_index(jan_2_2330), Some(2));
+
+        assert!(Zone::parse(Some("Mars/Olympus")).is_err());
+        assert!(DayRange::new(&DashboardQuery {
+            since: Some("2025-01-07".to_string()),
+            until: Some("2025-01-01".to_string()),
+            timezone: None,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_scan_project_uses_cache() {
+        let dir =
+            std::env::temp_dir().join(format!("agent-console-dashboard-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let usage =
+            json!({"input_tokens": 10, "output_tokens": 100, "cache_read_input_tokens": 1000});
+        let lines = [
+            json!({"type": "user", "timestamp": "2025-01-02T10:00:00Z", "message": {"content": "hi"}}),
+            // Two lines of the same response carry the same usage
+            json!({"type": "assistant", "timestamp": "2025-01-02T10:01:00Z",
+                   "message": {"id": "m1", "usage": usage, "content": [{"type": "text", "text": "ok"}]}}),
+            json!({"type": "assistant", "timestamp": "2025-01-02T10:02:00Z",
+                   "message": {"id": "m1", "usage": usage, "content": [{"type": "tool_use", "id": "t1",
+                       "name": "Edit", "input": {"file_path": "/p/a.rs", "old_string": "a", "new_string": "b"}}]}}),
+            json!({"type": "assistant", "timestamp": "2025-01-03T09:00:00Z",
+                   "message": {"id": "m2", "usage": usage, "content": [{"type": "tool_use", "id": "t2",
+                       "name": "Write", "input": {"file_path": "/p/b.rs", "content": "x"}}]}}),
+        ];
+        let mut content = jsonl(&lines).into_bytes();
+        // A line that isn't valid UTF-8 is skipped, not the whole session
+        content.splice(0..0, b"\xff\xfe\n".iter().copied());
+        let session = dir.join("040f5516-2ff1-4738-8190-2b8248f631de.jsonl");
+        fs::write(&session, content).unwrap();
+
+        let range = utc_range("2025-01-01", "2025-01-07");
+        let mut cache = ActivityCache::default();
+        let scan = scan_project(&dir, &range, &cache);
+        assert_eq!(scan.scanned.len(), 1);
+        assert_eq!(scan.days[1].sessions_started, 1);
+        assert_eq!(scan.days[1].active_ms, 2 * 60 * 1000);
+        assert_eq!(scan.days[1].output_tokens, 100);
+        assert_eq!(scan.days[1].cache_read_tokens, 1000);
+        assert_eq!(scan.days[1].files_edited, 1);
+        // The overnight gap isn't active time
+        assert_eq!(scan.days[2].active_ms, 0);
+        assert_eq!(scan.days[2].output_tokens, 100);
+        assert_eq!(scan.files_edited, 2);
+
+        // Same totals as the session's stats
+        let index = crate::session_index::build_session_index(&session, "/p").unwrap();
+        let stats = crate::session_index::get_session_stats(&index);
+        let output_tokens: u64 = scan.days.iter().map(|d| d.output_tokens).sum();
+        assert_eq!(output_tokens, stats.tokens.output_tokens);
+        assert_eq!(scan.days[1].active_ms, stats.active_ms);
+
+        for (path, activity) in scan.scanned {
+            cache.insert(path, activity);
+        }
+        let cached = scan_project(&dir, &range, &cache);
+        assert!(cached.scanned.is_empty());
+        assert_eq!(cached.days, scan.days);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,482 @@
+//! Export a session's edits as a git patch or branch.
+//!
+//! The selected Edit and Write calls are replayed over the files at HEAD in
+//! memory (the working directory is never touched), then diffed with git to
+//! get a patch that `git apply` accepts. Optionally the result is committed
+//! to a new branch, one commit per human prompt.
+
+use git2::build::TreeUpdateBuilder;
+use git2::{Commit, DiffFormat, FileMode, Oid, Repository, Signature, Tree};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::session_index::{get_edit_records, EditRecord, SessionIndex};
+
+/// Commit summaries are cut to this many characters.
+const MAX_SUMMARY_CHARS: usize = 72;
+
+/// Author of the export commits when git has no user configured.
+const FALLBACK_AUTHOR: (&str, &str) = ("Agent Console", "agent-console@localhost");
+
+/// Which edits of a session to export.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum EditSelection {
+    /// Every edit in the session
+    #[default]
+    All,
+    /// The edits made in response to one human prompt
+    #[serde(rename_all = "camelCase")]
+    Prompt { prompt_line: u32 },
+    /// Specific edits, by tool_use id
+    #[serde(rename_all = "camelCase")]
+    Edits { tool_use_ids: Vec<String> },
+}
+
+/// An edit left out of the export.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedEdit {
+    pub tool_use_id: Option<String>,
+    pub path: String,
+    pub reason: String,
+}
+
+/// A commit created on the export branch.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedCommit {
+    pub sha: String,
+    pub summary: String,
+    /// Line of the human prompt the commit's edits answer
+    pub prompt_line: Option<u32>,
+    pub edits: u32,
+}
+
+/// Result of an export.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditExport {
+    /// Unified diff against HEAD (empty if nothing changed)
+    pub patch: String,
+    /// Files changed by the patch, relative to the repository root
+    pub files: Vec<String>,
+    /// Edits included in the patch
+    pub edits: u32,
+    pub skipped: Vec<SkippedEdit>,
+    /// Branch created, if one was requested
+    pub branch: Option<String>,
+    pub commits: Vec<ExportedCommit>,
+}
+
+/// Edits of one human prompt, replayed into a tree.
+struct PromptGroup {
+    prompt_line: Option<u32>,
+    prompt: Option<String>,
+    tree: Oid,
+    edits: u32,
+}
+
+/// Export the selected edits of a session as a patch, and optionally as a
+/// new branch from HEAD with one commit per human prompt.
+pub fn export_session_edits(
+    index: &SessionIndex,
+    session_file: &Path,
+    project_path: &str,
+    session_id: &str,
+    selection: &EditSelection,
+    branch: Option<&str>,
+) -> Result<EditExport, String> {
+    let records = select_records(
+        get_edit_records(index, session_file, project_path)?,
+        selection,
+    );
+
+    let repo = Repository::discover(project_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "Repository has no working directory".to_string())?;
+    let workdir = fs::canonicalize(workdir).unwrap_or_else(|_| workdir.to_path_buf());
+    let project_dir =
+        fs::canonicalize(project_path).unwrap_or_else(|_| PathBuf::from(project_path));
+
+    let head = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let base_tree = match &head {
+        Some(commit) => commit
+            .tree()
+            .map_err(|e| format!("Failed to get HEAD tree: {}", e))?,
+        None => empty_tree(&repo)?,
+    };
+
+    // Replay the edits prompt by prompt, snapshotting a tree after each
+    let mut contents: HashMap<String, Option<String>> = HashMap::new();
+    let mut skipped = Vec::new();
+    let mut groups: Vec<PromptGroup> = Vec::new();
+    let mut tree = base_tree.clone();
+    let mut edits = 0;
+
+    for (prompt_line, group) in group_by_prompt(&records) {
+        let mut group_edits = 0;
+        for record in &group {
+            let Some(path) = repo_path(&workdir, &project_dir, &record.path) else {
+                skipped.push(skip(record, "Outside the repository"));
+                continue;
+            };
+            let content = match contents.entry(path) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let at_head = blob_content(&repo, &base_tree, entry.key())?;
+                    entry.insert(at_head)
+                }
+            };
+            match record.apply(content.as_deref()) {
+                Some(updated) => {
+                    *content = Some(updated);
+                    group_edits += 1;
+                }
+                None => skipped.push(skip(record, "Text to replace not found")),
+            }
+        }
+        if group_edits == 0 {
+            continue;
+        }
+
+        tree = write_tree(&repo, &tree, &contents)?;
+        edits += group_edits;
+        groups.push(PromptGroup {
+            prompt_line,
+            prompt: group[0].prompt.clone(),
+            tree: tree.id(),
+            edits: group_edits,
+        });
+    }
+
+    let (patch, files) = diff_trees(&repo, &base_tree, &tree)?;
+
+    let commits = match branch {
+        Some(name) if !groups.is_empty() => {
+            create_branch(&repo, name, head.as_ref(), &groups, session_id)?
+        }
+        Some(_) => return Err("No edits to commit".to_string()),
+        None => Vec::new(),
+    };
+
+    Ok(EditExport {
+        patch,
+        files,
+        edits,
+        skipped,
+        branch: branch.map(String::from),
+        commits,
+    })
+}
+
+/// Keep the records a selection asks for.
+fn select_records(records: Vec<EditRecord>, selection: &EditSelection) -> Vec<EditRecord> {
+    match selection {
+        EditSelection::All => records,
+        EditSelection::Prompt { prompt_line } => records
+            .into_iter()
+            .filter(|r| r.prompt_line == Some(*prompt_line))
+            .collect(),
+        EditSelection::Edits { tool_use_ids } => records
+            .into_iter()
+            .filter(|r| {
+                r.tool_use_id
+                    .as_ref()
+                    .is_some_and(|id| tool_use_ids.contains(id))
+            })
+            .collect(),
+    }
+}
+
+/// Split records (in session order) into runs sharing a human prompt.
+fn group_by_prompt(records: &[EditRecord]) -> Vec<(Option<u32>, Vec<&EditRecord>)> {
+    let mut groups: Vec<(Option<u32>, Vec<&EditRecord>)> = Vec::new();
+    for record in records {
+        match groups.last_mut() {
+            Some((line, group)) if *line == record.prompt_line => group.push(record),
+            _ => groups.push((record.prompt_line, vec![record])),
+        }
+    }
+    groups
+}
+
+fn skip(record: &EditRecord, reason: &str) -> SkippedEdit {
+    SkippedEdit {
+        tool_use_id: record.tool_use_id.clone(),
+        path: record.path.clone(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Path of an edited file relative to the repository root, if it is inside.
+fn repo_path(workdir: &Path, project_dir: &Path, path: &str) -> Option<String> {
+    // Joining an absolute path (a file outside the project) replaces the base
+    let absolute = project_dir.join(path);
+    let relative = absolute.strip_prefix(workdir).ok()?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+fn empty_tree(repo: &Repository) -> Result<Tree<'_>, String> {
+    let oid = repo
+        .treebuilder(None)
+        .and_then(|builder| builder.write())
+        .map_err(|e| format!("Failed to create empty tree: {}", e))?;
+    repo.find_tree(oid)
+        .map_err(|e| format!("Failed to create empty tree: {}", e))
+}
+
+/// Content of a file in a tree (None if it isn't there).
+fn blob_content(repo: &Repository, tree: &Tree, path: &str) -> Result<Option<String>, String> {
+    let Ok(entry) = tree.get_path(Path::new(path)) else {
+        return Ok(None);
+    };
+    let blob = repo
+        .find_blob(entry.id())
+        .map_err(|e| format!("Failed to read {} at HEAD: {}", path, e))?;
+    Ok(Some(String::from_utf8_lossy(blob.content()).to_string()))
+}
+
+/// Write the replayed contents over a tree, keeping each file's mode.
+fn write_tree<'r>(
+    repo: &'r Repository,
+    tree: &Tree,
+    contents: &HashMap<String, Option<String>>,
+) -> Result<Tree<'r>, String> {
+    let mut builder = TreeUpdateBuilder::new();
+    for (path, content) in contents {
+        let Some(content) = content else {
+            continue;
+        };
+        let blob = repo
+            .blob(content.as_bytes())
+            .map_err(|e| format!("Failed to write blob: {}", e))?;
+        let mode = match tree.get_path(Path::new(path)).map(|e| e.filemode()) {
+            Ok(mode) if mode == i32::from(FileMode::BlobExecutable) => FileMode::BlobExecutable,
+            _ => FileMode::Blob,
+        };
+        builder.upsert(path, blob, mode);
+    }
+    let oid = builder
+        .create_updated(repo, tree)
+        .map_err(|e| format!("Failed to write tree: {}", e))?;
+    repo.find_tree(oid)
+        .map_err(|e| format!("Failed to write tree: {}", e))
+}
+
+/// Unified diff between two trees, with the paths it changes.
+fn diff_trees(repo: &Repository, old: &Tree, new: &Tree) -> Result<(String, Vec<String>), String> {
+    let diff = repo
+        .diff_tree_to_tree(Some(old), Some(new), None)
+        .map_err(|e| format!("Failed to diff trees: {}", e))?;
+
+    let files = diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path())
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .map_err(|e| format!("Failed to format patch: {}", e))?;
+
+    Ok((patch, files))
+}
+
+/// Commit each prompt's tree in turn and point a new branch at the last one.
+fn create_branch(
+    repo: &Repository,
+    name: &str,
+    head: Option<&Commit>,
+    groups: &[PromptGroup],
+    session_id: &str,
+) -> Result<Vec<ExportedCommit>, String> {
+    if repo.find_branch(name, git2::BranchType::Local).is_ok() {
+        return Err(format!("Branch already exists: {}", name));
+    }
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now(FALLBACK_AUTHOR.0, FALLBACK_AUTHOR.1))
+        .map_err(|e| format!("Failed to create signature: {}", e))?;
+
+    let mut parent = head.cloned();
+    let mut commits = Vec::with_capacity(groups.len());
+    for group in groups {
+        let tree = repo
+            .find_tree(group.tree)
+            .map_err(|e| format!("Failed to find tree: {}", e))?;
+        let (summary, message) = commit_message(group.prompt.as_deref(), session_id);
+        let parents: Vec<&Commit> = parent.iter().collect();
+        let oid = repo
+            .commit(None, &signature, &signature, &message, &tree, &parents)
+            .map_err(|e| format!("Failed to create commit: {}", e))?;
+        let commit = repo
+            .find_commit(oid)
+            .map_err(|e| format!("Failed to find commit: {}", e))?;
+
+        commits.push(ExportedCommit {
+            sha: oid.to_string(),
+            summary,
+            prompt_line: group.prompt_line,
+            edits: group.edits,
+        });
+        parent = Some(commit);
+    }
+
+    if let Some(last) = &parent {
+        repo.branch(name, last, false)
+            .map_err(|e| format!("Failed to create branch: {}", e))?;
+    }
+    Ok(commits)
+}
+
+/// Commit summary and full message for a prompt's edits.
+fn commit_message(prompt: Option<&str>, session_id: &str) -> (String, String) {
+    let prompt = prompt.map(str::trim).filter(|p| !p.is_empty());
+    let summary = match prompt {
+        Some(text) if text.chars().count() > MAX_SUMMARY_CHARS => {
+            let cut: String = text.chars().take(MAX_SUMMARY_CHARS - 3).collect();
+            format!("{}...", cut.trim_end())
+        }
+        Some(text) => text.to_string(),
+        None => "Agent edits".to_string(),
+    };
+
+    let mut message = summary.clone();
+    if let Some(text) = prompt.filter(|text| *text != summary) {
+        message.push_str("\n\n");
+        message.push_str(text);
+    }
+    message.push_str(&format!("\n\nSession-Id: {}\n", session_id));
+    (summary, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::write_session;
+    use serde_json::json;
+
+    #[test]
+    fn test_export_patch_and_branch() {
+        let root =
+            std::env::temp_dir().join(format!("agent-console-export-{}", std::process::id()));
+        let project = root.join("repo");
+        fs::create_dir_all(&project).unwrap();
+        let project = fs::canonicalize(&project).unwrap();
+        let project_path = project.to_string_lossy().to_string();
+
+        // A repository with one committed file
+        let repo = Repository::init(&project).unwrap();
+        fs::write(project.join("main.rs"), "fn main() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("main.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        let prompt = |uuid: &str, text: &str| {
+            json!({"type": "user", "userType": "external", "uuid": uuid,
+                   "message": {"content": text}})
+        };
+        let tool_use = |id: &str, name: &str, input: serde_json::Value| {
+            json!({"type": "assistant", "uuid": id, "message": {"content": [
+                {"type": "tool_use", "id": id, "name": name, "input": input}]}})
+        };
+        let lines = [
+            prompt("p1", "Say hello"),
+            tool_use(
+                "e1",
+                "Edit",
+                json!({"file_path": project.join("main.rs"),
+                       "old_string": "fn main() {}", "new_string": "fn main() { hello(); }"}),
+            ),
+            prompt("p2", "Add the hello function"),
+            tool_use(
+                "w1",
+                "Write",
+                json!({"file_path": project.join("hello.rs"),
+                       "content": "fn hello() {}\nfn call() { hello(); }\n"}),
+            ),
+            tool_use(
+                "e3",
+                "Edit",
+                json!({"file_path": project.join("hello.rs"),
+                       "old_string": "hello", "new_string": "greet", "replace_all": true}),
+            ),
+            tool_use(
+                "e2",
+                "Edit",
+                json!({"file_path": project.join("main.rs"),
+                       "old_string": "not there", "new_string": "x"}),
+            ),
+        ];
+        let fixture = write_session("export", &lines);
+        let session_file = fixture.path();
+        let session = fixture.index(&project_path);
+
+        let export = export_session_edits(
+            &session,
+            session_file,
+            &project_path,
+            "session-1",
+            &EditSelection::All,
+            Some("agent/session-1"),
+        )
+        .unwrap();
+
+        assert_eq!(export.edits, 3);
+        assert_eq!(export.files, ["hello.rs", "main.rs"]);
+        assert!(export.patch.contains("new file mode"));
+        assert!(export.patch.contains("+fn main() { hello(); }"));
+        // replace_all renamed every occurrence
+        assert!(export
+            .patch
+            .contains("+fn greet() {}\n+fn call() { greet(); }"));
+        assert_eq!(export.skipped.len(), 1);
+        assert_eq!(export.skipped[0].tool_use_id.as_deref(), Some("e2"));
+
+        // One commit per prompt, on a branch; the working directory is untouched
+        assert_eq!(export.commits.len(), 2);
+        assert_eq!(export.commits[0].summary, "Say hello");
+        let branch = repo
+            .find_branch("agent/session-1", git2::BranchType::Local)
+            .unwrap();
+        let tip = branch.get().peel_to_commit().unwrap();
+        assert_eq!(tip.id().to_string(), export.commits[1].sha);
+        assert_eq!(
+            tip.message(),
+            Some("Add the hello function\n\nSession-Id: session-1\n")
+        );
+        assert_eq!(
+            fs::read_to_string(project.join("main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+
+        // Exporting one prompt leaves the other's edits out
+        let export = export_session_edits(
+            &session,
+            session_file,
+            &project_path,
+            "session-1",
+            &EditSelection::Prompt { prompt_line: 0 },
+            None,
+        )
+        .unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(export.files, ["main.rs"]);
+        assert!(export.commits.is_empty());
+    }
+}
@@ -0,0 +1,517 @@
+//! Project-wide file edit history.
+//!
+//! `get_file_diffs` and the session index only see one session. This module
+//! collects the Edit and Write calls of every session and sub-agent of a
+//! project, so a file's full history of agent edits can be shown and files
+//! can be ranked by churn.
+//!
+//! Indexing every session on each request would be slow, so a per-project
+//! cache remembers each session file's edits (without their content) keyed
+//! by size and mtime. Content is only read back for the sessions that
+//! touched the file being looked at.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::claude_code::{get_project_dir, map_in_parallel};
+use crate::project_cache::mtime_key;
+use crate::session_index::{build_session_index, get_edit_records, EditRecord};
+
+/// Bump when the cached shape changes so stale caches are discarded.
+/// Version 2 counts the edits of MultiEdit calls.
+const CACHE_VERSION: u32 = 2;
+
+/// Hot files returned when no limit is given.
+const DEFAULT_HOT_FILES: usize = 50;
+
+/// How many lines of a sub-agent file to search for its session ID.
+const SESSION_ID_SEARCH_LINES: usize = 10;
+
+/// An edit in the project-wide history of a file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub session_id: String,
+    /// Sub-agent that made the edit (None for the main session)
+    pub agent_id: Option<String>,
+    #[serde(flatten)]
+    pub edit: EditRecord,
+}
+
+/// Every agent edit of a file across a project.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEditHistory {
+    /// Path relative to the project
+    pub path: String,
+    /// Edits in timestamp order
+    pub edits: Vec<FileHistoryEntry>,
+    /// Sessions that edited the file
+    pub sessions: u32,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+}
+
+/// A file ranked by agent churn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotFile {
+    /// Path relative to the project
+    pub path: String,
+    pub edits: u32,
+    /// Sessions that edited the file (sub-agents count toward their session)
+    pub sessions: u32,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    /// Lines added plus lines removed
+    pub churn: u64,
+    pub last_edited_at: Option<String>,
+}
+
+/// An edit as remembered in the cache.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedEdit {
+    path: String,
+    timestamp: Option<String>,
+    lines_added: u64,
+    lines_removed: u64,
+}
+
+/// The edits of one session file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedSession {
+    /// File size when scanned
+    size: u64,
+    /// File mtime (nanoseconds since the epoch) when scanned
+    modified: u64,
+    /// Session the file belongs to (its parent session for sub-agents)
+    session_id: String,
+    agent_id: Option<String>,
+    edits: Vec<CachedEdit>,
+}
+
+/// Session edits of one project keyed by session file name.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EditHistoryCache {
+    version: u32,
+    sessions: HashMap<String, CachedSession>,
+}
+
+/// Default location of a project's cache file.
+fn default_cache_path(project_dir: &Path) -> Option<PathBuf> {
+    let dir_name = project_dir.file_name()?.to_string_lossy().to_string();
+    dirs::cache_dir().map(|d| {
+        d.join("io.eqtylab.agent-console")
+            .join("edit_history")
+            .join(format!("{}.json", dir_name))
+    })
+}
+
+impl EditHistoryCache {
+    /// Load the cache, returning an empty one if it is missing, unreadable,
+    /// or from an older version.
+    fn load(path: &Path) -> Self {
+        let cache = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<EditHistoryCache>(&bytes).ok());
+        match cache {
+            Some(cache) if cache.version == CACHE_VERSION => cache,
+            _ => Self::default(),
+        }
+    }
+
+    /// Write the cache atomically (temp file + rename).
+    fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        }
+        let json = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize edit history cache: {}", e))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write edit history cache: {}", e))?;
+        fs::rename(&tmp_path, path)
+            .map_err(|e| format!("Failed to write edit history cache: {}", e))
+    }
+}
+
+/// Rank the files of a project by agent churn, most churned first.
+pub fn get_hot_files(project_path: &str, limit: Option<usize>) -> Result<Vec<HotFile>, String> {
+    let project_dir = get_project_dir(project_path)
+        .ok_or_else(|| format!("Project not found: {}", project_path))?;
+    let sessions = load_sessions(&project_dir, project_path, default_cache_path(&project_dir));
+    Ok(hot_files(&sessions, limit.unwrap_or(DEFAULT_HOT_FILES)))
+}
+
+/// Get every agent edit of a file across the sessions of a project.
+///
+/// `file_path` can be absolute or relative to the project.
+pub fn get_file_edit_history(
+    project_path: &str,
+    file_path: &str,
+) -> Result<FileEditHistory, String> {
+    let project_dir = get_project_dir(project_path)
+        .ok_or_else(|| format!("Project not found: {}", project_path))?;
+    let sessions = load_sessions(&project_dir, project_path, default_cache_path(&project_dir));
+    let path = Path::new(file_path)
+        .strip_prefix(project_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file_path.to_string());
+    Ok(file_history(&project_dir, project_path, &sessions, &path))
+}
+
+/// Read the edits of every session file of a project, from the cache or by
+/// indexing the files that changed since they were cached.
+fn load_sessions(
+    project_dir: &Path,
+    project_path: &str,
+    cache_path: Option<PathBuf>,
+) -> Vec<(String, CachedSession)> {
+    let mut cache = cache_path
+        .as_deref()
+        .map(EditHistoryCache::load)
+        .unwrap_or_default();
+
+    let files: Vec<(String, u64, u64)> = fs::read_dir(project_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".jsonl") {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().map(mtime_key).ok()?;
+            Some((name, metadata.len(), modified))
+        })
+        .collect();
+
+    let stale: Vec<&(String, u64, u64)> = files
+        .iter()
+        .filter(|(name, size, modified)| {
+            !cache
+                .sessions
+                .get(name)
+                .is_some_and(|s| s.size == *size && s.modified == *modified)
+        })
+        .collect();
+    let scanned = map_in_parallel(&stale, |(name, size, modified)| {
+        let edits = scan_session(&project_dir.join(name), project_path).ok()?;
+        let (session_id, agent_id) = session_ids(&project_dir.join(name), name)?;
+        Some((
+            name.clone(),
+            CachedSession {
+                size: *size,
+                modified: *modified,
+                session_id,
+                agent_id,
+                edits,
+            },
+        ))
+    });
+
+    let changed = !scanned.is_empty() || cache.sessions.len() != files.len();
+    for (name, session) in scanned.into_iter().flatten() {
+        cache.version = CACHE_VERSION;
+        cache.sessions.insert(name, session);
+    }
+    let names: HashSet<&String> = files.iter().map(|(name, _, _)| name).collect();
+    cache.sessions.retain(|name, _| names.contains(name));
+
+    if changed {
+        if let Some(path) = &cache_path {
+            if let Err(e) = cache.save(path) {
+                eprintln!("[file_history] {}", e);
+            }
+        }
+    }
+
+    cache.sessions.into_iter().collect()
+}
+
+/// Index a session file and summarize its edits.
+fn scan_session(session_file: &Path, project_path: &str) -> Result<Vec<CachedEdit>, String> {
+    let index = build_session_index(session_file, project_path)?;
+    Ok(get_edit_records(&index, session_file, project_path)?
+        .into_iter()
+        .map(|record| CachedEdit {
+            path: record.path,
+            timestamp: record.timestamp,
+            lines_added: record.lines_added,
+            lines_removed: record.lines_removed,
+        })
+        .collect())
+}
+
+/// Session and sub-agent IDs of a session file.
+///
+/// Sub-agent files (`agent-<id>.jsonl`) record their parent session in each
+/// entry's `sessionId`.
+fn session_ids(session_file: &Path, file_name: &str) -> Option<(String, Option<String>)> {
+    let stem = file_name.strip_suffix(".jsonl")?;
+    let Some(agent_id) = stem.strip_prefix("agent-") else {
+        return Some((stem.to_string(), None));
+    };
+
+    let file = File::open(session_file).ok()?;
+    let session_id = BufReader::new(file)
+        .lines()
+        .take(SESSION_ID_SEARCH_LINES)
+        .map_while(Result::ok)
+        .find_map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(&line).ok()?;
+            entry.get("sessionId")?.as_str().map(String::from)
+        })
+        .unwrap_or_default();
+    Some((session_id, Some(agent_id.to_string())))
+}
+
+/// Aggregate cached edits per file and rank by churn.
+fn hot_files(sessions: &[(String, CachedSession)], limit: usize) -> Vec<HotFile> {
+    let mut files: HashMap<&str, (HotFile, HashSet<&str>)> = HashMap::new();
+
+    for (_, session) in sessions {
+        for edit in &session.edits {
+            let (file, file_sessions) = files.entry(&edit.path).or_insert_with(|| {
+                (
+                    HotFile {
+                        path: edit.path.clone(),
+                        edits: 0,
+                        sessions: 0,
+                        lines_added: 0,
+                        lines_removed: 0,
+                        churn: 0,
+                        last_edited_at: None,
+                    },
+                    HashSet::new(),
+                )
+            });
+            file.edits += 1;
+            file.lines_added += edit.lines_added;
+            file.lines_removed += edit.lines_removed;
+            // ISO 8601 timestamps compare correctly as strings
+            if edit.timestamp > file.last_edited_at {
+                file.last_edited_at = edit.timestamp.clone();
+            }
+            file_sessions.insert(&session.session_id);
+        }
+    }
+
+    let mut ranked: Vec<HotFile> = files
+        .into_values()
+        .map(|(mut file, file_sessions)| {
+            file.sessions = file_sessions.len() as u32;
+            file.churn = file.lines_added + file.lines_removed;
+            file
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        (Reverse(a.churn), Reverse(a.edits), &a.path).cmp(&(
+            Reverse(b.churn),
+            Reverse(b.edits),
+            &b.path,
+        ))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+/// Read back the edits of a file from the sessions that touched it.
+///
+/// Sessions that can no longer be read (e.g. deleted since they were
+/// cached) are skipped.
+fn file_history(
+    project_dir: &Path,
+    project_path: &str,
+    sessions: &[(String, CachedSession)],
+    path: &str,
+) -> FileEditHistory {
+    let touching: Vec<&(String, CachedSession)> = sessions
+        .iter()
+        .filter(|(_, session)| session.edits.iter().any(|e| e.path == path))
+        .collect();
+
+    let mut edits = Vec::new();
+    for (name, session) in touching {
+        let session_file = project_dir.join(name);
+        let Ok(index) = build_session_index(&session_file, project_path) else {
+            continue;
+        };
+        let Ok(records) = get_edit_records(&index, &session_file, project_path) else {
+            continue;
+        };
+        for edit in records {
+            if edit.path == path {
+                edits.push(FileHistoryEntry {
+                    session_id: session.session_id.clone(),
+                    agent_id: session.agent_id.clone(),
+                    edit,
+                });
+            }
+        }
+    }
+    edits.sort_by(|a, b| {
+        (&a.edit.timestamp, &a.session_id, &a.agent_id, a.edit.line).cmp(&(
+            &b.edit.timestamp,
+            &b.session_id,
+            &b.agent_id,
+            b.edit.line,
+        ))
+    });
+
+    let session_ids: HashSet<&str> = edits.iter().map(|e| e.session_id.as_str()).collect();
+    FileEditHistory {
+        path: path.to_string(),
+        sessions: session_ids.len() as u32,
+        lines_added: edits.iter().map(|e| e.edit.lines_added).sum(),
+        lines_removed: edits.iter().map(|e| e.edit.lines_removed).sum(),
+        edits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit_line(session_id: &str, uuid: &str, timestamp: &str, old: &str, new: &str) -> String {
+        let line = json!({"type": "assistant", "uuid": uuid, "sessionId": session_id,
+            "timestamp": timestamp,
+            "message": {"content": [{"type": "tool_use", "id": format!("t-{}", uuid), "name": "Edit",
+                "input": {"file_path": "/proj/src/lib.rs", "old_string": old, "new_string": new}}]}});
+        format!("{}\n", line)
+    }
+
+    #[test]
+    fn test_history_across_sessions_and_agents() {
+        let root =
+            std::env::temp_dir().join(format!("agent-console-file-history-{}", std::process::id()));
+        let project_dir = root.join("-proj");
+        fs::create_dir_all(&project_dir).unwrap();
+        let cache_path = root.join("cache.json");
+
+        let prompt = json!({"type": "user", "userType": "external", "uuid": "p1",
+            "timestamp": "2025-01-02T10:00:00Z", "message": {"content": "Fix the bug"}});
+        fs::write(
+            project_dir.join("s2.jsonl"),
+            format!(
+                "{}\n{}",
+                prompt,
+                edit_line("s2", "e2", "2025-01-02T10:00:01Z", "a", "b\nc")
+            ),
+        )
+        .unwrap();
+        fs::write(
+            project_dir.join("s1.jsonl"),
+            edit_line("s1", "e1", "2025-01-01T10:00:00Z", "", "a"),
+        )
+        .unwrap();
+        fs::write(
+            project_dir.join("agent-x1.jsonl"),
+            edit_line("s2", "e3", "2025-01-02T11:00:00Z", "c", "d"),
+        )
+        .unwrap();
+
+        let sessions = load_sessions(&project_dir, "/proj", Some(cache_path.clone()));
+        assert_eq!(sessions.len(), 3);
+        // Each session's edits were cached, sub-agents under their parent session
+        let cached = EditHistoryCache::load(&cache_path);
+        assert_eq!(cached.sessions.len(), 3);
+        assert_eq!(cached.sessions["agent-x1.jsonl"].session_id, "s2");
+
+        let hot = hot_files(&sessions, 10);
+        assert_eq!(
+            hot,
+            [HotFile {
+                path: "src/lib.rs".to_string(),
+                edits: 3,
+                sessions: 2,
+                lines_added: 4,
+                lines_removed: 2,
+                churn: 6,
+                last_edited_at: Some("2025-01-02T11:00:00Z".to_string()),
+            }]
+        );
+
+        let history = file_history(&project_dir, "/proj", &sessions, "src/lib.rs");
+        let order: Vec<(&str, Option<&str>)> = history
+            .edits
+            .iter()
+            .map(|e| (e.session_id.as_str(), e.agent_id.as_deref()))
+            .collect();
+        assert_eq!(order, [("s1", None), ("s2", None), ("s2", Some("x1"))]);
+        assert_eq!(history.sessions, 2);
+        assert_eq!(history.edits[1].edit.prompt.as_deref(), Some("Fix the bug"));
+        assert_eq!(history.edits[1].edit.new_string, "b\nc");
+
+        // A session deleted after it was cached is skipped, not an error
+        fs::remove_file(project_dir.join("s1.jsonl")).unwrap();
+        let history = file_history(&project_dir, "/proj", &sessions, "src/lib.rs");
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(history.edits.len(), 2);
+        assert_eq!(history.sessions, 1);
+    }
+
+    #[test]
+    fn test_multi_edit_counts_each_edit() {
+        let root = std::env::temp_dir().join(format!(
+            "agent-console-file-history-multi-{}",
+            std::process::id()
+        ));
+        let project_dir = root.join("-proj");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        let multi_edit = json!({"type": "assistant", "uuid": "m1", "sessionId": "s1",
+        "timestamp": "2025-01-01T10:00:00Z",
+        "message": {"content": [{"type": "tool_use", "id": "t-m1", "name": "MultiEdit",
+            "input": {"file_path": "/proj/src/lib.rs", "edits": [
+                {"old_string": "a", "new_string": "b\nc"},
+                {"old_string": "x", "new_string": "y", "replace_all": true},
+            ]}}]}});
+        fs::write(
+            project_dir.join("s1.jsonl"),
+            format!(
+                "{}\n{}",
+                multi_edit,
+                edit_line("s1", "e1", "2025-01-01T10:00:01Z", "y", "z")
+            ),
+        )
+        .unwrap();
+
+        let sessions = load_sessions(&project_dir, "/proj", None);
+        let hot = hot_files(&sessions, 10);
+        assert_eq!((hot[0].edits, hot[0].sessions), (3, 1));
+        assert_eq!((hot[0].lines_added, hot[0].lines_removed), (4, 3));
+
+        let history = file_history(&project_dir, "/proj", &sessions, "src/lib.rs");
+        fs::remove_dir_all(&root).unwrap();
+        let edits: Vec<(&str, &str, bool)> = history
+            .edits
+            .iter()
+            .map(|e| {
+                (
+                    e.edit.tool_name.as_str(),
+                    e.edit.old_string.as_str(),
+                    e.edit.replace_all,
+                )
+            })
+            .collect();
+        assert_eq!(
+            edits,
+            [
+                ("MultiEdit", "a", false),
+                ("MultiEdit", "x", true),
+                ("Edit", "y", false)
+            ]
+        );
+    }
+}
@@ -1,17 +1,36 @@
 mod claude_code;
+mod dashboard;
+mod edit_export;
+mod file_history;
 mod git;
+mod path_search;
+mod policy;
 mod process;
+mod project_cache;
+mod pty;
 mod search;
 mod session_index;
 mod terminal;
 mod watcher;
 
 use claude_code::{FileDiff, FileEdit, PolicyEvaluation, Project, Session};
+use dashboard::{Dashboard, DashboardQuery};
+use edit_export::{EditExport, EditSelection};
+use file_history::{FileEditHistory, HotFile};
 use git::GitFileDiff;
-use session_index::{get_edit_context, EditContext, IndexStatus};
-use std::path::Path;
+use policy::{
+    AnalyticsFilter, CoverageReport, EvaluationPage, EvaluationQuery, LoadedSpan, PolicyAnalytics,
+    PolicyEventLink, ReplayReport,
+};
+use pty::{PtyInfo, PtySize, PtyState};
+use session_index::{
+    get_edit_context, BashHistory, ContextWindow, ConversationGraph, EditContext, FileHunks,
+    IndexStatus, SessionComparison, SessionSide, SessionStats, ToolCallPage, ToolCallQuery,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tauri::{AppHandle, State};
-use terminal::TerminalType;
+use terminal::{LaunchOptions, TerminalType};
 use watcher::WatcherState;
 
 /// Discover all Claude Code projects (lightweight - no session content parsing).
@@ -20,6 +39,51 @@ fn get_projects() -> Vec<Project> {
     claude_code::discover_projects()
 }
 
+/// Aggregate activity across all projects per day (sessions, active time,
+/// tokens, files edited, commits and policy denials) for the dashboard.
+#[tauri::command]
+async fn get_activity_dashboard(
+    state: State<'_, WatcherState>,
+    query: Option<DashboardQuery>,
+) -> Result<Dashboard, String> {
+    let telemetry = state.telemetry_arc();
+
+    // Scanning every project (sessions, git history, telemetry) blocks; keep
+    // it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let projects = claude_code::discover_projects();
+        dashboard::compute_dashboard(&projects, &telemetry, &query.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Failed to compute dashboard: {}", e))?
+}
+
+/// Rank a project's files by agent churn (lines added and removed across
+/// every session and sub-agent), most churned first.
+#[tauri::command]
+async fn get_hot_files(project_path: String, limit: Option<usize>) -> Result<Vec<HotFile>, String> {
+    // Indexing the project's changed sessions blocks; keep it off the async
+    // runtime
+    tauri::async_runtime::spawn_blocking(move || file_history::get_hot_files(&project_path, limit))
+        .await
+        .map_err(|e| format!("Failed to rank hot files: {}", e))?
+}
+
+/// Get every agent edit of a file across all sessions and sub-agents of a
+/// project, in timestamp order with the prompts that triggered them.
+#[tauri::command]
+async fn get_file_edit_history(
+    project_path: String,
+    file_path: String,
+) -> Result<FileEditHistory, String> {
+    // Indexing the project's sessions blocks; keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        file_history::get_file_edit_history(&project_path, &file_path)
+    })
+    .await
+    .map_err(|e| format!("Failed to get file edit history: {}", e))?
+}
+
 /// Get full session details for a specific project (on-demand).
 #[tauri::command]
 fn get_project_sessions(project_path: String) -> Vec<Session> {
@@ -39,24 +103,67 @@ fn get_available_terminals() -> Vec<TerminalType> {
 }
 
 /// Launch Claude Code in a terminal.
+///
+/// `custom_command` is the command template of the custom terminal.
 #[tauri::command]
 fn launch_claude(
     terminal_type: TerminalType,
     project_path: String,
-    continue_session: bool,
-    yolo_mode: bool,
+    options: LaunchOptions,
+    custom_command: Option<String>,
 ) -> Result<(), String> {
-    let mut cmd = String::from("claude");
+    let cmd = terminal::claude_command(&options)?;
+    terminal::launch_terminal(
+        &terminal_type,
+        &project_path,
+        &cmd,
+        custom_command.as_deref(),
+    )
+}
 
-    if continue_session {
-        cmd.push_str(" --continue");
-    }
+/// Start Claude Code in an embedded terminal.
+///
+/// Output arrives as "pty-output" events, the session it starts as
+/// "pty-session" and its exit as "pty-exit".
+#[tauri::command]
+fn spawn_terminal(
+    app_handle: AppHandle,
+    state: State<'_, PtyState>,
+    project_path: String,
+    options: LaunchOptions,
+    cols: u16,
+    rows: u16,
+) -> Result<PtyInfo, String> {
+    state.spawn(app_handle, &project_path, &options, PtySize { cols, rows })
+}
 
-    if yolo_mode {
-        cmd.push_str(" --dangerously-skip-permissions");
-    }
+/// Send keyboard input to an embedded terminal.
+#[tauri::command]
+fn write_terminal(state: State<'_, PtyState>, id: u32, data: String) -> Result<(), String> {
+    state.write(id, &data)
+}
+
+/// Resize an embedded terminal.
+#[tauri::command]
+fn resize_terminal(
+    state: State<'_, PtyState>,
+    id: u32,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
+    state.resize(id, PtySize { cols, rows })
+}
 
-    terminal::launch_terminal(&terminal_type, &project_path, &cmd)
+/// Close an embedded terminal, hanging up its process if still running.
+#[tauri::command]
+fn close_terminal(state: State<'_, PtyState>, id: u32) -> Result<(), String> {
+    state.close(id)
+}
+
+/// List open embedded terminals.
+#[tauri::command]
+fn list_terminals(state: State<'_, PtyState>) -> Vec<PtyInfo> {
+    state.list()
 }
 
 /// Get file edits for a session (lightweight - just file list and types).
@@ -197,7 +304,7 @@ fn unwatch_subagent(
     watcher::unwatch_subagent(&state, &project_path, &agent_id)
 }
 
-/// Start watching a project's telemetry directory for changes.
+/// Start watching a project's telemetry locations for changes.
 #[tauri::command]
 fn watch_telemetry(
     app_handle: AppHandle,
@@ -207,7 +314,7 @@ fn watch_telemetry(
     watcher::watch_telemetry(app_handle, &state, project_path)
 }
 
-/// Stop watching a project's telemetry directory.
+/// Stop watching a project's telemetry locations.
 #[tauri::command]
 fn unwatch_telemetry(state: State<'_, WatcherState>, project_path: String) -> Result<(), String> {
     watcher::unwatch_telemetry(&state, &project_path)
@@ -234,13 +341,14 @@ fn get_indexed_file_edits(
 ) -> Vec<FileEdit> {
     // Try to get from cached index first
     if let Some(index) = state.get_index(&project_path, &session_id) {
-        return index.file_edits;
+        return index.file_edits.clone();
     }
     // Fallback to scanning (shouldn't happen if index is ready)
     claude_code::get_session_file_edits(&project_path, &session_id)
 }
 
 /// Get paginated events using cached line offsets (O(k) seeks instead of O(n) scan).
+/// If `branch_leaf` is given, only events on the branch ending at that UUID are paged.
 /// Falls back to scanning if index not available.
 #[tauri::command]
 fn get_indexed_events(
@@ -249,6 +357,7 @@ fn get_indexed_events(
     session_id: String,
     offset: Option<u32>,
     limit: Option<u32>,
+    branch_leaf: Option<String>,
 ) -> claude_code::SessionEventsResponse {
     // Try to get from cached index first
     if let Some(index) = state.get_index(&project_path, &session_id) {
@@ -258,12 +367,195 @@ fn get_indexed_events(
             &index,
             offset,
             limit,
+            branch_leaf.as_deref(),
         );
     }
     // Fallback to scanning (shouldn't happen if index is ready)
     claude_code::get_session_events(&project_path, &session_id, offset, limit)
 }
 
+/// Get the conversation DAG for a session: branch points, the active leaf,
+/// and abandoned branches. Requires the session index to be ready.
+#[tauri::command]
+fn get_conversation_graph(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+) -> Result<ConversationGraph, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+    Ok(session_index::get_conversation_graph(&index))
+}
+
+/// Page through a session's tool calls (each tool_use paired with its
+/// tool_result), with per-tool aggregates. Requires the session index to be ready.
+#[tauri::command]
+fn get_tool_calls(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+    query: ToolCallQuery,
+) -> Result<ToolCallPage, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+    Ok(session_index::query_tool_calls(&index, &query))
+}
+
+/// Get a summary of a session (duration, active time, turns, tool usage,
+/// compactions, errors and lines changed), from counters kept by the index.
+#[tauri::command]
+fn get_session_stats(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+) -> Result<SessionStats, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+    Ok(session_index::get_session_stats(&index))
+}
+
+/// Reconstruct the context window the model had at an event: the latest
+/// compact summary plus the events since its boundary, with token estimates,
+/// and every compaction in the session.
+#[tauri::command]
+fn get_context_window(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+    sequence: u32,
+) -> Result<ContextWindow, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+    let session_file = claude_code::get_session_file_path(&project_path, &session_id)
+        .ok_or_else(|| format!("Session file not found: {}", session_id))?;
+    session_index::get_context_window(&index, &session_file, sequence)
+}
+
+/// Compare two sessions side by side: prompts aligned by text with the tools
+/// called for each, tool counts, final file contents, and stats (tokens,
+/// duration). Sessions that aren't open are indexed on demand.
+#[tauri::command]
+async fn compare_sessions(
+    state: State<'_, WatcherState>,
+    left_project_path: String,
+    left_session_id: String,
+    right_project_path: String,
+    right_session_id: String,
+) -> Result<SessionComparison, String> {
+    let left_cached = state.get_index(&left_project_path, &left_session_id);
+    let right_cached = state.get_index(&right_project_path, &right_session_id);
+
+    // Indexing and replaying edits over git content block; keep them off the
+    // async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let load = |project_path: &str, session_id: &str, cached: Option<Arc<_>>| {
+            let session_file = claude_code::get_session_file_path(project_path, session_id)
+                .ok_or_else(|| format!("Session file not found: {}", session_id))?;
+            let index = match cached {
+                Some(index) => index,
+                None => Arc::new(session_index::build_session_index(
+                    &session_file,
+                    project_path,
+                )?),
+            };
+            Ok::<_, String>((index, session_file))
+        };
+        let (left_index, left_file) = load(&left_project_path, &left_session_id, left_cached)?;
+        let (right_index, right_file) = load(&right_project_path, &right_session_id, right_cached)?;
+
+        session_index::compare_sessions(
+            &SessionSide {
+                index: &left_index,
+                session_file: &left_file,
+                project_path: &left_project_path,
+                session_id: &left_session_id,
+            },
+            &SessionSide {
+                index: &right_index,
+                session_file: &right_file,
+                project_path: &right_project_path,
+                session_id: &right_session_id,
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to compare sessions: {}", e))?
+}
+
+/// Get line-level diff hunks for every edit to a file in a session, placed
+/// at line numbers of the reconstructed file where the edits can be located.
+#[tauri::command]
+fn get_file_hunks(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+    file_path: String,
+) -> Result<FileHunks, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+    let session_file = claude_code::get_session_file_path(&project_path, &session_id)
+        .ok_or_else(|| format!("Session file not found: {}", session_id))?;
+    session_index::get_file_hunks(&index, &session_file, &project_path, &file_path)
+}
+
+/// Export a session's edits (all of them, one prompt's, or chosen ones) as a
+/// unified-diff patch against HEAD. With `branch`, also commit them to a new
+/// branch, one commit per human prompt; the working directory is untouched.
+#[tauri::command]
+async fn export_session_edits(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+    selection: Option<EditSelection>,
+    branch: Option<String>,
+) -> Result<EditExport, String> {
+    let cached = state.get_index(&project_path, &session_id);
+
+    // git2 work blocks; keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let session_file = claude_code::get_session_file_path(&project_path, &session_id)
+            .ok_or_else(|| format!("Session file not found: {}", session_id))?;
+        let index = match cached {
+            Some(index) => index,
+            None => Arc::new(session_index::build_session_index(
+                &session_file,
+                &project_path,
+            )?),
+        };
+        edit_export::export_session_edits(
+            &index,
+            &session_file,
+            &project_path,
+            &session_id,
+            &selection.unwrap_or_default(),
+            branch.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to export session edits: {}", e))?
+}
+
+/// Get the Bash commands run in a session, aggregated by executable and
+/// checked against the dangerous-command rules (built-in plus `bash_rules.json`).
+#[tauri::command]
+fn get_bash_history(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+) -> Result<BashHistory, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+    let session_file = claude_code::get_session_file_path(&project_path, &session_id)
+        .ok_or_else(|| format!("Session file not found: {}", session_id))?;
+    session_index::get_bash_history(&index, &session_file, &session_index::load_bash_rules())
+}
+
 /// Get the context for a file edit - the chain of events from the human message to the edit.
 /// Uses the cached session index to walk the parent chain efficiently.
 ///
@@ -311,14 +603,212 @@ fn get_file_edit_context(
 
 /// Get list of policy evaluations for a project.
 #[tauri::command]
-fn get_policy_evaluations(project_path: String) -> Vec<PolicyEvaluation> {
-    claude_code::get_policy_evaluations(&project_path)
+fn get_policy_evaluations(
+    state: State<'_, WatcherState>,
+    project_path: String,
+) -> Result<Vec<PolicyEvaluation>, String> {
+    state.telemetry().evaluations(&project_path)
+}
+
+/// Get a filtered, sorted page of policy evaluations from the telemetry index.
+#[tauri::command]
+async fn query_policy_evaluations(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    query: Option<EvaluationQuery>,
+) -> Result<EvaluationPage, String> {
+    let query = query.unwrap_or_default();
+    let telemetry = state.telemetry_arc();
+
+    // Refreshing the index parses new telemetry; keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        telemetry.with_index(&project_path, |index| index.query(&query))
+    })
+    .await
+    .map_err(|e| format!("Failed to query policy evaluations: {}", e))?
+}
+
+/// Get the typed CupcakeSpan for a specific policy evaluation, with the raw
+/// JSON it was read from.
+///
+/// `path` is the evaluation's file; without it the filename is looked up in
+/// the project's own telemetry directory.
+#[tauri::command]
+fn get_policy_evaluation(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    filename: String,
+    path: Option<String>,
+) -> Result<Option<LoadedSpan>, String> {
+    state.telemetry().with_index(&project_path, |index| {
+        index
+            .find(&filename, path.as_deref())
+            .map(policy::load_span_with_raw)
+            .transpose()
+            .map(Option::flatten)
+    })?
 }
 
-/// Get raw JSON for a specific policy evaluation.
+/// Get aggregate statistics over a project's policy evaluations: decision
+/// counts, latency percentiles, phase timings and trends.
 #[tauri::command]
-fn get_policy_evaluation(project_path: String, filename: String) -> Option<String> {
-    claude_code::get_policy_evaluation(&project_path, &filename)
+async fn get_policy_analytics(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    filter: Option<AnalyticsFilter>,
+) -> Result<PolicyAnalytics, String> {
+    let filter = filter.unwrap_or_default();
+    let telemetry = state.telemetry_arc();
+
+    // Refreshing the index parses new telemetry; keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        telemetry.with_index(&project_path, |index| {
+            policy::compute_policy_analytics(index.summaries(), &filter)
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to compute policy analytics: {}", e))?
+}
+
+/// Get the policy coverage of a project's sessions: which tools, hook events
+/// and Bash commands ran without any policy evaluating or matching them, and
+/// which sessions ran without telemetry.
+#[tauri::command]
+async fn get_policy_coverage(
+    state: State<'_, WatcherState>,
+    project_path: String,
+) -> Result<CoverageReport, String> {
+    let telemetry = state.telemetry_arc();
+
+    // Scanning every session and refreshing telemetry block; keep them off
+    // the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let session_files: Vec<(String, PathBuf)> =
+            claude_code::get_sessions_for_project(&project_path)
+                .into_iter()
+                .filter_map(|s| {
+                    let path = claude_code::get_session_file_path(&project_path, &s.id)?;
+                    Some((s.id, path))
+                })
+                .collect();
+
+        // Only sessions that can no longer be opened are left out
+        let sessions: Vec<_> = claude_code::map_in_parallel(&session_files, |(id, path)| {
+            policy::scan_session(path)
+                .ok()
+                .map(|activity| (id.clone(), activity))
+        })
+        .into_iter()
+        .flatten()
+        .collect();
+
+        telemetry.with_index(&project_path, |index| {
+            policy::compute_coverage(&sessions, index.summaries())
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to compute policy coverage: {}", e))?
+}
+
+/// Re-run recorded policy evaluations through the local `cupcake` binary with
+/// the current policies and report which decisions changed.
+#[tauri::command]
+async fn replay_policy_evaluations(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    query: Option<EvaluationQuery>,
+) -> Result<ReplayReport, String> {
+    let cupcake = policy::find_cupcake().ok_or_else(|| "cupcake not found on PATH".to_string())?;
+    let query = query.unwrap_or_default();
+    let telemetry = state.telemetry_arc();
+
+    // Each replay runs cupcake to completion (or its timeout); keep them off
+    // the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let evaluations =
+            telemetry.with_index(&project_path, |index| index.query(&query).evaluations)?;
+        let events = policy::events_from_evaluations(&evaluations);
+        Ok(policy::replay_events(&cupcake, &project_path, events))
+    })
+    .await
+    .map_err(|e| format!("Failed to replay policy evaluations: {}", e))?
+}
+
+/// Re-run a session's tool calls through the local `cupcake` binary with the
+/// current policies and compare against the recorded decisions.
+#[tauri::command]
+async fn replay_session_tool_calls(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+) -> Result<ReplayReport, String> {
+    let cupcake = policy::find_cupcake().ok_or_else(|| "cupcake not found on PATH".to_string())?;
+    let session_file = claude_code::get_session_file_path(&project_path, &session_id)
+        .ok_or_else(|| "Session file not found".to_string())?;
+    let query = EvaluationQuery {
+        session_id: Some(session_id.clone()),
+        limit: Some(u32::MAX),
+        ..Default::default()
+    };
+    let telemetry = state.telemetry_arc();
+
+    // Each replay runs cupcake to completion (or its timeout); keep them off
+    // the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let evaluations =
+            telemetry.with_index(&project_path, |index| index.query(&query).evaluations)?;
+        let events =
+            policy::events_from_session(&session_file, &project_path, &session_id, &evaluations)?;
+        Ok(policy::replay_events(&cupcake, &project_path, events))
+    })
+    .await
+    .map_err(|e| format!("Failed to replay session tool calls: {}", e))?
+}
+
+/// Get the policy evaluations for a session, each linked to the tool_use
+/// and tool_result events it applies to. Requires the session index.
+#[tauri::command]
+async fn get_session_policy_evaluations(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+) -> Result<Vec<PolicyEventLink>, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+    let session_file = claude_code::get_session_file_path(&project_path, &session_id)
+        .ok_or_else(|| "Session file not found".to_string())?;
+    let telemetry = state.telemetry_arc();
+
+    // Refreshing telemetry and reading the session's tool calls block; keep
+    // them off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let evaluations = telemetry.evaluations(&project_path)?;
+        policy::link_session_evaluations(&index, &session_file, &session_id, evaluations)
+    })
+    .await
+    .map_err(|e| format!("Failed to link policy evaluations: {}", e))?
+}
+
+/// Get the policy evaluations for the tool calls on a single session event.
+#[tauri::command]
+fn get_event_policy_evaluations(
+    state: State<'_, WatcherState>,
+    project_path: String,
+    session_id: String,
+    sequence: u32,
+) -> Result<Vec<PolicyEvaluation>, String> {
+    let index = state
+        .get_index(&project_path, &session_id)
+        .ok_or_else(|| "Session index not available".to_string())?;
+
+    let evaluations = state.telemetry().evaluations(&project_path)?;
+    Ok(policy::evaluations_for_event(
+        &index,
+        &session_id,
+        sequence,
+        evaluations,
+    ))
 }
 
 /// Reveal a path in the system file manager.
@@ -375,12 +865,21 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(WatcherState::new())
+        .manage(PtyState::new())
         .invoke_handler(tauri::generate_handler![
             get_projects,
+            get_activity_dashboard,
+            get_hot_files,
+            get_file_edit_history,
             get_project_sessions,
             get_active_sessions,
             get_available_terminals,
             launch_claude,
+            spawn_terminal,
+            write_terminal,
+            resize_terminal,
+            close_terminal,
+            list_terminals,
             get_session_file_edits,
             get_file_diffs,
             get_git_file_diff,
@@ -399,10 +898,25 @@ pub fn run() {
             unwatch_telemetry,
             get_index_status,
             get_indexed_file_edits,
+            get_file_hunks,
             get_indexed_events,
+            get_conversation_graph,
+            get_tool_calls,
+            get_session_stats,
+            get_context_window,
+            compare_sessions,
+            export_session_edits,
+            get_bash_history,
             get_file_edit_context,
             get_policy_evaluations,
             get_policy_evaluation,
+            query_policy_evaluations,
+            get_policy_analytics,
+            get_policy_coverage,
+            replay_policy_evaluations,
+            replay_session_tool_calls,
+            get_session_policy_evaluations,
+            get_event_policy_evaluations,
             reveal_in_file_manager
         ])
         .run(tauri::generate_context!())
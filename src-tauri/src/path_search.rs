@@ -0,0 +1,160 @@
+//! Executable lookup on PATH.
+//!
+//! Searches PATH directly rather than shelling out to `which`, which isn't
+//! installed everywhere and costs a process per lookup.
+
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Find an executable on PATH.
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    find_in_path(name, &env::var_os("PATH")?)
+}
+
+/// Search a PATH-style list of directories for an executable.
+pub fn find_in_path(name: &str, path: &OsStr) -> Option<PathBuf> {
+    search(name, path, &executable_extensions())
+}
+
+/// Search with the given executable extensions (e.g., ".EXE"); none means
+/// the name is used as is.
+fn search(name: &str, path: &OsStr, extensions: &[String]) -> Option<PathBuf> {
+    let names = candidate_names(name, extensions);
+    env::split_paths(path)
+        .flat_map(|dir| names.iter().map(move |n| dir.join(n)))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// File names an executable may have. On Windows a bare name only runs with
+/// one of the PATHEXT extensions, so "claude" is "claude.cmd" and never the
+/// extensionless shell script npm installs next to it.
+fn candidate_names(name: &str, extensions: &[String]) -> Vec<String> {
+    if extensions.is_empty() {
+        return vec![name.to_string()];
+    }
+    let has_extension = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| {
+            extensions
+                .iter()
+                .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(e))
+        });
+    if has_extension {
+        return vec![name.to_string()];
+    }
+    extensions
+        .iter()
+        .map(|ext| format!("{}{}", name, ext))
+        .collect()
+}
+
+/// Extensions from PATHEXT, or the Windows defaults.
+#[cfg(windows)]
+fn executable_extensions() -> Vec<String> {
+    let pathext = env::var("PATHEXT").unwrap_or_default();
+    let extensions: Vec<String> = pathext
+        .split(';')
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .map(String::from)
+        .collect();
+    if extensions.is_empty() {
+        [".COM", ".EXE", ".BAT", ".CMD"].map(String::from).to_vec()
+    } else {
+        extensions
+    }
+}
+
+#[cfg(not(windows))]
+fn executable_extensions() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Create a file, executable on unix when `executable` is set.
+    fn touch(path: &Path, executable: bool) {
+        fs::write(path, "").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = if executable { 0o755 } else { 0o644 };
+            fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        #[cfg(not(unix))]
+        let _ = executable;
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let root =
+            env::temp_dir().join(format!("agent-console-path-search-{}", std::process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::create_dir_all(first.join("subdir")).unwrap();
+        touch(&first.join("tool"), false);
+        touch(&second.join("tool"), true);
+        touch(&second.join("claude"), true);
+        touch(&second.join("claude.cmd"), true);
+        let path = env::join_paths([&first, &second]).unwrap();
+
+        let found = |name: &str, extensions: &[&str]| {
+            let extensions: Vec<String> = extensions.iter().map(|e| e.to_string()).collect();
+            search(name, &path, &extensions)
+        };
+        // Directories and (on unix) files without an execute bit are skipped
+        #[cfg(unix)]
+        assert_eq!(found("tool", &[]), Some(second.join("tool")));
+        assert_eq!(found("subdir", &[]), None);
+        assert_eq!(found("missing", &[]), None);
+
+        // With PATHEXT, the extension is required
+        assert_eq!(
+            found("claude", &[".exe", ".cmd"]),
+            Some(second.join("claude.cmd"))
+        );
+        assert_eq!(
+            found("claude.cmd", &[".exe", ".cmd"]),
+            Some(second.join("claude.cmd"))
+        );
+        assert_eq!(found("claude", &[".exe"]), None);
+        assert_eq!(found("claude", &[]), Some(second.join("claude")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_candidate_names() {
+        let pathext: Vec<String> = [".COM", ".EXE", ".CMD"].map(String::from).to_vec();
+        assert_eq!(
+            candidate_names("claude", &pathext),
+            vec!["claude.COM", "claude.EXE", "claude.CMD"]
+        );
+        assert_eq!(candidate_names("claude.cmd", &pathext), vec!["claude.cmd"]);
+        // An unknown extension is part of the name
+        assert_eq!(
+            candidate_names("node.v20", &pathext),
+            vec!["node.v20.COM", "node.v20.EXE", "node.v20.CMD"]
+        );
+        assert_eq!(candidate_names("claude", &[]), vec!["claude"]);
+    }
+}
@@ -0,0 +1,179 @@
+//! What a session did, in terms Cupcake hooks see.
+//!
+//! Scans a session file for the tool calls (PreToolUse), tool results
+//! (PostToolUse) and prompts (UserPromptSubmit) that would have been sent to
+//! Cupcake.
+
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::session_index::parse_stages;
+
+/// Programs whose first argument names the real operation (e.g., "git push").
+const SUBCOMMAND_PROGRAMS: [&str; 12] = [
+    "git", "npm", "pnpm", "yarn", "cargo", "docker", "kubectl", "go", "pip", "uv", "gh", "brew",
+];
+
+/// A tool_use entry in a session.
+#[derive(Debug, Clone)]
+pub struct ToolUse {
+    /// Line of the assistant event containing the tool_use
+    pub line: u32,
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    pub cwd: Option<String>,
+    pub timestamp: Option<String>,
+}
+
+impl ToolUse {
+    /// The command of a Bash tool call.
+    pub fn bash_command(&self) -> Option<&str> {
+        if self.name != "Bash" {
+            return None;
+        }
+        self.input.get("command").and_then(|c| c.as_str())
+    }
+}
+
+/// Hook-relevant activity of a session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionActivity {
+    pub tool_uses: Vec<ToolUse>,
+    /// Number of tool_result entries
+    pub tool_results: u32,
+    /// Number of human prompts
+    pub prompts: u32,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+}
+
+/// Scan a session file for its hook-relevant activity.
+///
+/// Only a session file that can't be opened is an error; unreadable lines
+/// are skipped.
+pub fn scan_session(session_file: &Path) -> Result<SessionActivity, String> {
+    let file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+    let mut activity = SessionActivity::default();
+
+    for (line_num, line) in BufReader::new(file).lines().enumerate() {
+        // Skip lines that can't be read (e.g. invalid UTF-8)
+        let Ok(line) = line else {
+            continue;
+        };
+        let Ok(entry) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        if let Some(ts) = &timestamp {
+            if activity.first_timestamp.is_none() {
+                activity.first_timestamp = Some(ts.clone());
+            }
+            activity.last_timestamp = Some(ts.clone());
+        }
+
+        let content = entry.get("message").and_then(|m| m.get("content"));
+        let items = content.and_then(|c| c.as_array());
+        match entry.get("type").and_then(|t| t.as_str()) {
+            Some("assistant") => {
+                for item in items.into_iter().flatten() {
+                    if item.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
+                        continue;
+                    }
+                    activity.tool_uses.push(ToolUse {
+                        line: line_num as u32,
+                        id: item
+                            .get("id")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        name: item
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        input: item.get("input").cloned().unwrap_or(Value::Null),
+                        cwd: entry.get("cwd").and_then(|v| v.as_str()).map(String::from),
+                        timestamp: timestamp.clone(),
+                    });
+                }
+            }
+            Some("user") => {
+                let results = items
+                    .into_iter()
+                    .flatten()
+                    .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+                    .count() as u32;
+                activity.tool_results += results;
+
+                let is_prompt = results == 0
+                    && entry.get("userType").and_then(|v| v.as_str()) == Some("external")
+                    && entry.get("isMeta").and_then(|v| v.as_bool()) != Some(true);
+                if is_prompt {
+                    activity.prompts += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(activity)
+}
+
+/// Reduce a shell command to a pattern for grouping: the program of its
+/// first stage, plus the subcommand for programs like git. Leading
+/// `VAR=value` assignments and wrappers like `sudo` are skipped.
+///
+/// e.g., "FOO=1 sudo git push origin main" -> "git push"
+pub fn command_pattern(command: &str) -> String {
+    let Some(stage) = parse_stages(command).into_iter().next() else {
+        return String::new();
+    };
+
+    if SUBCOMMAND_PROGRAMS.contains(&stage.program.as_str()) {
+        if let Some(sub) = stage.args.first().filter(|a| !a.starts_with('-')) {
+            return format!("{} {}", stage.program, sub);
+        }
+    }
+    stage.program
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::write_session_raw;
+
+    #[test]
+    fn test_command_pattern() {
+        assert_eq!(command_pattern("ls -la"), "ls");
+        assert_eq!(
+            command_pattern("FOO=1 sudo git push origin main"),
+            "git push"
+        );
+        assert_eq!(command_pattern("/usr/bin/cargo --version"), "cargo");
+        assert_eq!(command_pattern("npm run build && npm test"), "npm run");
+        assert_eq!(command_pattern("   "), "");
+        assert_eq!(command_pattern("cd app; cargo test"), "cd");
+        assert_eq!(command_pattern("env -i 'git' log"), "git log");
+    }
+
+    #[test]
+    fn test_scan_session_skips_unreadable_lines() {
+        let tool_use = serde_json::json!({"type": "assistant", "message": {"content": [
+            {"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": "ls"}},
+        ]}});
+        let mut content = b"\xff\xfe not utf-8\n".to_vec();
+        content.extend(format!("{}\n", tool_use).into_bytes());
+        let session = write_session_raw("activity", content);
+        let activity = scan_session(session.path()).unwrap();
+        assert_eq!(activity.tool_uses.len(), 1);
+        assert_eq!(activity.tool_uses[0].line, 1);
+    }
+}
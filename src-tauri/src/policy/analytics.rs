@@ -0,0 +1,502 @@
+//! Aggregate statistics over policy evaluations.
+//!
+//! Answers questions like "which policies deny most often" or "what is the
+//! p95 evaluation latency" without opening telemetry files one by one.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::summary::TelemetrySummary;
+use crate::claude_code::PolicyEvaluation;
+
+/// Filters and options for `compute_policy_analytics`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsFilter {
+    /// Only include evaluations at or after this time (RFC 3339 or YYYY-MM-DD)
+    pub since: Option<String>,
+    /// Only include evaluations at or before this time (RFC 3339 or YYYY-MM-DD,
+    /// a bare date includes the whole day)
+    pub until: Option<String>,
+    /// Only include evaluations from this Claude Code session
+    pub session_id: Option<String>,
+    /// Trend bucket size (defaults to day)
+    pub bucket: Option<TimeBucket>,
+}
+
+/// Size of the time buckets used for trends (UTC).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    Hour,
+    #[default]
+    Day,
+    /// Weeks start on Monday
+    Week,
+}
+
+/// Aggregated statistics over a set of policy evaluations.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyAnalytics {
+    /// Number of evaluations that matched the filter
+    pub total_evaluations: u32,
+    /// Final decision → count
+    pub decisions: BTreeMap<String, u32>,
+    /// Decision counts per tool name, most evaluated first
+    pub by_tool: Vec<DecisionCounts>,
+    /// Decision counts per hook event type, most evaluated first
+    pub by_event_type: Vec<DecisionCounts>,
+    /// Decision counts per policy (rule) ID, most evaluated first. Policies
+    /// that matched without deciding are counted as "NoDecision".
+    pub by_policy: Vec<DecisionCounts>,
+    /// Latency of whole evaluations (total_duration_ms)
+    pub latency: LatencyStats,
+    /// Latency per phase name (e.g., "global", "project"), sorted by name
+    pub phases: Vec<PhaseTiming>,
+    /// Evaluation counts per time bucket, oldest first
+    pub trend: Vec<TrendBucket>,
+}
+
+/// Decision counts for one tool, event type or policy.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionCounts {
+    pub key: String,
+    pub total: u32,
+    /// Decision → count
+    pub decisions: BTreeMap<String, u32>,
+}
+
+/// Latency distribution in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub count: u32,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+/// Latency of a single evaluation phase.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTiming {
+    pub name: String,
+    pub latency: LatencyStats,
+}
+
+/// Evaluations within one time bucket.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendBucket {
+    /// Bucket start (RFC 3339, UTC)
+    pub start: String,
+    pub total: u32,
+    /// Decision → count
+    pub decisions: BTreeMap<String, u32>,
+}
+
+/// Compute analytics over telemetry summaries.
+pub fn compute_policy_analytics<'a>(
+    summaries: impl IntoIterator<Item = &'a TelemetrySummary>,
+    filter: &AnalyticsFilter,
+) -> PolicyAnalytics {
+    let since = filter.since.as_deref().and_then(|s| parse_bound(s, false));
+    let until = filter.until.as_deref().and_then(|s| parse_bound(s, true));
+    let bucket = filter.bucket.unwrap_or_default();
+
+    let mut decisions: BTreeMap<String, u32> = BTreeMap::new();
+    let mut by_tool: BTreeMap<String, BTreeMap<String, u32>> = BTreeMap::new();
+    let mut by_event_type: BTreeMap<String, BTreeMap<String, u32>> = BTreeMap::new();
+    let mut by_policy: BTreeMap<String, BTreeMap<String, u32>> = BTreeMap::new();
+    let mut latencies: Vec<f64> = Vec::new();
+    let mut phase_latencies: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    let mut trend: BTreeMap<DateTime<Utc>, BTreeMap<String, u32>> = BTreeMap::new();
+
+    for summary in summaries {
+        let evaluation = &summary.evaluation;
+        let timestamp = DateTime::parse_from_rfc3339(&evaluation.timestamp)
+            .ok()
+            .map(|t| t.with_timezone(&Utc));
+
+        if !matches_filter(evaluation, timestamp, since, until, filter) {
+            continue;
+        }
+
+        let decision = evaluation
+            .decision
+            .clone()
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        count(&mut decisions, &decision);
+        if let Some(tool) = &evaluation.tool_name {
+            count(by_tool.entry(tool.clone()).or_default(), &decision);
+        }
+        if let Some(event_type) = &evaluation.event_type {
+            count(
+                by_event_type.entry(event_type.clone()).or_default(),
+                &decision,
+            );
+        }
+        for (policy, policy_decision) in &summary.policy_decisions {
+            count(
+                by_policy.entry(policy.clone()).or_default(),
+                policy_decision,
+            );
+        }
+
+        latencies.push(evaluation.duration_ms as f64);
+        for (name, duration_ms) in &summary.phase_durations {
+            phase_latencies
+                .entry(name.clone())
+                .or_default()
+                .push(*duration_ms);
+        }
+
+        if let Some(timestamp) = timestamp {
+            count(
+                trend.entry(bucket_start(timestamp, bucket)).or_default(),
+                &decision,
+            );
+        }
+    }
+
+    PolicyAnalytics {
+        total_evaluations: latencies.len() as u32,
+        decisions,
+        by_tool: into_counts(by_tool),
+        by_event_type: into_counts(by_event_type),
+        by_policy: into_counts(by_policy),
+        latency: latency_stats(&mut latencies),
+        phases: phase_latencies
+            .into_iter()
+            .map(|(name, mut samples)| PhaseTiming {
+                name,
+                latency: latency_stats(&mut samples),
+            })
+            .collect(),
+        trend: trend
+            .into_iter()
+            .map(|(start, decisions)| TrendBucket {
+                start: start.to_rfc3339(),
+                total: decisions.values().sum(),
+                decisions,
+            })
+            .collect(),
+    }
+}
+
+fn matches_filter(
+    evaluation: &PolicyEvaluation,
+    timestamp: Option<DateTime<Utc>>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    filter: &AnalyticsFilter,
+) -> bool {
+    if let Some(session_id) = &filter.session_id {
+        if evaluation.session_id.as_ref() != Some(session_id) {
+            return false;
+        }
+    }
+
+    in_range(timestamp, since, until)
+}
+
+/// Check a timestamp against an optional inclusive date range.
+///
+/// A missing timestamp can't satisfy a range.
+pub(super) fn in_range(
+    timestamp: Option<DateTime<Utc>>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> bool {
+    if since.is_none() && until.is_none() {
+        return true;
+    }
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    if since.is_some_and(|since| timestamp < since) {
+        return false;
+    }
+    if until.is_some_and(|until| timestamp > until) {
+        return false;
+    }
+    true
+}
+
+/// Parse a date range bound. A bare date means the start of that day, or
+/// the end of it for an inclusive upper bound.
+pub(super) fn parse_bound(value: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let start = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?);
+    if end_of_day {
+        Some(start + Duration::days(1) - Duration::nanoseconds(1))
+    } else {
+        Some(start)
+    }
+}
+
+/// Truncate a timestamp to the start of its bucket.
+fn bucket_start(timestamp: DateTime<Utc>, bucket: TimeBucket) -> DateTime<Utc> {
+    let hour = timestamp
+        .with_minute(0)
+        .and_then(|t| t.with_second(0))
+        .and_then(|t| t.with_nanosecond(0))
+        .unwrap_or(timestamp);
+    let day = hour.with_hour(0).unwrap_or(hour);
+
+    match bucket {
+        TimeBucket::Hour => hour,
+        TimeBucket::Day => day,
+        TimeBucket::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+    }
+}
+
+fn count(counts: &mut BTreeMap<String, u32>, key: &str) {
+    *counts.entry(key.to_string()).or_default() += 1;
+}
+
+/// Flatten grouped counts, most evaluated first (ties by key).
+fn into_counts(groups: BTreeMap<String, BTreeMap<String, u32>>) -> Vec<DecisionCounts> {
+    let mut counts: Vec<DecisionCounts> = groups
+        .into_iter()
+        .map(|(key, decisions)| DecisionCounts {
+            key,
+            total: decisions.values().sum(),
+            decisions,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.key.cmp(&b.key)));
+    counts
+}
+
+/// Compute latency statistics (nearest-rank percentiles).
+fn latency_stats(samples: &mut [f64]) -> LatencyStats {
+    if samples.is_empty() {
+        return LatencyStats::default();
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * samples.len() as f64).ceil() as usize;
+        samples[rank.clamp(1, samples.len()) - 1]
+    };
+
+    LatencyStats {
+        count: samples.len() as u32,
+        min_ms: samples[0],
+        max_ms: samples[samples.len() - 1],
+        mean_ms: samples.iter().sum::<f64>() / samples.len() as f64,
+        p50_ms: percentile(50.0),
+        p90_ms: percentile(90.0),
+        p95_ms: percentile(95.0),
+        p99_ms: percentile(99.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(
+        timestamp: &str,
+        session: &str,
+        tool: &str,
+        total_ms: u64,
+        deny_policy: Option<&str>,
+    ) -> TelemetrySummary {
+        let (final_decision, denials) = match deny_policy {
+            Some(policy) => (
+                serde_json::json!({"Deny": {"reason": "nope", "agent_messages": []}}),
+                serde_json::json!([{
+                    "rule_id": "SAFETY-1",
+                    "package": policy,
+                    "reason": "nope",
+                    "severity": "HIGH",
+                }]),
+            ),
+            None => (
+                serde_json::json!({"Allow": {"context": []}}),
+                serde_json::json!([]),
+            ),
+        };
+        let span = serde_json::json!({
+            "timestamp": timestamp,
+            "trace_id": "t",
+            "total_duration_ms": total_ms,
+            "raw_event": {"hook_event_name": "PreToolUse", "tool_name": tool, "session_id": session},
+            "enrich": {"duration_us": 500},
+            "phases": [{
+                "name": "project",
+                "duration_ms": total_ms,
+                "evaluation": {
+                    "matched_policies": ["shell.safety", "audit.log"],
+                    "wasm_decision_set": {"halts": [], "denials": denials, "blocks": [], "asks": []},
+                    "final_decision": final_decision,
+                },
+            }],
+        });
+        let span = serde_json::from_value(span).unwrap();
+        TelemetrySummary::from_span(format!("{}.json", timestamp), &span)
+    }
+
+    fn spans() -> Vec<TelemetrySummary> {
+        vec![
+            span(
+                "2025-01-01T10:15:00Z",
+                "s1",
+                "Bash",
+                10,
+                Some("shell.safety"),
+            ),
+            span("2025-01-01T11:30:00Z", "s1", "Bash", 20, None),
+            span("2025-01-02T09:00:00Z", "s2", "Edit", 30, None),
+            span(
+                "2025-01-06T09:00:00Z",
+                "s2",
+                "Bash",
+                40,
+                Some("shell.safety"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_decisions_map_to_their_policy() {
+        let span = serde_json::from_value(serde_json::json!({
+            "timestamp": "2025-01-01T00:00:00Z",
+            "phases": [
+                {
+                    "name": "global",
+                    "evaluation": {
+                        "matched_policies": ["global.deny_rm"],
+                        "wasm_decision_set": {"denials": [
+                            {"rule_id": "RM-1", "reason": "no rm"},
+                            {"rule_id": "RM-2", "reason": "no rm -f"},
+                        ]},
+                    },
+                },
+                {
+                    "name": "project",
+                    "evaluation": {
+                        "matched_policies": ["project.git", "project.audit"],
+                        "wasm_decision_set": {"asks": [
+                            {"rule_id": "GIT-1", "policy": "project.git", "reason": "push?"},
+                        ]},
+                    },
+                },
+            ],
+        }))
+        .unwrap();
+        let summary = TelemetrySummary::from_span("a.json".to_string(), &span);
+
+        let mut decisions = summary.policy_decisions.clone();
+        decisions.sort();
+        let expected = [
+            ("global.deny_rm", "Deny"),
+            ("project.audit", "NoDecision"),
+            ("project.git", "Ask"),
+        ];
+        assert_eq!(
+            decisions,
+            expected.map(|(p, d)| (p.to_string(), d.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_counts_and_latency() {
+        let analytics = compute_policy_analytics(&spans(), &AnalyticsFilter::default());
+
+        assert_eq!(analytics.total_evaluations, 4);
+        assert_eq!(analytics.decisions["Deny"], 2);
+        assert_eq!(analytics.decisions["Allow"], 2);
+
+        assert_eq!(analytics.by_tool[0].key, "Bash");
+        assert_eq!(analytics.by_tool[0].total, 3);
+        assert_eq!(analytics.by_tool[0].decisions["Deny"], 2);
+        assert_eq!(analytics.by_event_type[0].key, "PreToolUse");
+
+        let safety = analytics
+            .by_policy
+            .iter()
+            .find(|p| p.key == "shell.safety")
+            .unwrap();
+        assert_eq!(safety.decisions["Deny"], 2);
+        // Matching without a decision is not an allow, and a policy that
+        // decided isn't also counted as not deciding
+        assert_eq!(safety.decisions["NoDecision"], 2);
+        assert_eq!(safety.decisions.get("Allow"), None);
+        assert_eq!(safety.total, 4);
+        // Decisions are keyed by policy, not rule ID
+        assert!(analytics.by_policy.iter().all(|p| p.key != "SAFETY-1"));
+        let audit = analytics
+            .by_policy
+            .iter()
+            .find(|p| p.key == "audit.log")
+            .unwrap();
+        assert_eq!(audit.decisions.get("Deny"), None);
+        assert_eq!(audit.decisions["NoDecision"], 4);
+        assert_eq!(audit.total, 4);
+
+        assert_eq!(analytics.latency.min_ms, 10.0);
+        assert_eq!(analytics.latency.max_ms, 40.0);
+        assert_eq!(analytics.latency.mean_ms, 25.0);
+        assert_eq!(analytics.latency.p50_ms, 20.0);
+        assert_eq!(analytics.latency.p95_ms, 40.0);
+
+        let names: Vec<&str> = analytics.phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["enrich", "project"]);
+        assert_eq!(analytics.phases[0].latency.mean_ms, 0.5);
+    }
+
+    #[test]
+    fn test_filters_and_buckets() {
+        let filter = AnalyticsFilter {
+            since: Some("2025-01-01".to_string()),
+            until: Some("2025-01-02".to_string()),
+            ..Default::default()
+        };
+        let analytics = compute_policy_analytics(&spans(), &filter);
+        assert_eq!(analytics.total_evaluations, 3);
+        let starts: Vec<&str> = analytics.trend.iter().map(|b| b.start.as_str()).collect();
+        assert_eq!(
+            starts,
+            vec!["2025-01-01T00:00:00+00:00", "2025-01-02T00:00:00+00:00"]
+        );
+        assert_eq!(analytics.trend[0].total, 2);
+
+        let filter = AnalyticsFilter {
+            session_id: Some("s2".to_string()),
+            bucket: Some(TimeBucket::Week),
+            ..Default::default()
+        };
+        let analytics = compute_policy_analytics(&spans(), &filter);
+        assert_eq!(analytics.total_evaluations, 2);
+        // 2025-01-02 is a Thursday, 2025-01-06 a Monday
+        let starts: Vec<&str> = analytics.trend.iter().map(|b| b.start.as_str()).collect();
+        assert_eq!(
+            starts,
+            vec!["2024-12-30T00:00:00+00:00", "2025-01-06T00:00:00+00:00"]
+        );
+
+        let filter = AnalyticsFilter {
+            bucket: Some(TimeBucket::Hour),
+            until: Some("2025-01-01T11:00:00Z".to_string()),
+            ..Default::default()
+        };
+        let analytics = compute_policy_analytics(&spans(), &filter);
+        assert_eq!(analytics.trend.len(), 1);
+        assert_eq!(analytics.trend[0].start, "2025-01-01T10:00:00+00:00");
+    }
+}
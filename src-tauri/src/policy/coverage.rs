@@ -0,0 +1,441 @@
+//! Policy coverage.
+//!
+//! Cross-references the tool calls in a project's sessions with the Cupcake
+//! telemetry (by tool_use id) to find what ran without policy coverage:
+//! tools, hook event types and Bash command patterns that were never
+//! evaluated or only ever matched no policy, and sessions that ran while
+//! Cupcake wasn't emitting telemetry.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::activity::{command_pattern, SessionActivity};
+use super::summary::TelemetrySummary;
+
+/// How well something is covered by policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CoverageStatus {
+    /// Never sent to Cupcake
+    NeverEvaluated,
+    /// Evaluated, but no policy ever matched
+    NoPolicyMatched,
+    /// At least one policy matched at least once
+    Covered,
+}
+
+/// Coverage of one tool, hook event type or command pattern.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageEntry {
+    pub key: String,
+    /// Number of times it occurred in sessions
+    pub occurrences: u32,
+    /// Occurrences with at least one evaluation
+    pub evaluated: u32,
+    /// Occurrences where at least one policy matched
+    pub matched: u32,
+    pub status: CoverageStatus,
+}
+
+/// Coverage of one session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCoverage {
+    pub session_id: String,
+    pub tool_calls: u32,
+    /// Tool calls with at least one evaluation
+    pub evaluated_tool_calls: u32,
+    /// Evaluations recorded for the session
+    pub evaluations: u32,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    /// The session made tool calls but Cupcake recorded nothing for it
+    pub missing_telemetry: bool,
+}
+
+/// Policy coverage of a project's sessions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReport {
+    pub total_tool_calls: u32,
+    pub evaluated_tool_calls: u32,
+    pub matched_tool_calls: u32,
+    /// Per tool name, least covered first
+    pub tools: Vec<CoverageEntry>,
+    /// Per hook event type, least covered first
+    pub event_types: Vec<CoverageEntry>,
+    /// Per Bash command pattern (e.g., "git push"), least covered first
+    pub bash_commands: Vec<CoverageEntry>,
+    /// Per session, ordered by first activity
+    pub sessions: Vec<SessionCoverage>,
+    pub sessions_without_telemetry: u32,
+}
+
+/// Occurrence counts while building an entry.
+#[derive(Default)]
+struct Tally {
+    occurrences: u32,
+    evaluated: u32,
+    matched: u32,
+}
+
+impl Tally {
+    fn add(&mut self, evaluated: bool, matched: bool) {
+        self.occurrences += 1;
+        self.evaluated += evaluated as u32;
+        self.matched += matched as u32;
+    }
+}
+
+/// Build the coverage report for sessions given as (session id, activity).
+pub fn compute_coverage<'a>(
+    sessions: &[(String, SessionActivity)],
+    summaries: impl IntoIterator<Item = &'a TelemetrySummary>,
+) -> CoverageReport {
+    let session_ids: HashSet<&str> = sessions.iter().map(|(id, _)| id.as_str()).collect();
+
+    // Evaluations by tool_use id, and per session
+    let mut by_tool_use: HashMap<&str, Vec<&TelemetrySummary>> = HashMap::new();
+    let mut by_session: HashMap<&str, Vec<&TelemetrySummary>> = HashMap::new();
+    for summary in summaries {
+        let evaluation = &summary.evaluation;
+        if let Some(id) = evaluation.tool_use_id.as_deref() {
+            by_tool_use.entry(id).or_default().push(summary);
+        }
+        if let Some(id) = evaluation.session_id.as_deref() {
+            if session_ids.contains(id) {
+                by_session.entry(id).or_default().push(summary);
+            }
+        }
+    }
+
+    let is_matched = |s: &TelemetrySummary| !s.policy_decisions.is_empty();
+    let has_event = |evals: Option<&Vec<&TelemetrySummary>>, event: &str| {
+        let evals = evals.map(|v| v.as_slice()).unwrap_or_default();
+        let of_type: Vec<_> = evals
+            .iter()
+            .filter(|s| s.evaluation.event_type.as_deref() == Some(event))
+            .collect();
+        (!of_type.is_empty(), of_type.iter().any(|s| is_matched(s)))
+    };
+
+    let mut tools: BTreeMap<String, Tally> = BTreeMap::new();
+    let mut commands: BTreeMap<String, Tally> = BTreeMap::new();
+    let mut events: BTreeMap<String, Tally> = BTreeMap::new();
+    let mut total = Tally::default();
+    let mut session_coverage = Vec::with_capacity(sessions.len());
+
+    for (session_id, activity) in sessions {
+        let mut session_tally = Tally::default();
+        let mut linked = 0;
+
+        for tool_use in &activity.tool_uses {
+            // Spans that name another session belong to that session's call
+            let evals: Option<Vec<&TelemetrySummary>> = by_tool_use
+                .get(tool_use.id.as_str())
+                .map(|v| {
+                    v.iter()
+                        .copied()
+                        .filter(|s| {
+                            s.evaluation
+                                .session_id
+                                .as_deref()
+                                .is_none_or(|id| id == session_id)
+                        })
+                        .collect()
+                })
+                .filter(|v: &Vec<_>| !v.is_empty());
+            let evals = evals.as_ref();
+            let evaluated = evals.is_some();
+            let matched = evals.is_some_and(|v| v.iter().any(|s| is_matched(s)));
+            linked += evals.map(|v| v.len()).unwrap_or(0);
+
+            total.add(evaluated, matched);
+            session_tally.add(evaluated, matched);
+            tools
+                .entry(tool_use.name.clone())
+                .or_default()
+                .add(evaluated, matched);
+            if let Some(command) = tool_use.bash_command() {
+                commands
+                    .entry(command_pattern(command))
+                    .or_default()
+                    .add(evaluated, matched);
+            }
+
+            let (pre, pre_matched) = has_event(evals, "PreToolUse");
+            events
+                .entry("PreToolUse".to_string())
+                .or_default()
+                .add(pre, pre_matched);
+        }
+
+        // Results can't be tied to a tool name without the tool_use, so
+        // PostToolUse is counted per session
+        let session_evals = by_session.get(session_id.as_str());
+        for (event, occurrences) in [
+            ("PostToolUse", activity.tool_results),
+            ("UserPromptSubmit", activity.prompts),
+        ] {
+            let of_type: Vec<&&TelemetrySummary> = session_evals
+                .into_iter()
+                .flatten()
+                .filter(|s| s.evaluation.event_type.as_deref() == Some(event))
+                .collect();
+            let evaluated = (of_type.len() as u32).min(occurrences);
+            let matched = (of_type.iter().filter(|s| is_matched(s)).count() as u32).min(evaluated);
+            let tally = events.entry(event.to_string()).or_default();
+            tally.occurrences += occurrences;
+            tally.evaluated += evaluated;
+            tally.matched += matched;
+        }
+
+        let evaluations = session_evals.map(|v| v.len()).unwrap_or(0).max(linked) as u32;
+        session_coverage.push(SessionCoverage {
+            session_id: session_id.clone(),
+            tool_calls: session_tally.occurrences,
+            evaluated_tool_calls: session_tally.evaluated,
+            evaluations,
+            first_timestamp: activity.first_timestamp.clone(),
+            last_timestamp: activity.last_timestamp.clone(),
+            missing_telemetry: session_tally.occurrences > 0 && evaluations == 0,
+        });
+    }
+
+    // Hook events we can't see in session files (e.g., Stop, SessionStart)
+    // still show up if Cupcake evaluated them
+    for summary in by_session.values().flatten() {
+        if let Some(event) = summary.evaluation.event_type.as_deref() {
+            if !matches!(event, "PreToolUse" | "PostToolUse" | "UserPromptSubmit") {
+                events
+                    .entry(event.to_string())
+                    .or_default()
+                    .add(true, is_matched(summary));
+            }
+        }
+    }
+
+    session_coverage.sort_by(|a, b| a.first_timestamp.cmp(&b.first_timestamp));
+
+    CoverageReport {
+        total_tool_calls: total.occurrences,
+        evaluated_tool_calls: total.evaluated,
+        matched_tool_calls: total.matched,
+        tools: into_entries(tools),
+        event_types: into_entries(events),
+        bash_commands: into_entries(commands),
+        sessions_without_telemetry: session_coverage
+            .iter()
+            .filter(|s| s.missing_telemetry)
+            .count() as u32,
+        sessions: session_coverage,
+    }
+}
+
+/// Convert tallies to entries, least covered (then most frequent) first.
+fn into_entries(tallies: BTreeMap<String, Tally>) -> Vec<CoverageEntry> {
+    let mut entries: Vec<CoverageEntry> = tallies
+        .into_iter()
+        .filter(|(_, t)| t.occurrences > 0)
+        .map(|(key, t)| CoverageEntry {
+            key,
+            occurrences: t.occurrences,
+            evaluated: t.evaluated,
+            matched: t.matched,
+            status: if t.matched > 0 {
+                CoverageStatus::Covered
+            } else if t.evaluated > 0 {
+                CoverageStatus::NoPolicyMatched
+            } else {
+                CoverageStatus::NeverEvaluated
+            },
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        a.status
+            .cmp(&b.status)
+            .then_with(|| b.occurrences.cmp(&a.occurrences))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::activity::{scan_session, ToolUse};
+    use crate::session_index::test_util::write_session;
+
+    fn summary(
+        session: &str,
+        tool_use_id: &str,
+        event: &str,
+        policies: &[&str],
+    ) -> TelemetrySummary {
+        let span = serde_json::from_value(serde_json::json!({
+            "timestamp": "2025-01-01T00:00:00Z",
+            "raw_event": {"hook_event_name": event, "session_id": session, "tool_use_id": tool_use_id},
+            "phases": [{"name": "project", "evaluation": {"matched_policies": policies}}],
+        }))
+        .unwrap();
+        TelemetrySummary::from_span(format!("{}.json", tool_use_id), &span)
+    }
+
+    fn tool_use(id: &str, name: &str) -> ToolUse {
+        ToolUse {
+            line: 0,
+            id: id.to_string(),
+            name: name.to_string(),
+            input: serde_json::json!({"command": "git status"}),
+            cwd: None,
+            timestamp: None,
+        }
+    }
+
+    fn activity(tool_uses: Vec<ToolUse>) -> SessionActivity {
+        SessionActivity {
+            tool_uses,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_coverage_by_tool_command_and_session() {
+        let lines = [
+            serde_json::json!({"type": "user", "userType": "external", "timestamp": "2025-01-01T00:00:00Z", "message": {"content": "go"}}),
+            serde_json::json!({"type": "assistant", "message": {"content": [
+                {"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": "git push origin main"}},
+                {"type": "tool_use", "id": "t2", "name": "Bash", "input": {"command": "ls"}},
+                {"type": "tool_use", "id": "t3", "name": "Read", "input": {"file_path": "a.txt"}},
+            ]}}),
+            serde_json::json!({"type": "user", "message": {"content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": "ok"},
+            ]}}),
+        ];
+        let session = write_session("coverage", &lines);
+        let activity = scan_session(session.path()).unwrap();
+        assert_eq!(activity.tool_uses.len(), 3);
+        assert_eq!((activity.tool_results, activity.prompts), (1, 1));
+
+        let sessions = vec![
+            ("s1".to_string(), activity.clone()),
+            ("s2".to_string(), activity),
+        ];
+        let summaries = [
+            summary("s1", "t1", "PreToolUse", &["shell.git"]),
+            summary("s1", "t2", "PreToolUse", &[]),
+        ];
+        let report = compute_coverage(&sessions, &summaries);
+
+        assert_eq!(report.total_tool_calls, 6);
+        assert_eq!(report.evaluated_tool_calls, 2);
+        assert_eq!(report.matched_tool_calls, 1);
+
+        let statuses = |entries: &[CoverageEntry]| -> Vec<(String, CoverageStatus)> {
+            entries.iter().map(|e| (e.key.clone(), e.status)).collect()
+        };
+        assert_eq!(
+            statuses(&report.tools),
+            vec![
+                ("Read".to_string(), CoverageStatus::NeverEvaluated),
+                ("Bash".to_string(), CoverageStatus::Covered),
+            ]
+        );
+        assert_eq!(
+            statuses(&report.bash_commands),
+            vec![
+                ("ls".to_string(), CoverageStatus::NoPolicyMatched),
+                ("git push".to_string(), CoverageStatus::Covered),
+            ]
+        );
+        let event_keys: Vec<String> = report.event_types.iter().map(|e| e.key.clone()).collect();
+        assert_eq!(
+            event_keys,
+            vec!["PostToolUse", "UserPromptSubmit", "PreToolUse"]
+        );
+
+        assert_eq!(report.sessions_without_telemetry, 1);
+        let s2 = report
+            .sessions
+            .iter()
+            .find(|s| s.session_id == "s2")
+            .unwrap();
+        assert!(s2.missing_telemetry);
+        assert_eq!(s2.tool_calls, 3);
+    }
+
+    #[test]
+    fn test_coverage_without_events() {
+        let report = compute_coverage(&[], &[]);
+        assert_eq!(report.total_tool_calls, 0);
+        assert!(report.tools.is_empty() && report.event_types.is_empty());
+        assert!(report.sessions.is_empty());
+
+        // A session that did nothing isn't missing telemetry, and spans of
+        // other sessions aren't attributed to it
+        let sessions = vec![("s1".to_string(), SessionActivity::default())];
+        let summaries = [summary("other", "t1", "Stop", &["p"])];
+        let report = compute_coverage(&sessions, &summaries);
+        assert!(report.tools.is_empty() && report.event_types.is_empty());
+        assert_eq!(report.sessions_without_telemetry, 0);
+        assert_eq!(report.sessions[0].evaluations, 0);
+    }
+
+    #[test]
+    fn test_fully_covered_tool() {
+        let sessions = vec![(
+            "s1".to_string(),
+            activity(vec![tool_use("t1", "Bash"), tool_use("t2", "Bash")]),
+        )];
+        let summaries = [
+            summary("s1", "t1", "PreToolUse", &["shell.git"]),
+            summary("s1", "t2", "PreToolUse", &["shell.git"]),
+        ];
+        let report = compute_coverage(&sessions, &summaries);
+
+        let bash = &report.tools[0];
+        assert_eq!(bash.status, CoverageStatus::Covered);
+        assert_eq!((bash.occurrences, bash.evaluated, bash.matched), (2, 2, 2));
+        assert_eq!(report.bash_commands[0].key, "git status");
+        assert_eq!(report.bash_commands[0].matched, 2);
+        assert_eq!(report.matched_tool_calls, report.total_tool_calls);
+        assert!(!report.sessions[0].missing_telemetry);
+    }
+
+    #[test]
+    fn test_unknown_tool_name() {
+        let sessions = vec![(
+            "s1".to_string(),
+            activity(vec![
+                tool_use("t1", "mcp__custom__lookup"),
+                tool_use("t2", ""),
+            ]),
+        )];
+        // Telemetry for a tool_use the session never made, and for the
+        // session's call but claimed by another session
+        let summaries = [
+            summary("s1", "unknown", "PreToolUse", &["p"]),
+            summary("s2", "t1", "PreToolUse", &["p"]),
+        ];
+        let report = compute_coverage(&sessions, &summaries);
+
+        let tools: Vec<(&str, CoverageStatus, u32)> = report
+            .tools
+            .iter()
+            .map(|e| (e.key.as_str(), e.status, e.evaluated))
+            .collect();
+        assert_eq!(
+            tools,
+            vec![
+                ("", CoverageStatus::NeverEvaluated, 0),
+                ("mcp__custom__lookup", CoverageStatus::NeverEvaluated, 0),
+            ]
+        );
+        assert!(report.bash_commands.is_empty());
+        assert_eq!(report.evaluated_tool_calls, 0);
+        // The stray span still counts as telemetry for the session
+        assert_eq!(report.sessions[0].evaluations, 1);
+        assert!(!report.sessions[0].missing_telemetry);
+    }
+}
@@ -0,0 +1,225 @@
+//! Join policy evaluations to session events.
+//!
+//! Every CupcakeSpan's `raw_event` carries the Claude Code `session_id` and
+//! the `tool_use_id` of the tool call being evaluated. The session index maps
+//! tool_use ids to the lines of the tool_use and its tool_result, which is
+//! enough to link the two.
+
+use serde::Serialize;
+use std::fs::File;
+use std::path::Path;
+
+use crate::claude_code::{PolicyEvaluation, SessionEvent};
+use crate::session_index::{get_event_at_line, SessionIndex};
+
+/// A policy evaluation together with the session events it applies to.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyEventLink {
+    pub evaluation: PolicyEvaluation,
+    /// Assistant event containing the evaluated tool_use
+    pub tool_use_event: Option<SessionEvent>,
+    /// User event containing the matching tool_result (absent if the call was
+    /// blocked before it ran, or hasn't finished yet)
+    pub tool_result_event: Option<SessionEvent>,
+}
+
+/// Check whether an evaluation belongs to this session.
+///
+/// Spans without a session_id are matched on tool_use_id alone.
+fn belongs_to_session(
+    evaluation: &PolicyEvaluation,
+    index: &SessionIndex,
+    session_id: &str,
+) -> bool {
+    match evaluation.session_id.as_deref() {
+        Some(id) => id == session_id,
+        None => evaluation
+            .tool_use_id
+            .as_ref()
+            .is_some_and(|id| index.tool_call(id).is_some()),
+    }
+}
+
+/// Link every evaluation for a session to its tool_use and tool_result events.
+///
+/// Evaluations are returned in the order given.
+pub fn link_session_evaluations(
+    index: &SessionIndex,
+    session_file: &Path,
+    session_id: &str,
+    evaluations: Vec<PolicyEvaluation>,
+) -> Result<Vec<PolicyEventLink>, String> {
+    let mut file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let mut links = Vec::new();
+    for evaluation in evaluations {
+        if !belongs_to_session(&evaluation, index, session_id) {
+            continue;
+        }
+
+        let lines = evaluation
+            .tool_use_id
+            .as_ref()
+            .and_then(|id| index.tool_call(id))
+            .cloned()
+            .unwrap_or_default();
+
+        let tool_use_event = match lines.tool_use_line {
+            Some(line) => get_event_at_line(index, &mut file, line)?,
+            None => None,
+        };
+        let tool_result_event = match lines.tool_result_line {
+            Some(line) => get_event_at_line(index, &mut file, line)?,
+            None => None,
+        };
+
+        links.push(PolicyEventLink {
+            evaluation,
+            tool_use_event,
+            tool_result_event,
+        });
+    }
+
+    Ok(links)
+}
+
+/// List the evaluations for the tool calls on a single session event.
+///
+/// Matches both the tool_use event and the tool_result event of a call.
+pub fn evaluations_for_event(
+    index: &SessionIndex,
+    session_id: &str,
+    line: u32,
+    evaluations: Vec<PolicyEvaluation>,
+) -> Vec<PolicyEvaluation> {
+    let tool_use_ids = index.tool_use_ids_at(line);
+    if tool_use_ids.is_empty() {
+        return Vec::new();
+    }
+
+    evaluations
+        .into_iter()
+        .filter(|evaluation| {
+            evaluation
+                .tool_use_id
+                .as_deref()
+                .is_some_and(|id| tool_use_ids.contains(&id))
+                && belongs_to_session(evaluation, index, session_id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::TelemetrySource;
+    use crate::session_index::test_util::write_session;
+
+    fn evaluation(session_id: Option<&str>, tool_use_id: &str, decision: &str) -> PolicyEvaluation {
+        PolicyEvaluation {
+            filename: format!("{}.json", tool_use_id),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            event_type: Some("PreToolUse".to_string()),
+            tool_name: Some("Bash".to_string()),
+            decision: Some(decision.to_string()),
+            duration_ms: 1,
+            trace_id: "trace".to_string(),
+            session_id: session_id.map(String::from),
+            tool_use_id: Some(tool_use_id.to_string()),
+            reason: None,
+            source: TelemetrySource::Project,
+            path: String::new(),
+            line: None,
+            offset: None,
+        }
+    }
+
+    #[test]
+    fn test_links_evaluations_to_tool_use_and_result() {
+        let lines = [
+            serde_json::json!({"type": "user", "userType": "external", "uuid": "u1", "message": {"content": "list files"}}),
+            serde_json::json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1", "message": {"content": [
+                {"type": "tool_use", "id": "toolu_1", "name": "Bash", "input": {"command": "ls"}},
+                {"type": "tool_use", "id": "toolu_2", "name": "Bash", "input": {"command": "rm -rf /"}},
+            ]}}),
+            serde_json::json!({"type": "user", "uuid": "r1", "parentUuid": "a1", "message": {"content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "a.txt"},
+            ]}}),
+        ];
+        let session = write_session("join", &lines);
+        let path = session.path();
+        let index = session.index("/proj");
+
+        let evaluations = vec![
+            evaluation(Some("s1"), "toolu_1", "Allow"),
+            evaluation(Some("s1"), "toolu_2", "Block"),
+            evaluation(Some("other"), "toolu_1", "Allow"),
+            evaluation(None, "toolu_2", "Block"),
+        ];
+
+        let links = link_session_evaluations(&index, path, "s1", evaluations.clone()).unwrap();
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].tool_use_event.as_ref().unwrap().sequence, 1);
+        assert_eq!(links[0].tool_result_event.as_ref().unwrap().sequence, 2);
+        // The blocked call never produced a result
+        assert_eq!(links[1].tool_use_event.as_ref().unwrap().sequence, 1);
+        assert!(links[1].tool_result_event.is_none());
+
+        let on_result = evaluations_for_event(&index, "s1", 2, evaluations.clone());
+        assert_eq!(on_result.len(), 1);
+        assert_eq!(on_result[0].decision.as_deref(), Some("Allow"));
+        assert_eq!(
+            evaluations_for_event(&index, "s1", 1, evaluations.clone()).len(),
+            3
+        );
+        assert!(evaluations_for_event(&index, "s1", 0, evaluations).is_empty());
+    }
+
+    #[test]
+    fn test_sidechain_subagent_and_unmatched_evaluations() {
+        let lines = [
+            serde_json::json!({"type": "user", "userType": "external", "uuid": "u1", "message": {"content": "review"}}),
+            // A sidechain (inline sub-agent) call is indexed like any other
+            serde_json::json!({"type": "assistant", "uuid": "s1", "parentUuid": "u1", "isSidechain": true, "message": {"content": [
+                {"type": "tool_use", "id": "toolu_side", "name": "Grep", "input": {"pattern": "TODO"}},
+            ]}}),
+            serde_json::json!({"type": "user", "uuid": "s2", "parentUuid": "s1", "isSidechain": true, "message": {"content": [
+                {"type": "tool_result", "tool_use_id": "toolu_side", "content": "none"},
+            ]}}),
+        ];
+        let session = write_session("join-unmatched", &lines);
+        let path = session.path();
+        let index = session.index("/proj");
+
+        let evaluations = vec![
+            evaluation(Some("s1"), "toolu_side", "Allow"),
+            // A sub-agent's call runs under the parent session id, but lives
+            // in the agent's own file
+            evaluation(Some("s1"), "toolu_agent", "Deny"),
+            // Neither the session nor the tool call match
+            evaluation(None, "toolu_unknown", "Allow"),
+        ];
+
+        let links = link_session_evaluations(&index, path, "s1", evaluations.clone()).unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].tool_use_event.as_ref().unwrap().sequence, 1);
+        assert_eq!(links[0].tool_result_event.as_ref().unwrap().sequence, 2);
+        assert_eq!(
+            links[1].evaluation.tool_use_id.as_deref(),
+            Some("toolu_agent")
+        );
+        assert!(links[1].tool_use_event.is_none());
+        assert!(links[1].tool_result_event.is_none());
+
+        let on_sidechain = evaluations_for_event(&index, "s1", 1, evaluations.clone());
+        assert_eq!(on_sidechain.len(), 1);
+        assert_eq!(on_sidechain[0].tool_use_id.as_deref(), Some("toolu_side"));
+        // The prompt line has no tool calls
+        assert!(evaluations_for_event(&index, "s1", 0, evaluations.clone()).is_empty());
+        assert!(link_session_evaluations(&index, path, "other", evaluations)
+            .unwrap()
+            .is_empty());
+    }
+}
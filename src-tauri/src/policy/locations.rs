@@ -0,0 +1,198 @@
+//! Where Cupcake telemetry is written.
+//!
+//! Spans can come from three places:
+//! - the project (`<project>/.cupcake/telemetry`)
+//! - global policies (`~/.config/cupcake/telemetry`)
+//! - extra outputs listed in the app's `telemetry.json` config
+//!
+//! A location is either a directory of span files or a single span file.
+//! Span files hold one span (`.json`) or a stream of spans, one per line
+//! (`.jsonl`).
+//!
+//! Global and configured locations are shared by every project, so they are
+//! scoped to the project: only spans whose event `cwd` is the project
+//! directory (or inside it) belong to it.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::claude_code::get_telemetry_dir;
+
+/// Which kind of location a span was read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TelemetrySource {
+    /// The project's own `.cupcake/telemetry` directory
+    #[default]
+    Project,
+    /// Cupcake's global configuration directory
+    Global,
+    /// A location listed in the app config
+    Configured,
+}
+
+/// A directory or file that telemetry spans are read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryLocation {
+    pub source: TelemetrySource,
+    pub path: PathBuf,
+    /// Only keep spans whose event `cwd` is within this directory (None
+    /// keeps every span)
+    pub scope: Option<PathBuf>,
+}
+
+/// App config listing extra telemetry locations.
+#[derive(Debug, Default, Deserialize)]
+struct TelemetryConfig {
+    /// Directories or span files; relative paths are resolved against the
+    /// project and `~/` against the home directory
+    #[serde(default)]
+    locations: Vec<String>,
+}
+
+/// Default location of the telemetry config file.
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("io.eqtylab.agent-console").join("telemetry.json"))
+}
+
+/// Cupcake's global telemetry directory.
+fn global_telemetry_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".config").join("cupcake").join("telemetry"))
+}
+
+/// Resolve every telemetry location for a project, in priority order.
+pub fn telemetry_locations(project_path: &str) -> Vec<TelemetryLocation> {
+    resolve_locations(
+        project_path,
+        global_telemetry_dir(),
+        default_config_path().as_deref(),
+    )
+}
+
+fn resolve_locations(
+    project_path: &str,
+    global_dir: Option<PathBuf>,
+    config_path: Option<&Path>,
+) -> Vec<TelemetryLocation> {
+    let mut locations = vec![TelemetryLocation {
+        source: TelemetrySource::Project,
+        path: get_telemetry_dir(project_path),
+        scope: None,
+    }];
+    let scope = Some(PathBuf::from(project_path));
+
+    if let Some(path) = global_dir {
+        locations.push(TelemetryLocation {
+            source: TelemetrySource::Global,
+            path,
+            scope: scope.clone(),
+        });
+    }
+
+    let config = config_path
+        .and_then(|p| fs::read(p).ok())
+        .and_then(|bytes| serde_json::from_slice::<TelemetryConfig>(&bytes).ok())
+        .unwrap_or_default();
+    for location in config.locations {
+        locations.push(TelemetryLocation {
+            source: TelemetrySource::Configured,
+            path: resolve_path(project_path, &location),
+            scope: scope.clone(),
+        });
+    }
+
+    // The same directory may be reachable more than once (e.g., a configured
+    // location pointing at the project directory); keep the first
+    let mut seen = Vec::new();
+    locations.retain(|location| {
+        let key = fs::canonicalize(&location.path).unwrap_or_else(|_| location.path.clone());
+        if seen.contains(&key) {
+            false
+        } else {
+            seen.push(key);
+            true
+        }
+    });
+    locations
+}
+
+fn resolve_path(project_path: &str, location: &str) -> PathBuf {
+    if let Some(rest) = location.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    let path = PathBuf::from(location);
+    if path.is_absolute() {
+        path
+    } else {
+        PathBuf::from(project_path).join(path)
+    }
+}
+
+/// Whether a file name looks like a span file.
+pub fn is_span_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("json") | Some("jsonl")
+    )
+}
+
+/// Whether a span file holds one span per line.
+pub fn is_span_stream(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_locations_merges_sources() {
+        let root =
+            std::env::temp_dir().join(format!("agent-console-locations-{}", std::process::id()));
+        let project = root.join("project");
+        fs::create_dir_all(project.join(".cupcake").join("telemetry")).unwrap();
+        let config_path = root.join("telemetry.json");
+        fs::write(
+            &config_path,
+            r#"{"locations": ["/var/log/cupcake/spans.jsonl", "logs", ".cupcake/telemetry"]}"#,
+        )
+        .unwrap();
+
+        let project_path = project.to_string_lossy().to_string();
+        let locations =
+            resolve_locations(&project_path, Some(root.join("global")), Some(&config_path));
+        assert!(locations[0].scope.is_none());
+        assert!(locations[1..]
+            .iter()
+            .all(|l| l.scope.as_deref() == Some(project.as_path())));
+        let summary: Vec<(TelemetrySource, PathBuf)> =
+            locations.into_iter().map(|l| (l.source, l.path)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    TelemetrySource::Project,
+                    project.join(".cupcake").join("telemetry")
+                ),
+                (TelemetrySource::Global, root.join("global")),
+                (
+                    TelemetrySource::Configured,
+                    PathBuf::from("/var/log/cupcake/spans.jsonl")
+                ),
+                (TelemetrySource::Configured, project.join("logs")),
+            ]
+        );
+
+        // A missing or invalid config only yields the built-in locations
+        fs::write(&config_path, "not json").unwrap();
+        assert_eq!(
+            resolve_locations(&project_path, None, Some(&config_path)).len(),
+            1
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
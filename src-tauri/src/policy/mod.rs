@@ -0,0 +1,29 @@
+//! Cupcake policy telemetry analysis.
+//!
+//! Keeps an incremental index of each project's telemetry (from project,
+//! global and configured locations), relates evaluations to Claude Code
+//! sessions, aggregates them, measures how much of the sessions' activity
+//! they cover and replays them against the current policies.
+
+mod activity;
+mod analytics;
+mod coverage;
+mod join;
+mod locations;
+mod replay;
+mod span;
+mod store;
+mod summary;
+
+pub use activity::scan_session;
+pub use analytics::{compute_policy_analytics, AnalyticsFilter, PolicyAnalytics};
+pub use coverage::{compute_coverage, CoverageReport};
+pub use join::{evaluations_for_event, link_session_evaluations, PolicyEventLink};
+pub use locations::{is_span_file, telemetry_locations, TelemetrySource};
+pub use replay::{
+    events_from_evaluations, events_from_session, find_cupcake, replay_events, ReplayReport,
+};
+pub use span::CupcakeSpan;
+pub use store::{
+    load_span, load_span_with_raw, EvaluationPage, EvaluationQuery, LoadedSpan, TelemetryStore,
+};
@@ -0,0 +1,482 @@
+//! Policy replay.
+//!
+//! Re-runs recorded hook events through the locally installed `cupcake`
+//! binary, with the project's current policies, and compares the new
+//! decisions with the recorded ones. Events come either from telemetry spans
+//! (their `raw_event`) or are rebuilt from the tool_use entries of a session.
+//!
+//! Cupcake treats replayed events like any other, so with telemetry enabled
+//! each replay also writes new spans. Replayed events carry `REPLAY_MARKER`,
+//! which ends up in the span's `raw_event`, and the telemetry index skips
+//! those spans so replays never show up as real evaluations.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::activity::scan_session;
+use super::store::load_span;
+use crate::claude_code::PolicyEvaluation;
+use crate::path_search::find_executable;
+
+/// Arguments for evaluating a Claude Code hook event read from stdin.
+const EVAL_ARGS: [&str; 3] = ["eval", "--harness", "claude"];
+
+/// Decision when cupcake's response doesn't object.
+const DEFAULT_DECISION: &str = "Allow";
+
+/// Field added to replayed hook events so the spans they produce can be told
+/// apart from real evaluations.
+const REPLAY_MARKER: &str = "agent_console_replay";
+
+/// Longest a single `cupcake eval` may run before it is killed.
+const EVAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a running evaluation is checked for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Where a replayed event came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplaySource {
+    /// The `raw_event` of a telemetry span
+    Telemetry,
+    /// A tool_use entry in a session file
+    Session,
+}
+
+/// A hook event to replay.
+#[derive(Debug, Clone)]
+pub struct ReplayEvent {
+    pub source: ReplaySource,
+    /// Hook event JSON as Claude Code sends it
+    pub raw_event: Value,
+    /// Decision recorded when the event first happened
+    pub recorded_decision: Option<String>,
+    /// Telemetry file the event came from
+    pub filename: Option<String>,
+    /// Session line, or line of a JSONL telemetry stream, the event came from
+    pub line: Option<u32>,
+    pub timestamp: Option<String>,
+}
+
+/// Outcome of replaying one event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    pub source: ReplaySource,
+    /// Telemetry file the event came from
+    pub filename: Option<String>,
+    /// Session line, or line of a JSONL telemetry stream, the event came from
+    pub line: Option<u32>,
+    pub timestamp: Option<String>,
+    pub event_type: Option<String>,
+    pub tool_name: Option<String>,
+    pub tool_use_id: Option<String>,
+    /// Decision recorded when the event first happened (None if it was
+    /// never evaluated)
+    pub recorded_decision: Option<String>,
+    /// Decision from the current policies (None if the replay failed)
+    pub replayed_decision: Option<String>,
+    pub replayed_reason: Option<String>,
+    /// Whether the current policies decide differently from the recorded
+    /// decision
+    pub changed: bool,
+    /// Whether the event had no recorded decision and was evaluated for the
+    /// first time
+    pub newly_evaluated: bool,
+    pub error: Option<String>,
+}
+
+/// Results of a replay run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    /// The `cupcake` binary that was used
+    pub cupcake_path: String,
+    pub total: u32,
+    /// Number of events whose decision changed
+    pub changed: u32,
+    /// Number of events evaluated for the first time
+    pub newly_evaluated: u32,
+    /// Number of events that couldn't be replayed
+    pub errors: u32,
+    pub results: Vec<ReplayResult>,
+}
+
+/// Find the `cupcake` binary on PATH.
+pub fn find_cupcake() -> Option<PathBuf> {
+    find_executable("cupcake")
+}
+
+/// Build replay events from the raw events of telemetry spans.
+///
+/// Spans that can no longer be read are skipped.
+pub fn events_from_evaluations(evaluations: &[PolicyEvaluation]) -> Vec<ReplayEvent> {
+    evaluations
+        .iter()
+        .filter_map(|evaluation| {
+            let span = load_span(evaluation).ok()??;
+            Some(ReplayEvent {
+                source: ReplaySource::Telemetry,
+                raw_event: span.raw_event,
+                recorded_decision: evaluation.decision.clone(),
+                filename: Some(evaluation.filename.clone()),
+                line: evaluation.line,
+                timestamp: Some(evaluation.timestamp.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Rebuild PreToolUse hook events from the tool_use entries of a session.
+///
+/// The recorded decision is taken from the PreToolUse evaluation of the same
+/// tool call, when there is one.
+pub fn events_from_session(
+    session_file: &Path,
+    project_path: &str,
+    session_id: &str,
+    evaluations: &[PolicyEvaluation],
+) -> Result<Vec<ReplayEvent>, String> {
+    let recorded: HashMap<&str, &PolicyEvaluation> = evaluations
+        .iter()
+        .filter(|e| e.event_type.as_deref() == Some("PreToolUse"))
+        .filter_map(|e| Some((e.tool_use_id.as_deref()?, e)))
+        .collect();
+
+    let activity = scan_session(session_file)?;
+    let events = activity
+        .tool_uses
+        .into_iter()
+        .map(|tool_use| {
+            let recorded = recorded.get(tool_use.id.as_str());
+            let raw_event = serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": session_id,
+                "transcript_path": session_file.to_string_lossy(),
+                "cwd": tool_use.cwd.as_deref().unwrap_or(project_path),
+                "tool_name": tool_use.name,
+                "tool_input": tool_use.input,
+                "tool_use_id": tool_use.id,
+            });
+
+            ReplayEvent {
+                source: ReplaySource::Session,
+                raw_event,
+                recorded_decision: recorded.and_then(|e| e.decision.clone()),
+                filename: recorded.map(|e| e.filename.clone()),
+                line: Some(tool_use.line),
+                timestamp: tool_use.timestamp,
+            }
+        })
+        .collect();
+
+    Ok(events)
+}
+
+/// Replay events through `cupcake` from the project directory (so project
+/// policies apply) and diff the decisions.
+pub fn replay_events(cupcake: &Path, project_path: &str, events: Vec<ReplayEvent>) -> ReplayReport {
+    let results: Vec<ReplayResult> = events
+        .into_iter()
+        .map(|event| {
+            let field = |key: &str| {
+                event
+                    .raw_event
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+            };
+            let (replayed_decision, replayed_reason, error) =
+                match run_cupcake(cupcake, project_path, &event.raw_event) {
+                    Ok((decision, reason)) => (Some(decision), reason, None),
+                    Err(e) => (None, None, Some(e)),
+                };
+            let (changed, newly_evaluated) = match (&event.recorded_decision, &replayed_decision) {
+                (Some(recorded), Some(replayed)) => (recorded != replayed, false),
+                (None, Some(_)) => (false, true),
+                (_, None) => (false, false),
+            };
+
+            ReplayResult {
+                source: event.source,
+                event_type: field("hook_event_name"),
+                tool_name: field("tool_name"),
+                tool_use_id: field("tool_use_id"),
+                filename: event.filename,
+                line: event.line,
+                timestamp: event.timestamp,
+                recorded_decision: event.recorded_decision,
+                replayed_decision,
+                replayed_reason,
+                changed,
+                newly_evaluated,
+                error,
+            }
+        })
+        .collect();
+
+    ReplayReport {
+        cupcake_path: cupcake.to_string_lossy().to_string(),
+        total: results.len() as u32,
+        changed: results.iter().filter(|r| r.changed).count() as u32,
+        newly_evaluated: results.iter().filter(|r| r.newly_evaluated).count() as u32,
+        errors: results.iter().filter(|r| r.error.is_some()).count() as u32,
+        results,
+    }
+}
+
+/// Check whether a span was produced by a replay.
+pub fn is_replay_event(raw_event: &Value) -> bool {
+    raw_event.get(REPLAY_MARKER).is_some()
+}
+
+/// Evaluate one hook event. Returns the decision and its reason.
+fn run_cupcake(
+    cupcake: &Path,
+    project_path: &str,
+    raw_event: &Value,
+) -> Result<(String, Option<String>), String> {
+    run_cupcake_with_timeout(cupcake, project_path, raw_event, EVAL_TIMEOUT)
+}
+
+fn run_cupcake_with_timeout(
+    cupcake: &Path,
+    project_path: &str,
+    raw_event: &Value,
+    timeout: Duration,
+) -> Result<(String, Option<String>), String> {
+    let mut event = raw_event.clone();
+    if let Value::Object(fields) = &mut event {
+        fields.insert(REPLAY_MARKER.to_string(), Value::Bool(true));
+    }
+
+    let mut child = Command::new(cupcake)
+        .args(EVAL_ARGS)
+        .current_dir(project_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run cupcake: {}", e))?;
+
+    // Feed stdin and drain the output on their own threads so a child that
+    // stops reading (or fills a pipe) can't block us past the deadline
+    let stdin = child.stdin.take().map(|mut stdin| {
+        let input = event.to_string();
+        thread::spawn(move || stdin.write_all(input.as_bytes()))
+    });
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let status = wait_with_timeout(&mut child, timeout)?;
+    if let Some(stdin) = stdin {
+        // A child may exit without reading its input; only its answer matters
+        let _ = stdin.join();
+    }
+    let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        let bytes = reader.and_then(|r| r.join().ok()).unwrap_or_default();
+        String::from_utf8_lossy(&bytes).trim().to_string()
+    };
+    let (stdout, stderr) = (collect(stdout), collect(stderr));
+
+    match status.code() {
+        Some(0) => Ok(parse_hook_response(&stdout)),
+        // Claude Code treats exit code 2 as a block with the reason on stderr
+        Some(2) => Ok(("Block".to_string(), Some(stderr).filter(|s| !s.is_empty()))),
+        _ => Err(format!("cupcake exited with {}: {}", status, stderr)),
+    }
+}
+
+/// Read a pipe to the end on a background thread.
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+/// Wait for a child to exit, killing it once `timeout` has passed.
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<ExitStatus, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("cupcake timed out after {:?}", timeout));
+            }
+            Err(e) => return Err(format!("Failed to run cupcake: {}", e)),
+        }
+    }
+}
+
+/// Map a Claude Code hook response to a Cupcake decision name and reason.
+fn parse_hook_response(stdout: &str) -> (String, Option<String>) {
+    let Ok(response) = serde_json::from_str::<Value>(stdout.trim()) else {
+        return (DEFAULT_DECISION.to_string(), None);
+    };
+    let text = |value: Option<&Value>| value.and_then(|v| v.as_str()).map(String::from);
+
+    if response.get("continue").and_then(|c| c.as_bool()) == Some(false) {
+        return ("Halt".to_string(), text(response.get("stopReason")));
+    }
+
+    if let Some(output) = response.get("hookSpecificOutput") {
+        let reason = text(output.get("permissionDecisionReason"));
+        match output.get("permissionDecision").and_then(|d| d.as_str()) {
+            Some("deny") => return ("Deny".to_string(), reason),
+            Some("ask") => return ("Ask".to_string(), reason),
+            _ if output.get("updatedInput").is_some() => return ("Modify".to_string(), reason),
+            _ => {}
+        }
+    }
+
+    if response.get("decision").and_then(|d| d.as_str()) == Some("block") {
+        return ("Block".to_string(), text(response.get("reason")));
+    }
+
+    (DEFAULT_DECISION.to_string(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_hook_response() {
+        let deny = r#"{"hookSpecificOutput": {"hookEventName": "PreToolUse", "permissionDecision": "deny", "permissionDecisionReason": "no rm"}}"#;
+        assert_eq!(
+            parse_hook_response(deny),
+            ("Deny".to_string(), Some("no rm".to_string()))
+        );
+        assert_eq!(
+            parse_hook_response(r#"{"continue": false, "stopReason": "halted"}"#),
+            ("Halt".to_string(), Some("halted".to_string()))
+        );
+        assert_eq!(
+            parse_hook_response(r#"{"decision": "block", "reason": "nope"}"#).0,
+            "Block"
+        );
+        assert_eq!(parse_hook_response("").0, "Allow");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replays_session_through_stub_cupcake() {
+        use crate::session_index::test_util::write_session;
+        use std::os::unix::fs::PermissionsExt;
+
+        let root =
+            std::env::temp_dir().join(format!("agent-console-replay-{}", std::process::id()));
+        let bin = root.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+
+        // Denies anything mentioning rm, allows the rest
+        let stub = bin.join("cupcake");
+        fs::write(
+            &stub,
+            "#!/bin/sh\n\
+             [ \"$1 $2 $3\" = \"eval --harness claude\" ] || exit 64\n\
+             input=$(cat)\n\
+             echo \"$input\" | grep -q agent_console_replay || exit 65\n\
+             if echo \"$input\" | grep -q 'rm -rf'; then\n\
+               echo '{\"hookSpecificOutput\":{\"permissionDecision\":\"deny\",\"permissionDecisionReason\":\"no rm\"}}'\n\
+             fi\n",
+        )
+        .unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path_var = std::env::join_paths([root.join("missing"), bin.clone()]).unwrap();
+        let cupcake = crate::path_search::find_in_path("cupcake", &path_var).unwrap();
+        assert_eq!(cupcake, stub);
+
+        let lines = [
+            serde_json::json!({"type": "user", "uuid": "u1", "message": {"content": "clean up"}}),
+            serde_json::json!({"type": "assistant", "uuid": "a1", "message": {"content": [
+                {"type": "tool_use", "id": "toolu_1", "name": "Bash", "input": {"command": "ls"}},
+                {"type": "tool_use", "id": "toolu_2", "name": "Bash", "input": {"command": "rm -rf build"}},
+            ]}}),
+        ];
+        let session = write_session("replay", &lines);
+
+        let project_path = root.to_string_lossy().to_string();
+        // Only the second call was evaluated when it happened
+        let recorded = PolicyEvaluation {
+            filename: "spans.jsonl#7".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            event_type: Some("PreToolUse".to_string()),
+            tool_name: Some("Bash".to_string()),
+            decision: Some("Allow".to_string()),
+            duration_ms: 1,
+            trace_id: "trace".to_string(),
+            session_id: Some("s1".to_string()),
+            tool_use_id: Some("toolu_2".to_string()),
+            reason: None,
+            source: crate::policy::TelemetrySource::Project,
+            path: String::new(),
+            line: Some(7),
+            offset: None,
+        };
+        let events = events_from_session(session.path(), &project_path, "s1", &[recorded]).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].line, Some(1));
+        assert_eq!(events[1].raw_event["tool_input"]["command"], "rm -rf build");
+        assert_eq!(events[1].recorded_decision.as_deref(), Some("Allow"));
+
+        let report = replay_events(&cupcake, &project_path, events);
+        assert_eq!(report.total, 2);
+        assert_eq!(report.errors, 0);
+        assert_eq!(report.changed, 1);
+        assert_eq!(report.newly_evaluated, 1);
+        assert_eq!(
+            report.results[0].replayed_decision.as_deref(),
+            Some("Allow")
+        );
+        // Nothing was recorded for the first call, so it can't have changed
+        assert!(!report.results[0].changed);
+        assert!(report.results[0].newly_evaluated);
+        assert!(report.results[1].changed);
+        assert_eq!(report.results[1].replayed_decision.as_deref(), Some("Deny"));
+        assert_eq!(report.results[1].replayed_reason.as_deref(), Some("no rm"));
+        assert_eq!(report.results[1].tool_use_id.as_deref(), Some("toolu_2"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hung_cupcake_is_killed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root =
+            std::env::temp_dir().join(format!("agent-console-replay-hang-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let stub = root.join("cupcake");
+        fs::write(&stub, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let event = serde_json::json!({"hook_event_name": "PreToolUse"});
+        let started = Instant::now();
+        let result = run_cupcake_with_timeout(
+            &stub,
+            &root.to_string_lossy(),
+            &event,
+            Duration::from_millis(200),
+        );
+        assert!(result.unwrap_err().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,533 @@
+//! Typed model of a CupcakeSpan.
+//!
+//! Mirrors the span types in the frontend's `types.ts`, using Cupcake's own
+//! snake_case field names. Every field has a default (also used for an
+//! explicit `null`) and unknown fields are ignored, so spans from older or
+//! newer Cupcake versions still parse. Final
+//! decisions with a variant we don't know about are kept as
+//! `FinalDecision::Unknown`, and the numbers listings rely on accept floats
+//! and strings, rather than failing the whole span.
+
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Root span for a complete policy evaluation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CupcakeSpan {
+    #[serde(deserialize_with = "null_as_default")]
+    pub span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub trace_id: String,
+    /// Start time in nanoseconds since the Unix epoch
+    #[serde(deserialize_with = "null_as_default")]
+    pub start_time_unix_nano: u64,
+    /// End time in nanoseconds since the Unix epoch
+    #[serde(deserialize_with = "null_as_default")]
+    pub end_time_unix_nano: u64,
+    /// Hook event as received from the harness
+    pub raw_event: Value,
+    /// Harness that generated the event (e.g., "ClaudeCode")
+    #[serde(deserialize_with = "null_as_default")]
+    pub harness: String,
+    /// Timestamp (ISO 8601)
+    #[serde(deserialize_with = "null_as_default")]
+    pub timestamp: String,
+    pub enrich: Option<EnrichPhase>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub phases: Vec<PolicyPhase>,
+    /// Response returned to the harness
+    pub response: Option<Value>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub errors: Vec<String>,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub total_duration_ms: u64,
+}
+
+/// Enrichment phase (preprocessing/normalization of the event).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnrichPhase {
+    #[serde(deserialize_with = "null_as_default")]
+    pub span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub parent_span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub start_time_unix_nano: u64,
+    #[serde(deserialize_with = "null_as_default")]
+    pub end_time_unix_nano: u64,
+    pub enriched_event: Value,
+    #[serde(deserialize_with = "null_as_default")]
+    pub operations: Vec<String>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub duration_us: f64,
+}
+
+/// A single policy evaluation phase (global, catalog, project).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolicyPhase {
+    #[serde(deserialize_with = "null_as_default")]
+    pub span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub parent_span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub start_time_unix_nano: u64,
+    #[serde(deserialize_with = "null_as_default")]
+    pub end_time_unix_nano: u64,
+    /// Phase name (e.g., "global", "project", "catalog:xyz")
+    #[serde(deserialize_with = "null_as_default")]
+    pub name: String,
+    pub signals: Option<SignalsPhase>,
+    pub evaluation: Option<EvaluationResult>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub duration_ms: f64,
+}
+
+/// Signal executions for a policy phase.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignalsPhase {
+    #[serde(deserialize_with = "null_as_default")]
+    pub span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub parent_span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub start_time_unix_nano: u64,
+    #[serde(deserialize_with = "null_as_default")]
+    pub end_time_unix_nano: u64,
+    #[serde(deserialize_with = "null_as_default")]
+    pub duration_ms: f64,
+    #[serde(deserialize_with = "null_as_default")]
+    pub signals: Vec<SignalExecution>,
+}
+
+/// A single signal execution.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignalExecution {
+    #[serde(deserialize_with = "null_as_default")]
+    pub name: String,
+    /// Command that was executed
+    #[serde(deserialize_with = "null_as_default")]
+    pub command: String,
+    /// Result value (JSON)
+    pub result: Value,
+    pub duration_ms: Option<f64>,
+    #[serde(deserialize_with = "lenient_i32")]
+    pub exit_code: Option<i32>,
+}
+
+/// Evaluation result for a policy phase.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvaluationResult {
+    #[serde(deserialize_with = "null_as_default")]
+    pub span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub parent_span_id: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub start_time_unix_nano: u64,
+    #[serde(deserialize_with = "null_as_default")]
+    pub end_time_unix_nano: u64,
+    /// Whether the event was routed to policies
+    #[serde(deserialize_with = "null_as_default")]
+    pub routed: bool,
+    #[serde(deserialize_with = "null_as_default")]
+    pub matched_policies: Vec<String>,
+    /// Reason for an early exit
+    pub exit_reason: Option<String>,
+    pub wasm_decision_set: Option<DecisionSet>,
+    pub final_decision: Option<FinalDecision>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub duration_ms: f64,
+}
+
+/// Decisions returned by the WASM policies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecisionSet {
+    #[serde(deserialize_with = "null_as_default")]
+    pub halts: Vec<DecisionResult>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub denials: Vec<DecisionResult>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub blocks: Vec<DecisionResult>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub asks: Vec<DecisionResult>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub modifications: Vec<Value>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub add_context: Vec<Value>,
+}
+
+impl DecisionSet {
+    /// Every decision with the decision type it represents, most severe first.
+    pub fn decisions(&self) -> impl Iterator<Item = (&'static str, &DecisionResult)> {
+        self.halts
+            .iter()
+            .map(|r| ("Halt", r))
+            .chain(self.denials.iter().map(|r| ("Deny", r)))
+            .chain(self.blocks.iter().map(|r| ("Block", r)))
+            .chain(self.asks.iter().map(|r| ("Ask", r)))
+    }
+}
+
+/// A single decision (halt, deny, block, ask).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecisionResult {
+    /// Rule ID that produced the decision
+    #[serde(deserialize_with = "null_as_default")]
+    pub rule_id: String,
+    /// Policy (Rego package) the rule belongs to
+    #[serde(alias = "package")]
+    pub policy: Option<String>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub reason: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub severity: String,
+}
+
+/// Body of an Allow decision.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AllowDecision {
+    #[serde(deserialize_with = "null_as_default")]
+    pub context: Vec<String>,
+}
+
+/// Body of a Deny, Block, Halt or Ask decision.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlockingDecision {
+    #[serde(deserialize_with = "null_as_default")]
+    pub reason: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub agent_messages: Vec<String>,
+}
+
+/// Body of a Modify decision.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModifyDecision {
+    #[serde(deserialize_with = "null_as_default")]
+    pub reason: String,
+    pub updated_input: Value,
+    #[serde(deserialize_with = "null_as_default")]
+    pub agent_messages: Vec<String>,
+}
+
+/// Final decision of an evaluation, serialized as a tagged union like
+/// `{"Deny": {"reason": ...}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FinalDecision {
+    Allow(AllowDecision),
+    Deny(BlockingDecision),
+    Block(BlockingDecision),
+    Halt(BlockingDecision),
+    Ask(BlockingDecision),
+    Modify(ModifyDecision),
+    /// A decision type this version doesn't know about
+    Unknown {
+        name: String,
+        body: Value,
+    },
+}
+
+impl FinalDecision {
+    /// Decision type name (e.g., "Deny").
+    pub fn name(&self) -> &str {
+        match self {
+            FinalDecision::Allow(_) => "Allow",
+            FinalDecision::Deny(_) => "Deny",
+            FinalDecision::Block(_) => "Block",
+            FinalDecision::Halt(_) => "Halt",
+            FinalDecision::Ask(_) => "Ask",
+            FinalDecision::Modify(_) => "Modify",
+            FinalDecision::Unknown { name, .. } => name,
+        }
+    }
+
+    /// Reason given for the decision, if any.
+    pub fn reason(&self) -> Option<&str> {
+        let reason = match self {
+            FinalDecision::Allow(_) => None,
+            FinalDecision::Deny(d)
+            | FinalDecision::Block(d)
+            | FinalDecision::Halt(d)
+            | FinalDecision::Ask(d) => Some(d.reason.as_str()),
+            FinalDecision::Modify(d) => Some(d.reason.as_str()),
+            FinalDecision::Unknown { body, .. } => body.get("reason").and_then(|r| r.as_str()),
+        };
+        reason.filter(|r| !r.is_empty())
+    }
+
+    /// Parse a tagged-union decision value. Returns None if the value isn't
+    /// an object with at least one key.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let (name, body) = value.as_object()?.iter().next()?;
+        let unknown = || FinalDecision::Unknown {
+            name: name.clone(),
+            body: body.clone(),
+        };
+        let parsed = match name.as_str() {
+            "Allow" => serde_json::from_value(body.clone()).map(FinalDecision::Allow),
+            "Deny" => serde_json::from_value(body.clone()).map(FinalDecision::Deny),
+            "Block" => serde_json::from_value(body.clone()).map(FinalDecision::Block),
+            "Halt" => serde_json::from_value(body.clone()).map(FinalDecision::Halt),
+            "Ask" => serde_json::from_value(body.clone()).map(FinalDecision::Ask),
+            "Modify" => serde_json::from_value(body.clone()).map(FinalDecision::Modify),
+            _ => return Some(unknown()),
+        };
+        // A known decision with an unexpected body keeps its name
+        Some(parsed.unwrap_or_else(|_| unknown()))
+    }
+}
+
+impl<'de> Deserialize<'de> for FinalDecision {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        FinalDecision::from_value(&value)
+            .ok_or_else(|| de::Error::custom("expected a tagged final decision"))
+    }
+}
+
+impl Serialize for FinalDecision {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self {
+            FinalDecision::Allow(d) => map.serialize_entry("Allow", d)?,
+            FinalDecision::Deny(d) => map.serialize_entry("Deny", d)?,
+            FinalDecision::Block(d) => map.serialize_entry("Block", d)?,
+            FinalDecision::Halt(d) => map.serialize_entry("Halt", d)?,
+            FinalDecision::Ask(d) => map.serialize_entry("Ask", d)?,
+            FinalDecision::Modify(d) => map.serialize_entry("Modify", d)?,
+            FinalDecision::Unknown { name, body } => map.serialize_entry(name, body)?,
+        }
+        map.end()
+    }
+}
+
+/// Deserialize a field that may be `null` as its default.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// A number that may have been written as a float or a string.
+fn lenient_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Deserialize a count, truncating floats; anything unreadable is 0.
+fn lenient_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    Ok(value
+        .as_u64()
+        .or_else(|| lenient_number(&value).map(|n| n.max(0.0) as u64))
+        .unwrap_or(0))
+}
+
+/// Deserialize an exit code; anything that isn't a whole number in range is
+/// None.
+fn lenient_i32<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i32>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    Ok(lenient_number(&value)
+        .filter(|n| n.fract() == 0.0 && *n >= i32::MIN as f64 && *n <= i32::MAX as f64)
+        .map(|n| n as i32))
+}
+
+impl CupcakeSpan {
+    /// Read a string field of the raw hook event (e.g., "tool_name").
+    pub fn event_field(&self, key: &str) -> Option<&str> {
+        self.raw_event.get(key).and_then(|v| v.as_str())
+    }
+
+    /// The decision returned to the harness, falling back to the last
+    /// phase's final decision.
+    pub fn final_decision(&self) -> Option<FinalDecision> {
+        self.response
+            .as_ref()
+            .and_then(|r| r.get("decision"))
+            .and_then(FinalDecision::from_value)
+            .or_else(|| {
+                self.phases
+                    .last()
+                    .and_then(|phase| phase.evaluation.as_ref())
+                    .and_then(|eval| eval.final_decision.clone())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_span_with_unknown_fields_and_variants() {
+        let span: CupcakeSpan = serde_json::from_value(serde_json::json!({
+            "span_id": "root",
+            "trace_id": "trace-1",
+            "harness": "ClaudeCode",
+            "timestamp": "2025-01-01T00:00:00Z",
+            "raw_event": {"hook_event_name": "PreToolUse", "tool_name": "Bash"},
+            "total_duration_ms": 12,
+            "some_future_field": {"nested": true},
+            "phases": [
+                {
+                    "name": "global",
+                    "duration_ms": 1.5,
+                    "signals": {"signals": [{"name": "git", "command": "git status", "result": "clean", "exit_code": 0}]},
+                    "evaluation": {
+                        "routed": true,
+                        "matched_policies": ["global.deny_rm"],
+                        "wasm_decision_set": {"denials": [{"rule_id": "RM-1", "reason": "no rm"}]},
+                        "final_decision": {"Deny": {"reason": "no rm", "agent_messages": []}}
+                    }
+                },
+                {
+                    "name": "project",
+                    "evaluation": {"final_decision": {"Escalate": {"reason": "page someone"}}}
+                }
+            ],
+            "response": null
+        }))
+        .unwrap();
+
+        assert_eq!(span.event_field("tool_name"), Some("Bash"));
+        assert_eq!(span.phases.len(), 2);
+        let global = span.phases[0].evaluation.as_ref().unwrap();
+        assert_eq!(
+            global.final_decision.as_ref().unwrap().reason(),
+            Some("no rm")
+        );
+        let denials: Vec<(&str, &str)> = global
+            .wasm_decision_set
+            .as_ref()
+            .unwrap()
+            .decisions()
+            .map(|(decision, result)| (decision, result.rule_id.as_str()))
+            .collect();
+        assert_eq!(denials, vec![("Deny", "RM-1")]);
+        let signals = span.phases[0].signals.as_ref().unwrap();
+        assert_eq!(signals.signals[0].exit_code, Some(0));
+
+        // An unknown decision variant is kept, and round-trips
+        let last = span.final_decision().unwrap();
+        assert_eq!(last.name(), "Escalate");
+        assert_eq!(last.reason(), Some("page someone"));
+        assert_eq!(
+            serde_json::to_value(&last).unwrap(),
+            serde_json::json!({"Escalate": {"reason": "page someone"}})
+        );
+
+        // The harness response takes precedence over the phases
+        let mut span = span;
+        span.response = Some(serde_json::json!({"decision": {"Allow": {"context": ["ok"]}}}));
+        assert_eq!(
+            span.final_decision(),
+            Some(FinalDecision::Allow(AllowDecision {
+                context: vec!["ok".to_string()]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_lenient_numbers_keep_the_span() {
+        let span: CupcakeSpan = serde_json::from_value(serde_json::json!({
+            "timestamp": "2025-01-01T00:00:00Z",
+            "total_duration_ms": 12.7,
+            "phases": [{"signals": {"signals": [
+                {"name": "a", "exit_code": "1"},
+                {"name": "b", "exit_code": 2.0},
+                {"name": "c", "exit_code": "killed"},
+                {"name": "d", "exit_code": null}
+            ]}}]
+        }))
+        .unwrap();
+        assert_eq!(span.total_duration_ms, 12);
+
+        let exit_codes: Vec<Option<i32>> = span.phases[0]
+            .signals
+            .as_ref()
+            .unwrap()
+            .signals
+            .iter()
+            .map(|s| s.exit_code)
+            .collect();
+        assert_eq!(exit_codes, [Some(1), Some(2), None, None]);
+
+        let span: CupcakeSpan =
+            serde_json::from_value(serde_json::json!({"total_duration_ms": "30"})).unwrap();
+        assert_eq!(span.total_duration_ms, 30);
+        let span: CupcakeSpan =
+            serde_json::from_value(serde_json::json!({"total_duration_ms": null})).unwrap();
+        assert_eq!(span.total_duration_ms, 0);
+    }
+
+    #[test]
+    fn test_nulls_read_as_defaults() {
+        let ids = serde_json::json!({"span_id": null, "parent_span_id": null,
+            "start_time_unix_nano": null, "end_time_unix_nano": null, "duration_ms": null});
+        let with_ids = |fields: Value| {
+            let mut object = ids.as_object().unwrap().clone();
+            object.extend(fields.as_object().unwrap().clone());
+            Value::Object(object)
+        };
+        let span: CupcakeSpan = serde_json::from_value(serde_json::json!({
+            "span_id": null, "trace_id": null, "start_time_unix_nano": null,
+            "end_time_unix_nano": null, "raw_event": null, "harness": null, "timestamp": null,
+            "enrich": with_ids(serde_json::json!({"enriched_event": null, "operations": null,
+                "duration_us": null})),
+            "phases": [with_ids(serde_json::json!({
+                "name": null,
+                "signals": with_ids(serde_json::json!({"signals": [
+                    {"name": null, "command": null, "result": null, "duration_ms": null,
+                     "exit_code": null}
+                ]})),
+                "evaluation": with_ids(serde_json::json!({
+                    "routed": null, "matched_policies": null, "exit_reason": null,
+                    "wasm_decision_set": {"halts": null, "blocks": null, "asks": null,
+                        "modifications": null, "add_context": null,
+                        "denials": [{"rule_id": null, "policy": null, "reason": null,
+                                     "severity": null}]},
+                    "final_decision": {"Deny": {"reason": null, "agent_messages": null}}
+                }))
+            }))],
+            "response": null, "errors": null, "total_duration_ms": null
+        }))
+        .unwrap();
+
+        assert!(span.span_id.is_empty() && span.harness.is_empty() && span.errors.is_empty());
+        assert_eq!(span.enrich.as_ref().unwrap().duration_us, 0.0);
+        let phase = &span.phases[0];
+        assert!(phase.name.is_empty());
+        assert_eq!(phase.signals.as_ref().unwrap().signals[0].exit_code, None);
+        let evaluation = phase.evaluation.as_ref().unwrap();
+        assert!(!evaluation.routed && evaluation.matched_policies.is_empty());
+        let decisions = evaluation.wasm_decision_set.as_ref().unwrap();
+        assert!(decisions.halts.is_empty() && decisions.denials[0].rule_id.is_empty());
+        assert_eq!(
+            evaluation.final_decision,
+            Some(FinalDecision::Deny(BlockingDecision::default()))
+        );
+        for body in [
+            serde_json::json!({"Allow": {"context": null}}),
+            serde_json::json!({"Modify": {"reason": null, "updated_input": null,
+                                          "agent_messages": null}}),
+        ] {
+            let decision = FinalDecision::from_value(&body).unwrap();
+            assert!(!matches!(decision, FinalDecision::Unknown { .. }));
+        }
+    }
+}
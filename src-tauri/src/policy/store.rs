@@ -0,0 +1,947 @@
+//! Incremental index of a project's policy telemetry.
+//!
+//! Telemetry locations can hold tens of thousands of spans. The index keeps
+//! the summaries per file and, on refresh, only parses files that are new or
+//! whose size/mtime changed. Span files are written once and JSONL streams
+//! are append-only, so an unchanged directory mtime (with no file left
+//! half-written) means nothing to do, and a grown stream only has its new
+//! lines parsed (after checking the part already parsed is unchanged).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata};
+use std::hash::Hasher;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::analytics::{in_range, parse_bound};
+use super::locations::{
+    is_span_file, is_span_stream, telemetry_locations, TelemetryLocation, TelemetrySource,
+};
+use super::replay::is_replay_event;
+use super::span::CupcakeSpan;
+use super::summary::TelemetrySummary;
+use crate::claude_code::PolicyEvaluation;
+
+/// Filter, sort and paging options for `TelemetryIndex::query`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationQuery {
+    /// Page start (number of evaluations to skip)
+    pub offset: Option<u32>,
+    /// Page size (defaults to 200)
+    pub limit: Option<u32>,
+    /// Only include this final decision (e.g., "Deny")
+    pub decision: Option<String>,
+    /// Only include this tool (e.g., "Bash")
+    pub tool_name: Option<String>,
+    /// Only include this hook event type (e.g., "PreToolUse")
+    pub event_type: Option<String>,
+    /// Only include evaluations from this Claude Code session
+    pub session_id: Option<String>,
+    /// Only include evaluations at or after this time (RFC 3339 or YYYY-MM-DD)
+    pub since: Option<String>,
+    /// Only include evaluations at or before this time (RFC 3339 or YYYY-MM-DD)
+    pub until: Option<String>,
+    /// Sort key (defaults to timestamp)
+    pub sort_by: Option<EvaluationSortKey>,
+    /// Sort ascending instead of descending (newest first)
+    pub ascending: Option<bool>,
+}
+
+/// Sort keys for evaluation queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvaluationSortKey {
+    #[default]
+    Timestamp,
+    DurationMs,
+    ToolName,
+    Decision,
+}
+
+/// A page of policy evaluations.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationPage {
+    /// Evaluations for the requested page
+    pub evaluations: Vec<PolicyEvaluation>,
+    /// Number of evaluations matching the filters
+    pub total_count: u32,
+    /// Offset of this page
+    pub offset: u32,
+    /// Whether there are more evaluations after this page
+    pub has_more: bool,
+}
+
+/// A span file as of the last refresh.
+struct IndexedFile {
+    modified: SystemTime,
+    size: u64,
+    /// Bytes consumed so far (a JSONL stream resumes from here)
+    parsed_len: u64,
+    /// Lines consumed so far (JSONL streams only)
+    parsed_lines: u32,
+    /// Checked before resuming a stream (JSONL streams only)
+    fingerprint: StreamFingerprint,
+    /// False if the file (or the end of a stream) couldn't be parsed, e.g.,
+    /// because it is still being written
+    complete: bool,
+    summaries: Vec<TelemetrySummary>,
+}
+
+/// (length, hash) of the first and last consumed lines of a stream.
+///
+/// Like the session index's `PrefixFingerprint`: a stream that was truncated
+/// and regrew, or was rotated, can be as large as what was parsed, so its
+/// size alone doesn't show that the parsed prefix is gone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct StreamFingerprint {
+    first_line: (u64, u64),
+    last_line: (u64, u64),
+}
+
+impl StreamFingerprint {
+    /// Check the consumed prefix of a stream (`parsed_len` bytes) is still
+    /// what was fingerprinted.
+    fn matches(&self, path: &Path, parsed_len: u64) -> bool {
+        if parsed_len == 0 {
+            return true;
+        }
+        let Ok(mut handle) = File::open(path) else {
+            return false;
+        };
+        let (first_len, _) = self.first_line;
+        let (last_len, _) = self.last_line;
+        let Some(last_start) = parsed_len.checked_sub(last_len) else {
+            return false;
+        };
+        read_line_at(&mut handle, 0, first_len).map(|l| line_fingerprint(&l))
+            == Some(self.first_line)
+            && read_line_at(&mut handle, last_start, last_len).map(|l| line_fingerprint(&l))
+                == Some(self.last_line)
+    }
+}
+
+/// (length, hash) of a line's raw bytes.
+fn line_fingerprint(line: &[u8]) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(line);
+    (line.len() as u64, hasher.finish())
+}
+
+fn read_line_at(handle: &mut File, offset: u64, length: u64) -> Option<Vec<u8>> {
+    handle.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buffer = vec![0u8; length as usize];
+    handle.read_exact(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+/// Summaries of every span in one telemetry location.
+struct LocationIndex {
+    location: TelemetryLocation,
+    /// Directory mtime at the last full scan
+    dir_modified: Option<SystemTime>,
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+impl LocationIndex {
+    fn new(location: TelemetryLocation) -> Self {
+        Self {
+            location,
+            dir_modified: None,
+            files: HashMap::new(),
+        }
+    }
+
+    fn refresh(&mut self) -> usize {
+        let metadata = match fs::metadata(&self.location.path) {
+            Ok(m) => m,
+            Err(_) => {
+                self.files.clear();
+                self.dir_modified = None;
+                return 0;
+            }
+        };
+
+        // A configured location can be a single span file
+        if metadata.is_file() {
+            let path = self.location.path.clone();
+            let existing = self.files.remove(&path);
+            self.files.clear();
+            let (file, parsed) = self.index_file(&path, &metadata, existing);
+            self.files.insert(path, file);
+            return parsed as usize;
+        }
+
+        let dir_modified = metadata.modified().ok();
+        let has_unparsed = self.files.values().any(|f| !f.complete);
+        if dir_modified.is_some() && dir_modified == self.dir_modified && !has_unparsed {
+            return 0;
+        }
+
+        let entries = match fs::read_dir(&self.location.path) {
+            Ok(e) => e,
+            Err(_) => {
+                self.files.clear();
+                self.dir_modified = None;
+                return 0;
+            }
+        };
+
+        let mut parsed = 0;
+        let mut seen: HashMap<PathBuf, IndexedFile> = HashMap::with_capacity(self.files.len());
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            // Only process .json and .jsonl files
+            if !is_span_file(&path) {
+                continue;
+            }
+
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+
+            let existing = self.files.remove(&path);
+            let (file, was_parsed) = self.index_file(&path, &metadata, existing);
+            if was_parsed {
+                parsed += 1;
+            }
+            seen.insert(path, file);
+        }
+
+        // Anything not seen this time was deleted
+        self.files = seen;
+        self.dir_modified = dir_modified;
+        parsed
+    }
+
+    /// Bring one file's entry up to date. Returns the entry and whether the
+    /// file had to be read.
+    fn index_file(
+        &self,
+        path: &Path,
+        metadata: &Metadata,
+        existing: Option<IndexedFile>,
+    ) -> (IndexedFile, bool) {
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let size = metadata.len();
+
+        // Reuse the summaries if the file is unchanged
+        if let Some(existing) = existing {
+            if existing.modified == modified && existing.size == size {
+                return (existing, false);
+            }
+            // A stream that only grew is parsed from where we left off
+            if is_span_stream(path)
+                && size >= existing.parsed_len
+                && existing.fingerprint.matches(path, existing.parsed_len)
+            {
+                return (self.parse_stream(path, modified, size, existing), true);
+            }
+        }
+
+        let mut file = IndexedFile {
+            modified,
+            size,
+            parsed_len: 0,
+            parsed_lines: 0,
+            fingerprint: StreamFingerprint::default(),
+            complete: false,
+            summaries: Vec::new(),
+        };
+        if is_span_stream(path) {
+            file = self.parse_stream(path, modified, size, file);
+        } else if let Some(span) = parse_span_file(path) {
+            file.parsed_len = size;
+            file.complete = true;
+            if self.keeps(&span) {
+                let filename = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                file.summaries
+                    .push(self.summarize(path, filename, None, &span));
+            }
+        }
+        (file, true)
+    }
+
+    /// Parse the lines of a `.jsonl` stream after `file.parsed_len`.
+    fn parse_stream(
+        &self,
+        path: &Path,
+        modified: SystemTime,
+        size: u64,
+        mut file: IndexedFile,
+    ) -> IndexedFile {
+        file.modified = modified;
+        file.size = size;
+        file.complete = false;
+
+        let Ok(mut handle) = File::open(path) else {
+            return file;
+        };
+        if handle.seek(SeekFrom::Start(file.parsed_len)).is_err() {
+            return file;
+        }
+        let mut tail = Vec::new();
+        if handle.read_to_end(&mut tail).is_err() {
+            return file;
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut consumed = 0;
+        let mut last_line = None;
+        for chunk in tail.split_inclusive(|&b| b == b'\n') {
+            // An unterminated last line is still being written, even if it
+            // already parses; leave it for the next refresh so its newline
+            // isn't counted as a line of its own
+            if !chunk.ends_with(b"\n") {
+                break;
+            }
+            let span = serde_json::from_slice::<CupcakeSpan>(chunk).ok();
+
+            let offset = file.parsed_len + consumed as u64;
+            consumed += chunk.len();
+            if file.parsed_lines == 0 {
+                file.fingerprint.first_line = line_fingerprint(chunk);
+            }
+            last_line = Some(chunk);
+            file.parsed_lines += 1;
+            if let Some(span) = span.filter(|span| self.keeps(span)) {
+                let line = file.parsed_lines;
+                let filename = format!("{}#{}", name, line);
+                file.summaries
+                    .push(self.summarize(path, filename, Some((line, offset)), &span));
+            }
+        }
+
+        if let Some(chunk) = last_line {
+            file.fingerprint.last_line = line_fingerprint(chunk);
+        }
+        file.parsed_len += consumed as u64;
+        file.complete = file.parsed_len == size;
+        file
+    }
+
+    /// Whether a span belongs in the index: spans written by our own replays
+    /// aren't real evaluations, and spans in a shared location may be from
+    /// another project.
+    fn keeps(&self, span: &CupcakeSpan) -> bool {
+        if is_replay_event(&span.raw_event) {
+            return false;
+        }
+        match &self.location.scope {
+            Some(scope) => span
+                .event_field("cwd")
+                .is_some_and(|cwd| Path::new(cwd).starts_with(scope)),
+            None => true,
+        }
+    }
+
+    /// Summarize a span; `line` is its (line, byte offset) in a stream.
+    fn summarize(
+        &self,
+        path: &Path,
+        filename: String,
+        line: Option<(u32, u64)>,
+        span: &CupcakeSpan,
+    ) -> TelemetrySummary {
+        let mut summary = TelemetrySummary::from_span(filename, span);
+        summary.evaluation.source = self.location.source;
+        summary.evaluation.path = path.to_string_lossy().to_string();
+        summary.evaluation.line = line.map(|(line, _)| line);
+        summary.evaluation.offset = line.map(|(_, offset)| offset);
+        summary
+    }
+
+    fn summaries(&self) -> impl Iterator<Item = &TelemetrySummary> {
+        self.files.values().flat_map(|f| f.summaries.iter())
+    }
+}
+
+/// Summaries of every span in a project's telemetry locations.
+pub struct TelemetryIndex {
+    locations: Vec<LocationIndex>,
+}
+
+impl TelemetryIndex {
+    pub fn new(locations: Vec<TelemetryLocation>) -> Self {
+        Self {
+            locations: locations.into_iter().map(LocationIndex::new).collect(),
+        }
+    }
+
+    /// Whether the index covers exactly these locations.
+    fn has_locations(&self, locations: &[TelemetryLocation]) -> bool {
+        self.locations.len() == locations.len()
+            && self
+                .locations
+                .iter()
+                .zip(locations)
+                .all(|(index, location)| &index.location == location)
+    }
+
+    /// Force the next refresh to rescan every location (e.g., after a
+    /// watcher reported an in-place change).
+    pub fn invalidate(&mut self) {
+        for location in &mut self.locations {
+            location.dir_modified = None;
+        }
+    }
+
+    /// Bring the index up to date with every location.
+    ///
+    /// Returns the number of files parsed.
+    pub fn refresh(&mut self) -> usize {
+        self.locations.iter_mut().map(|l| l.refresh()).sum()
+    }
+
+    /// Iterate over the summaries of every parsed span.
+    pub fn summaries(&self) -> impl Iterator<Item = &TelemetrySummary> {
+        self.locations.iter().flat_map(|l| l.summaries())
+    }
+
+    /// Find an evaluation by filename, in the file at `path` or, without a
+    /// path, in the project location.
+    pub fn find(&self, filename: &str, path: Option<&str>) -> Option<&PolicyEvaluation> {
+        self.summaries().map(|s| &s.evaluation).find(|e| {
+            e.filename == filename
+                && match path {
+                    Some(path) => e.path == path,
+                    None => e.source == TelemetrySource::Project,
+                }
+        })
+    }
+
+    /// Filter, sort and page the indexed evaluations.
+    pub fn query(&self, query: &EvaluationQuery) -> EvaluationPage {
+        let since = query.since.as_deref().and_then(|s| parse_bound(s, false));
+        let until = query.until.as_deref().and_then(|s| parse_bound(s, true));
+
+        let mut matching: Vec<&PolicyEvaluation> = self
+            .summaries()
+            .map(|s| &s.evaluation)
+            .filter(|e| matches_field(&query.decision, &e.decision))
+            .filter(|e| matches_field(&query.tool_name, &e.tool_name))
+            .filter(|e| matches_field(&query.event_type, &e.event_type))
+            .filter(|e| matches_field(&query.session_id, &e.session_id))
+            .filter(|e| in_range(parse_bound(&e.timestamp, false), since, until))
+            .collect();
+
+        let sort_by = query.sort_by.unwrap_or_default();
+        matching.sort_by(|a, b| {
+            let ordering = match sort_by {
+                EvaluationSortKey::Timestamp => a.timestamp.cmp(&b.timestamp),
+                EvaluationSortKey::DurationMs => a.duration_ms.cmp(&b.duration_ms),
+                EvaluationSortKey::ToolName => a.tool_name.cmp(&b.tool_name),
+                EvaluationSortKey::Decision => a.decision.cmp(&b.decision),
+            }
+            // Break ties by file so paging is stable
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.filename.cmp(&b.filename));
+            if query.ascending.unwrap_or(false) {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+
+        let total_count = matching.len() as u32;
+        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(200);
+        let evaluations: Vec<PolicyEvaluation> = matching
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect();
+        let has_more = (offset as usize + evaluations.len()) < total_count as usize;
+
+        EvaluationPage {
+            evaluations,
+            total_count,
+            offset,
+            has_more,
+        }
+    }
+}
+
+/// Parse a single-span `.json` file.
+fn parse_span_file(path: &Path) -> Option<CupcakeSpan> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn matches_field(wanted: &Option<String>, actual: &Option<String>) -> bool {
+    match wanted {
+        Some(wanted) => actual.as_ref() == Some(wanted),
+        None => true,
+    }
+}
+
+/// Read and parse an indexed span. Returns None if the file (or line) is
+/// gone.
+pub fn load_span(evaluation: &PolicyEvaluation) -> Result<Option<CupcakeSpan>, String> {
+    Ok(load_span_with_raw(evaluation)?.map(|loaded| loaded.span))
+}
+
+/// A span with the JSON it was parsed from, which keeps the fields the typed
+/// model doesn't know about.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedSpan {
+    pub span: CupcakeSpan,
+    pub raw: Value,
+}
+
+/// Load the typed span of an evaluation along with its raw JSON.
+pub fn load_span_with_raw(evaluation: &PolicyEvaluation) -> Result<Option<LoadedSpan>, String> {
+    let Some(text) = read_span(evaluation) else {
+        return Ok(None);
+    };
+    let parse_error = |e: serde_json::Error| format!("Failed to parse policy evaluation: {}", e);
+    let raw: Value = serde_json::from_str(&text).map_err(parse_error)?;
+    let span = CupcakeSpan::deserialize(&raw).map_err(parse_error)?;
+    Ok(Some(LoadedSpan { span, raw }))
+}
+
+/// Read the raw JSON of an indexed span, seeking straight to its line in a
+/// stream.
+fn read_span(evaluation: &PolicyEvaluation) -> Option<String> {
+    if evaluation.line.is_none() {
+        return fs::read_to_string(&evaluation.path).ok();
+    }
+    let mut file = File::open(&evaluation.path).ok()?;
+    file.seek(SeekFrom::Start(evaluation.offset?)).ok()?;
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line).ok()?;
+    let len = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(len);
+    Some(line)
+}
+
+/// A project's telemetry index.
+struct ProjectTelemetry {
+    index: Mutex<TelemetryIndex>,
+    /// Set by `invalidate` so it doesn't wait for a refresh in progress
+    stale: AtomicBool,
+}
+
+/// Telemetry indices for every project that has been queried.
+///
+/// Each project has its own lock, so refreshing one project's telemetry
+/// doesn't hold up queries (or watcher invalidations) for the others.
+#[derive(Default)]
+pub struct TelemetryStore {
+    indices: Mutex<HashMap<String, Arc<ProjectTelemetry>>>,
+}
+
+impl TelemetryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refresh a project's index and run `f` against it.
+    pub fn with_index<R>(
+        &self,
+        project_path: &str,
+        f: impl FnOnce(&TelemetryIndex) -> R,
+    ) -> Result<R, String> {
+        let project = {
+            let mut indices = self
+                .indices
+                .lock()
+                .map_err(|_| "Failed to lock telemetry indices".to_string())?;
+            Arc::clone(indices.entry(project_path.to_string()).or_insert_with(|| {
+                Arc::new(ProjectTelemetry {
+                    index: Mutex::new(TelemetryIndex::new(Vec::new())),
+                    stale: AtomicBool::new(false),
+                })
+            }))
+        };
+
+        // Locations can change with the config file
+        let locations = telemetry_locations(project_path);
+        let mut index = project
+            .index
+            .lock()
+            .map_err(|_| "Failed to lock telemetry index".to_string())?;
+        if !index.has_locations(&locations) {
+            *index = TelemetryIndex::new(locations);
+        }
+        if project.stale.swap(false, Ordering::SeqCst) {
+            index.invalidate();
+        }
+        index.refresh();
+        Ok(f(&index))
+    }
+
+    /// Every evaluation of a project, newest first.
+    pub fn evaluations(&self, project_path: &str) -> Result<Vec<PolicyEvaluation>, String> {
+        let query = EvaluationQuery {
+            limit: Some(u32::MAX),
+            ..Default::default()
+        };
+        self.with_index(project_path, |index| index.query(&query).evaluations)
+    }
+
+    /// Force a rescan of a project's telemetry on the next query.
+    pub fn invalidate(&self, project_path: &str) {
+        if let Ok(indices) = self.indices.lock() {
+            if let Some(project) = indices.get(project_path) {
+                project.stale.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::jsonl;
+    use serde_json::Value;
+
+    fn project_location(dir: &Path) -> TelemetryLocation {
+        TelemetryLocation {
+            source: TelemetrySource::Project,
+            path: dir.to_path_buf(),
+            scope: None,
+        }
+    }
+
+    fn span(timestamp: &str, tool: &str, decision: &str, ms: u64) -> Value {
+        serde_json::json!({
+            "timestamp": timestamp,
+            "trace_id": timestamp,
+            "total_duration_ms": ms,
+            "raw_event": {"hook_event_name": "PreToolUse", "tool_name": tool},
+            "response": {"decision": {decision: {}}},
+        })
+    }
+
+    fn write_span(dir: &Path, name: &str, timestamp: &str, tool: &str, decision: &str, ms: u64) {
+        let span = span(timestamp, tool, decision, ms);
+        fs::write(dir.join(name), span.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_refresh_only_parses_new_and_changed_files() {
+        let dir =
+            std::env::temp_dir().join(format!("agent-console-telemetry-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        write_span(&dir, "a.json", "2025-01-01T00:00:00Z", "Bash", "Allow", 5);
+        write_span(&dir, "b.json", "2025-01-02T00:00:00Z", "Edit", "Deny", 9);
+        fs::write(dir.join("partial.json"), "{\"timestamp\":").unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let mut index = TelemetryIndex::new(vec![project_location(&dir)]);
+        assert_eq!(index.refresh(), 3);
+        assert_eq!(index.summaries().count(), 2);
+
+        // The half-written file is retried; the parsed ones are not
+        write_span(
+            &dir,
+            "partial.json",
+            "2025-01-03T00:00:00Z",
+            "Bash",
+            "Deny",
+            7,
+        );
+        assert_eq!(index.refresh(), 1);
+        assert_eq!(index.summaries().count(), 3);
+        assert_eq!(index.refresh(), 0);
+
+        fs::remove_file(dir.join("a.json")).unwrap();
+        index.invalidate();
+        assert_eq!(index.refresh(), 0);
+        assert_eq!(index.summaries().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_query_filters_sorts_and_pages() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-console-telemetry-query-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        write_span(&dir, "1.json", "2025-01-01T00:00:00Z", "Bash", "Allow", 5);
+        write_span(&dir, "2.json", "2025-01-02T00:00:00Z", "Bash", "Deny", 30);
+        write_span(&dir, "3.json", "2025-01-03T00:00:00Z", "Edit", "Allow", 10);
+        write_span(&dir, "4.json", "2025-01-04T00:00:00Z", "Bash", "Allow", 20);
+
+        let mut index = TelemetryIndex::new(vec![project_location(&dir)]);
+        index.refresh();
+
+        let page = index.query(&EvaluationQuery {
+            limit: Some(2),
+            ..Default::default()
+        });
+        let names: Vec<&str> = page
+            .evaluations
+            .iter()
+            .map(|e| e.filename.as_str())
+            .collect();
+        assert_eq!(names, vec!["4.json", "3.json"]);
+        assert_eq!(page.total_count, 4);
+        assert!(page.has_more);
+
+        let page = index.query(&EvaluationQuery {
+            tool_name: Some("Bash".to_string()),
+            sort_by: Some(EvaluationSortKey::DurationMs),
+            offset: Some(1),
+            ..Default::default()
+        });
+        let names: Vec<&str> = page
+            .evaluations
+            .iter()
+            .map(|e| e.filename.as_str())
+            .collect();
+        assert_eq!(names, vec!["4.json", "1.json"]);
+        assert_eq!(page.total_count, 3);
+        assert!(!page.has_more);
+
+        let page = index.query(&EvaluationQuery {
+            decision: Some("Allow".to_string()),
+            since: Some("2025-01-02".to_string()),
+            ascending: Some(true),
+            ..Default::default()
+        });
+        let names: Vec<&str> = page
+            .evaluations
+            .iter()
+            .map(|e| e.filename.as_str())
+            .collect();
+        assert_eq!(names, vec!["3.json", "4.json"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_jsonl_streams_and_merged_locations() {
+        let root = std::env::temp_dir().join(format!(
+            "agent-console-telemetry-streams-{}",
+            std::process::id()
+        ));
+        let project = root.join("project");
+        let global = root.join("global");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(&global).unwrap();
+        write_span(
+            &project,
+            "a.json",
+            "2025-01-01T00:00:00Z",
+            "Bash",
+            "Allow",
+            5,
+        );
+
+        // Two complete spans and a half-written third
+        let stream = global.join("spans.jsonl");
+        let first = span("2025-01-02T00:00:00Z", "Edit", "Deny", 9);
+        let second = span("2025-01-03T00:00:00Z", "Bash", "Ask", 3);
+        fs::write(&stream, format!("{}\n{}\n{{\"timest", first, second)).unwrap();
+
+        let mut index = TelemetryIndex::new(vec![
+            project_location(&project),
+            TelemetryLocation {
+                source: TelemetrySource::Global,
+                path: global.clone(),
+                scope: None,
+            },
+        ]);
+        assert_eq!(index.refresh(), 2);
+        assert_eq!(index.summaries().count(), 3);
+
+        let evaluation = index
+            .find("spans.jsonl#2", Some(&stream.to_string_lossy()))
+            .unwrap();
+        assert_eq!(evaluation.source, TelemetrySource::Global);
+        assert_eq!(evaluation.decision.as_deref(), Some("Ask"));
+        assert_eq!(read_span(evaluation), Some(second.to_string()));
+        let loaded = load_span(evaluation).unwrap().unwrap();
+        assert_eq!(loaded.timestamp, "2025-01-03T00:00:00Z");
+        assert_eq!(load_span_with_raw(evaluation).unwrap().unwrap().raw, second);
+        assert!(index.find("spans.jsonl#2", None).is_none());
+        assert_eq!(
+            index.find("a.json", None).unwrap().source,
+            TelemetrySource::Project
+        );
+
+        // Finishing the stream only parses the new line
+        let third = span("2025-01-04T00:00:00Z", "Read", "Allow", 1);
+        let content = format!("{}\n{}\n{}\n", first, second, third);
+        fs::write(&stream, content).unwrap();
+        index.invalidate();
+        assert_eq!(index.refresh(), 1);
+        assert_eq!(index.summaries().count(), 4);
+        let lines: Vec<Option<u32>> = index
+            .query(&EvaluationQuery {
+                tool_name: Some("Read".to_string()),
+                ..Default::default()
+            })
+            .evaluations
+            .iter()
+            .map(|e| e.line)
+            .collect();
+        assert_eq!(lines, vec![Some(3)]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unterminated_line_waits_for_its_newline() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-console-telemetry-unterminated-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let stream = dir.join("spans.jsonl");
+        let first = span("2025-01-01T00:00:00Z", "Edit", "Deny", 9);
+        let second = span("2025-01-02T00:00:00Z", "Bash", "Ask", 3);
+        // The second span is flushed before its newline
+        fs::write(&stream, format!("{}\n{}", first, second)).unwrap();
+
+        let mut index = TelemetryIndex::new(vec![project_location(&dir)]);
+        index.refresh();
+        assert_eq!(index.summaries().count(), 1);
+
+        let third = span("2025-01-03T00:00:00Z", "Read", "Allow", 1);
+        fs::write(&stream, format!("{}\n{}\n{}\n", first, second, third)).unwrap();
+        index.invalidate();
+        index.refresh();
+        assert_eq!(index.summaries().count(), 3);
+
+        let location = stream.to_string_lossy();
+        let evaluation = index.find("spans.jsonl#2", Some(&location)).unwrap();
+        assert_eq!(evaluation.line, Some(2));
+        assert_eq!(evaluation.offset, Some(first.to_string().len() as u64 + 1));
+        assert_eq!(read_span(evaluation), Some(second.to_string()));
+        let evaluation = index.find("spans.jsonl#3", Some(&location)).unwrap();
+        assert_eq!(evaluation.tool_name.as_deref(), Some("Read"));
+        assert_eq!(read_span(evaluation), Some(third.to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rewritten_stream_is_reparsed() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-console-telemetry-rotated-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let stream = dir.join("spans.jsonl");
+        let old = span("2025-01-01T00:00:00Z", "Edit", "Deny", 9);
+        fs::write(&stream, format!("{}\n", old)).unwrap();
+
+        let mut index = TelemetryIndex::new(vec![project_location(&dir)]);
+        assert_eq!(index.refresh(), 1);
+
+        // Rotated: new content at least as long as what was parsed
+        let first = span("2025-02-01T00:00:00Z", "Bash", "Allow", 1);
+        let second = span("2025-02-02T00:00:00Z", "Read", "Ask", 2);
+        fs::write(&stream, format!("{}\n{}\n", first, second)).unwrap();
+        index.invalidate();
+        assert_eq!(index.refresh(), 1);
+
+        let mut tools: Vec<(Option<u32>, Option<String>)> = index
+            .summaries()
+            .map(|s| (s.evaluation.line, s.evaluation.tool_name.clone()))
+            .collect();
+        tools.sort();
+        assert_eq!(
+            tools,
+            vec![
+                (Some(1), Some("Bash".to_string())),
+                (Some(2), Some("Read".to_string())),
+            ]
+        );
+        let evaluation = index
+            .find("spans.jsonl#2", Some(&stream.to_string_lossy()))
+            .unwrap();
+        assert_eq!(read_span(evaluation), Some(second.to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_spans_are_skipped() {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-console-telemetry-replay-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let mut replayed = span("2025-01-02T00:00:00Z", "Bash", "Deny", 3);
+        replayed["raw_event"]["agent_console_replay"] = Value::Bool(true);
+        fs::write(dir.join("replayed.json"), replayed.to_string()).unwrap();
+        let real = span("2025-01-01T00:00:00Z", "Bash", "Allow", 5);
+        fs::write(dir.join("spans.jsonl"), format!("{}\n{}\n", replayed, real)).unwrap();
+
+        let mut index = TelemetryIndex::new(vec![project_location(&dir)]);
+        assert_eq!(index.refresh(), 2);
+        let lines: Vec<Option<u32>> = index.summaries().map(|s| s.evaluation.line).collect();
+        assert_eq!(lines, vec![Some(2)]);
+        // Skipped spans are done with, not retried
+        assert_eq!(index.refresh(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_shared_locations_are_scoped_to_the_project() {
+        let root = std::env::temp_dir().join(format!(
+            "agent-console-telemetry-scoped-{}",
+            std::process::id()
+        ));
+        let global = root.join("global");
+        fs::create_dir_all(&global).unwrap();
+        let in_project = |cwd: &str, tool: &str| {
+            let mut span = span("2025-01-01T00:00:00Z", tool, "Deny", 1);
+            span["raw_event"]["cwd"] = Value::String(cwd.to_string());
+            span
+        };
+        let spans = [
+            in_project("/work/app", "Bash"),
+            in_project("/work/app/src", "Edit"),
+            in_project("/work/api", "Read"),
+            // A sibling whose name starts with the project's
+            in_project("/work/app2", "Write"),
+            // No cwd: can't be attributed to any project
+            span("2025-01-01T00:00:00Z", "Grep", "Deny", 1),
+        ];
+        fs::write(global.join("spans.jsonl"), jsonl(&spans)).unwrap();
+
+        let tools = |project: &str| {
+            let mut index = TelemetryIndex::new(vec![TelemetryLocation {
+                source: TelemetrySource::Global,
+                path: global.clone(),
+                scope: Some(PathBuf::from(project)),
+            }]);
+            index.refresh();
+            let mut tools: Vec<String> = index
+                .summaries()
+                .filter_map(|s| s.evaluation.tool_name.clone())
+                .collect();
+            tools.sort();
+            tools
+        };
+        assert_eq!(tools("/work/app"), vec!["Bash", "Edit"]);
+        assert_eq!(tools("/work/api"), vec!["Read"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+//! Per-span telemetry summaries.
+//!
+//! Everything the list view, filters and analytics need from a CupcakeSpan,
+//! extracted once so the (large) span JSON doesn't need to be kept around.
+
+use std::collections::HashSet;
+
+use super::span::{CupcakeSpan, DecisionResult};
+use crate::claude_code::{parse_policy_evaluation, PolicyEvaluation};
+
+/// Summary of one telemetry file.
+#[derive(Debug, Clone)]
+pub struct TelemetrySummary {
+    pub evaluation: PolicyEvaluation,
+    /// (policy, decision) for every policy involved
+    pub policy_decisions: Vec<(String, String)>,
+    /// (phase name, duration_ms) for every phase, plus enrichment
+    pub phase_durations: Vec<(String, f64)>,
+}
+
+impl TelemetrySummary {
+    pub fn from_span(filename: String, span: &CupcakeSpan) -> Self {
+        Self {
+            evaluation: parse_policy_evaluation(filename, span),
+            policy_decisions: policy_decisions(span),
+            phase_durations: phase_durations(span),
+        }
+    }
+}
+
+/// Decision recorded when a policy matched but produced no decision.
+///
+/// Kept apart from "Allow" so per-policy allow rates only count explicit
+/// allows, and policies that match but never fire stand out.
+const NO_DECISION: &str = "NoDecision";
+
+/// Per-policy decisions for a span.
+///
+/// Each rule in the WASM decision sets contributes its decision to the policy
+/// it belongs to; a matched policy that produced no decision is recorded as
+/// `NO_DECISION`.
+fn policy_decisions(span: &CupcakeSpan) -> Vec<(String, String)> {
+    let mut results = Vec::new();
+    let mut decided: HashSet<String> = HashSet::new();
+    let mut matched: Vec<String> = Vec::new();
+
+    for evaluation in span.phases.iter().filter_map(|p| p.evaluation.as_ref()) {
+        matched.extend(evaluation.matched_policies.iter().cloned());
+
+        let Some(decision_set) = &evaluation.wasm_decision_set else {
+            continue;
+        };
+        for (decision, result) in decision_set.decisions() {
+            let Some(policy) = decision_policy(result, &evaluation.matched_policies) else {
+                continue;
+            };
+            decided.insert(policy.to_string());
+            // Several rules of a policy can agree; count the policy once
+            let entry = (policy.to_string(), decision.to_string());
+            if !results.contains(&entry) {
+                results.push(entry);
+            }
+        }
+    }
+
+    for policy in matched {
+        if decided.insert(policy.clone()) {
+            results.push((policy, NO_DECISION.to_string()));
+        }
+    }
+    results
+}
+
+/// The policy a decision belongs to: the one the result names or, if it
+/// doesn't, the phase's only matched policy. Results from older Cupcake
+/// versions that don't say which of several policies decided fall back to
+/// the rule ID.
+fn decision_policy<'a>(result: &'a DecisionResult, matched: &'a [String]) -> Option<&'a str> {
+    if let Some(policy) = result.policy.as_deref().filter(|p| !p.is_empty()) {
+        return Some(policy);
+    }
+    match matched {
+        [policy] => Some(policy),
+        _ => Some(result.rule_id.as_str()).filter(|r| !r.is_empty()),
+    }
+}
+
+/// (phase name, duration_ms) for each phase of a span, plus enrichment.
+fn phase_durations(span: &CupcakeSpan) -> Vec<(String, f64)> {
+    let mut durations = Vec::new();
+
+    if let Some(enrich) = &span.enrich {
+        durations.push(("enrich".to_string(), enrich.duration_us / 1000.0));
+    }
+
+    for phase in &span.phases {
+        if !phase.name.is_empty() {
+            durations.push((phase.name.clone(), phase.duration_ms));
+        }
+    }
+
+    durations
+}
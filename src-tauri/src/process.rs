@@ -1,11 +1,44 @@
 //! Process detection for active Claude Code sessions.
 //!
-//! This module provides cross-platform detection of running Claude Code processes
-//! and their working directories.
+//! This module provides cross-platform detection of running Claude Code processes,
+//! their working directories and, where the platform allows, the session each
+//! process is writing to.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+#[cfg(any(target_os = "linux", test))]
+use std::path::PathBuf;
+#[cfg(target_os = "macos")]
 use std::process::Command;
+#[cfg(any(target_os = "linux", test))]
+use std::time::SystemTime;
+
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+use crate::claude_code::is_uuid_format;
+
+/// Interpreters Claude Code can run under, with its entry point as the
+/// first argument.
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+const INTERPRETERS: [&str; 7] = ["node", "bun", "deno", "sh", "bash", "zsh", "env"];
+
+/// A running Claude Code process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveSession {
+    pub pid: u32,
+    /// Working directory of the process (the project path).
+    pub cwd: String,
+    /// Session the process is writing to, when it can be determined.
+    pub session_id: Option<String>,
+    /// Session file the process has open.
+    pub session_file: Option<String>,
+    /// Process start time (ISO 8601).
+    pub started_at: Option<String>,
+    /// Average CPU usage over the lifetime of the process, in percent of one core.
+    pub cpu_percent: Option<f64>,
+    /// Resident memory.
+    pub memory_bytes: Option<u64>,
+}
 
 /// Result of active session detection.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,84 +48,294 @@ pub struct ActiveSessionsResult {
     pub supported: bool,
     /// Set of project paths with active Claude sessions.
     pub active_paths: HashSet<String>,
+    /// Every detected Claude process.
+    pub sessions: Vec<ActiveSession>,
+}
+
+impl ActiveSessionsResult {
+    fn from_sessions(supported: bool, sessions: Vec<ActiveSession>) -> Self {
+        ActiveSessionsResult {
+            supported,
+            active_paths: sessions.iter().map(|s| s.cwd.clone()).collect(),
+            sessions,
+        }
+    }
 }
 
-/// Detect active Claude Code sessions and return their working directories.
+/// Detect active Claude Code sessions.
 ///
 /// # Platform Support
-/// - **macOS**: Full support via `ps` and `lsof`
-/// - **Linux**: Full support via `ps` and `/proc`
+/// - **macOS**: Processes and working directories via `ps` and `lsof`
+/// - **Linux**: Full support via `/proc`, including the open session file,
+///   start time and resource usage
 /// - **Windows**: Not currently supported (returns supported=false)
 pub fn get_active_sessions() -> ActiveSessionsResult {
     #[cfg(target_os = "macos")]
     {
-        ActiveSessionsResult {
-            supported: true,
-            active_paths: detect_macos_sessions(),
-        }
+        ActiveSessionsResult::from_sessions(true, detect_macos_sessions())
     }
 
     #[cfg(target_os = "linux")]
     {
-        ActiveSessionsResult {
-            supported: true,
-            active_paths: detect_linux_sessions(),
-        }
+        let proc = ProcFs::new("/proc");
+        ActiveSessionsResult::from_sessions(true, proc.active_sessions(SystemTime::now()))
     }
 
-    #[cfg(target_os = "windows")]
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     {
-        ActiveSessionsResult {
-            supported: false,
-            active_paths: HashSet::new(),
-        }
+        ActiveSessionsResult::from_sessions(false, Vec::new())
     }
+}
 
-    #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
-    {
-        ActiveSessionsResult {
-            supported: false,
-            active_paths: HashSet::new(),
-        }
+/// Entry point of the npm package, run by an interpreter.
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+const CLI_ENTRY_POINT: &str = "/@anthropic-ai/claude-code/cli.js";
+
+/// Whether a command line is a Claude Code process.
+///
+/// Matches the `claude` binary, and interpreters or wrapper scripts running
+/// it or the npm entry point, e.g. `node .../@anthropic-ai/claude-code/cli.js`.
+/// Claude Desktop (`Claude`, `Claude Helper`, `claude-desktop`) and programs
+/// that merely mention "claude" in their arguments (`grep claude`) don't
+/// match.
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+fn is_claude_command(args: &[String]) -> bool {
+    let Some(program) = args.first() else {
+        return false;
+    };
+    fn basename(arg: &str) -> &str {
+        arg.rsplit('/').next().unwrap_or(arg)
     }
+    if basename(program) == "claude" {
+        return true;
+    }
+
+    INTERPRETERS.contains(&basename(program))
+        && args[1..]
+            .iter()
+            .find(|a| !a.starts_with('-'))
+            .is_some_and(|a| basename(a) == "claude" || a.ends_with(CLI_ENTRY_POINT))
 }
 
-/// Detect Claude sessions on macOS.
-#[cfg(target_os = "macos")]
-fn detect_macos_sessions() -> HashSet<String> {
-    let mut paths = HashSet::new();
+/// Session ID passed on the command line (`--resume <id>`, `--session-id <id>`).
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+fn session_id_from_args(args: &[String]) -> Option<String> {
+    args.windows(2)
+        .find(|w| matches!(w[0].as_str(), "--resume" | "-r" | "--session-id"))
+        .map(|w| w[1].clone())
+        .filter(|id| is_uuid_format(id))
+}
 
-    for pid in get_claude_pids() {
-        if let Some(cwd) = get_process_cwd_macos(pid) {
-            paths.insert(cwd);
-        }
+/// Session ID of a session file path (`~/.claude/projects/<project>/<id>.jsonl`).
+#[cfg(any(target_os = "linux", test))]
+fn session_id_from_file(path: &str) -> Option<String> {
+    if !path.contains("/.claude/projects/") {
+        return None;
     }
+    let stem = path.strip_suffix(".jsonl")?.rsplit('/').next()?;
+    is_uuid_format(stem).then(|| stem.to_string())
+}
+
+/// A procfs tree: the real `/proc`, or a fixture in tests.
+#[cfg(any(target_os = "linux", test))]
+struct ProcFs {
+    root: PathBuf,
+    /// Clock ticks per second of `/proc/<pid>/stat` times (USER_HZ)
+    clock_ticks: f64,
+}
 
-    paths
+/// CPU times of a process from `/proc/<pid>/stat`, in clock ticks.
+#[cfg(any(target_os = "linux", test))]
+struct ProcStat {
+    /// User plus system time
+    cpu_ticks: u64,
+    /// Start time since boot
+    start_ticks: u64,
 }
 
-/// Detect Claude sessions on Linux.
-#[cfg(target_os = "linux")]
-fn detect_linux_sessions() -> HashSet<String> {
-    let mut paths = HashSet::new();
+#[cfg(any(target_os = "linux", test))]
+impl ProcFs {
+    fn new(root: impl Into<PathBuf>) -> Self {
+        ProcFs {
+            root: root.into(),
+            clock_ticks: clock_ticks(),
+        }
+    }
+
+    fn pid_path(&self, pid: u32, name: &str) -> PathBuf {
+        self.root.join(pid.to_string()).join(name)
+    }
+
+    /// Detect every Claude process in the tree.
+    fn active_sessions(&self, now: SystemTime) -> Vec<ActiveSession> {
+        let boot_time = self.boot_time();
+        let now = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        let mut sessions: Vec<ActiveSession> =
+            self.pids()
+                .into_iter()
+                .filter_map(|pid| {
+                    let args = self.cmdline(pid);
+                    if !is_claude_command(&args) {
+                        return None;
+                    }
+                    // Processes that exited mid-scan have no cwd
+                    let cwd = self.cwd(pid)?;
+
+                    let session_file = self.open_session_files(pid).into_iter().next();
+                    let session_id = session_file
+                        .as_deref()
+                        .and_then(session_id_from_file)
+                        .or_else(|| session_id_from_args(&args));
+
+                    let stat = self.stat(pid);
+                    let started = stat.as_ref().zip(boot_time).map(|(stat, boot)| {
+                        boot as f64 + stat.start_ticks as f64 / self.clock_ticks
+                    });
+                    let started_at = started
+                        .and_then(|secs| chrono::DateTime::from_timestamp(secs as i64, 0))
+                        .map(|dt| dt.to_rfc3339());
+                    let cpu_percent = stat.as_ref().zip(started).and_then(|(stat, started)| {
+                        let elapsed = now - started;
+                        (elapsed > 0.0)
+                            .then(|| stat.cpu_ticks as f64 / self.clock_ticks / elapsed * 100.0)
+                    });
+
+                    Some(ActiveSession {
+                        pid,
+                        cwd,
+                        session_id,
+                        session_file,
+                        started_at,
+                        cpu_percent,
+                        memory_bytes: self.rss_bytes(pid),
+                    })
+                })
+                .collect();
+
+        sessions.sort_by_key(|s| s.pid);
+        sessions
+    }
+
+    fn pids(&self) -> Vec<u32> {
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse().ok())
+            .collect()
+    }
+
+    /// Boot time in seconds since the epoch (`btime` in `/proc/stat`).
+    fn boot_time(&self) -> Option<u64> {
+        let stat = std::fs::read_to_string(self.root.join("stat")).ok()?;
+        stat.lines()
+            .find_map(|line| line.strip_prefix("btime "))
+            .and_then(|v| v.trim().parse().ok())
+    }
+
+    fn cmdline(&self, pid: u32) -> Vec<String> {
+        let Ok(bytes) = std::fs::read(self.pid_path(pid, "cmdline")) else {
+            return Vec::new();
+        };
+        bytes
+            .split(|b| *b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect()
+    }
+
+    fn cwd(&self, pid: u32) -> Option<String> {
+        std::fs::read_link(self.pid_path(pid, "cwd"))
+            .ok()
+            .and_then(|p| p.to_str().map(|s| s.to_string()))
+    }
+
+    /// Session files the process has open, main sessions first.
+    fn open_session_files(&self, pid: u32) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(self.pid_path(pid, "fd")) else {
+            return Vec::new();
+        };
+        let mut files: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| std::fs::read_link(e.path()).ok())
+            .filter_map(|p| p.to_str().map(|s| s.to_string()))
+            .filter(|p| p.contains("/.claude/projects/") && p.ends_with(".jsonl"))
+            .collect();
+        files.sort_by_key(|f| session_id_from_file(f).is_none());
+        files.dedup();
+        files
+    }
 
-    for pid in get_claude_pids() {
-        if let Some(cwd) = get_process_cwd_linux(pid) {
-            paths.insert(cwd);
+    fn stat(&self, pid: u32) -> Option<ProcStat> {
+        let stat = std::fs::read_to_string(self.pid_path(pid, "stat")).ok()?;
+        // The command name is parenthesized and may contain spaces, so fields
+        // are counted from the closing parenthesis (field 3 onwards)
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+        let field = |n: usize| fields.get(n - 3).and_then(|v| v.parse::<u64>().ok());
+        Some(ProcStat {
+            cpu_ticks: field(14)? + field(15)?,
+            start_ticks: field(22)?,
+        })
+    }
+
+    /// Resident memory (`VmRSS` in `/proc/<pid>/status`).
+    fn rss_bytes(&self, pid: u32) -> Option<u64> {
+        let status = std::fs::read_to_string(self.pid_path(pid, "status")).ok()?;
+        let kb: u64 = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        Some(kb * 1024)
+    }
+}
+
+/// Clock ticks per second (USER_HZ), falling back to the usual 100.
+#[cfg(any(target_os = "linux", test))]
+fn clock_ticks() -> f64 {
+    #[cfg(unix)]
+    {
+        // SAFETY: sysconf has no preconditions
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 {
+            return ticks as f64;
         }
     }
+    100.0
+}
 
-    paths
+/// Detect Claude sessions on macOS.
+#[cfg(target_os = "macos")]
+fn detect_macos_sessions() -> Vec<ActiveSession> {
+    get_claude_pids()
+        .into_iter()
+        .filter_map(|(pid, args)| {
+            let cwd = get_process_cwd_macos(pid)?;
+            Some(ActiveSession {
+                pid,
+                cwd,
+                session_id: session_id_from_args(&args),
+                session_file: None,
+                started_at: None,
+                cpu_percent: None,
+                memory_bytes: None,
+            })
+        })
+        .collect()
 }
 
-/// Get PIDs of all running "claude" processes.
-#[cfg(any(target_os = "macos", target_os = "linux"))]
-fn get_claude_pids() -> Vec<u32> {
+/// Get PIDs and arguments of all running Claude processes.
+#[cfg(target_os = "macos")]
+fn get_claude_pids() -> Vec<(u32, Vec<String>)> {
     // Use ps which is more reliable than pgrep across systems
-    let output = Command::new("ps")
-        .args(["-eo", "pid,comm"])
-        .output()
-        .ok();
+    let output = Command::new("ps").args(["-eo", "pid,args"]).output().ok();
 
     let Some(output) = output else {
         return Vec::new();
@@ -103,12 +346,10 @@ fn get_claude_pids() -> Vec<u32> {
     stdout
         .lines()
         .filter_map(|line| {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 && parts[1] == "claude" {
-                parts[0].parse::<u32>().ok()
-            } else {
-                None
-            }
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?.parse::<u32>().ok()?;
+            let args: Vec<String> = parts.map(String::from).collect();
+            is_claude_command(&args).then_some((pid, args))
         })
         .collect()
 }
@@ -137,18 +378,12 @@ fn get_process_cwd_macos(pid: u32) -> Option<String> {
     None
 }
 
-/// Get the current working directory of a process by PID on Linux.
-#[cfg(target_os = "linux")]
-fn get_process_cwd_linux(pid: u32) -> Option<String> {
-    let proc_path = format!("/proc/{}/cwd", pid);
-    std::fs::read_link(&proc_path)
-        .ok()
-        .and_then(|p| p.to_str().map(|s| s.to_string()))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
 
     #[test]
     fn test_get_active_sessions_returns_result() {
@@ -160,4 +395,131 @@ mod tests {
         #[cfg(target_os = "windows")]
         assert!(!result.supported);
     }
+
+    #[test]
+    fn test_is_claude_command() {
+        let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<_>>();
+        assert!(is_claude_command(&args("claude --resume abc")));
+        assert!(is_claude_command(&args("/usr/local/bin/claude -p hi")));
+        assert!(is_claude_command(&args(
+            "node /usr/lib/node_modules/@anthropic-ai/claude-code/cli.js"
+        )));
+        assert!(is_claude_command(&args(
+            "/bin/sh /home/me/.local/bin/claude"
+        )));
+        assert!(!is_claude_command(&args("grep claude")));
+        // Claude Desktop and its helpers
+        assert!(!is_claude_command(&args(
+            "/Applications/Claude.app/Contents/MacOS/Claude"
+        )));
+        assert!(!is_claude_command(&args(
+            "/Applications/Claude.app/Contents/Frameworks/Claude Helper.app/Contents/MacOS/Claude Helper --type=renderer"
+        )));
+        assert!(!is_claude_command(&args("/usr/bin/claude-desktop")));
+        assert!(!is_claude_command(&args(
+            "/home/me/src/claude-code/build.sh"
+        )));
+        assert!(!is_claude_command(&args(
+            "node /home/me/claude-code/server.js"
+        )));
+        assert!(!is_claude_command(&args("node server.js claude")));
+        assert!(!is_claude_command(&[]));
+    }
+
+    /// Add a process to a fake /proc tree.
+    #[cfg(unix)]
+    fn add_process(root: &Path, pid: u32, cmdline: &[&str], cwd: &str, fds: &[&str]) {
+        use std::os::unix::fs::symlink;
+
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(dir.join("fd")).unwrap();
+        let cmdline: Vec<u8> = cmdline
+            .iter()
+            .flat_map(|a| [a.as_bytes(), b"\0"].concat())
+            .collect();
+        fs::write(dir.join("cmdline"), cmdline).unwrap();
+        symlink(cwd, dir.join("cwd")).unwrap();
+        for (i, target) in fds.iter().enumerate() {
+            symlink(target, dir.join("fd").join(i.to_string())).unwrap();
+        }
+        // utime=300 stime=100 ticks, started 1000s after boot
+        fs::write(
+            dir.join("stat"),
+            format!(
+                "{} (claude main) S 1 1 1 0 -1 0 0 0 0 0 300 100 0 0 20 0 1 0 100000 0 0",
+                pid
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("status"), "Name:\tclaude\nVmRSS:\t  2048 kB\n").unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_proc_active_sessions() {
+        let root = std::env::temp_dir().join(format!("agent-console-proc-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("stat"), "cpu  1 2 3\nbtime 1700000000\n").unwrap();
+
+        let session =
+            "/home/me/.claude/projects/-work-app/0b1c2d3e-1111-2222-3333-444455556666.jsonl";
+        let resumed = "8f9e0d1c-aaaa-bbbb-cccc-ddddeeeeffff";
+        add_process(
+            &root,
+            100,
+            &["claude"],
+            "/work/app",
+            &["/dev/null", session],
+        );
+        add_process(
+            &root,
+            200,
+            &[
+                "node",
+                "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js",
+                "--resume",
+                resumed,
+            ],
+            "/work/lib",
+            &[],
+        );
+        add_process(&root, 300, &["grep", "claude"], "/work/app", &[]);
+        // Not a process directory
+        fs::create_dir_all(root.join("self-test")).unwrap();
+
+        // 2000s after boot, so each process has run for 1000s
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_002_000);
+        let proc = ProcFs {
+            clock_ticks: 100.0,
+            ..ProcFs::new(&root)
+        };
+        let sessions = proc.active_sessions(now);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(sessions.len(), 2);
+        let first = &sessions[0];
+        assert_eq!(first.pid, 100);
+        assert_eq!(first.cwd, "/work/app");
+        assert_eq!(
+            first.session_id.as_deref(),
+            Some("0b1c2d3e-1111-2222-3333-444455556666")
+        );
+        assert_eq!(first.session_file.as_deref(), Some(session));
+        assert_eq!(
+            first.started_at.as_deref(),
+            Some("2023-11-14T22:30:00+00:00")
+        );
+        assert_eq!(first.memory_bytes, Some(2048 * 1024));
+        let cpu = first.cpu_percent.unwrap();
+        assert!((cpu - 0.4).abs() < 1e-9, "cpu {}", cpu);
+
+        // No open session file; the ID comes from --resume
+        assert_eq!(sessions[1].pid, 200);
+        assert_eq!(sessions[1].session_id.as_deref(), Some(resumed));
+        assert_eq!(sessions[1].session_file, None);
+
+        let result = ActiveSessionsResult::from_sessions(true, sessions);
+        assert_eq!(result.active_paths.len(), 2);
+    }
 }
@@ -0,0 +1,125 @@
+//! Persistent project metadata cache.
+//!
+//! Discovering a project means listing its directory and reading session
+//! content to find the project `cwd`. This module remembers the result per
+//! project directory, keyed by the directory's mtime, so projects whose
+//! directory hasn't changed (no sessions added or removed) are never re-read.
+//! Directories where no `cwd` was found yet are not cached, since appending to
+//! their sessions doesn't change the directory mtime.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Bump when the cached shape changes so stale caches are discarded.
+const CACHE_VERSION: u32 = 1;
+
+/// Cached metadata for a single project directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedProject {
+    /// Directory mtime (nanoseconds since the epoch) when this entry was built
+    pub dir_mtime: u64,
+    /// Project path found in session content (None if it couldn't be found)
+    pub project_path: Option<String>,
+    /// File names of the UUID-format session files
+    pub session_files: Vec<String>,
+    /// Number of sub-agent session files
+    pub subagent_count: u32,
+}
+
+/// Project metadata keyed by encoded directory name.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectCache {
+    version: u32,
+    projects: HashMap<String, CachedProject>,
+}
+
+/// Default location of the cache file.
+pub fn default_cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("io.eqtylab.agent-console").join("projects.json"))
+}
+
+/// Convert a directory mtime to the cache key representation.
+pub fn mtime_key(mtime: SystemTime) -> u64 {
+    mtime
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+impl ProjectCache {
+    /// Load the cache, returning an empty one if it is missing, unreadable,
+    /// or from an older version.
+    pub fn load(path: &Path) -> Self {
+        let cache = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ProjectCache>(&bytes).ok());
+        match cache {
+            Some(cache) if cache.version == CACHE_VERSION => cache,
+            _ => Self::default(),
+        }
+    }
+
+    /// Write the cache atomically (temp file + rename).
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        }
+        let json = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize project cache: {}", e))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json).map_err(|e| format!("Failed to write project cache: {}", e))?;
+        fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write project cache: {}", e))
+    }
+
+    /// Get the cached entry for a directory if its mtime still matches.
+    pub fn get(&self, dir_name: &str, dir_mtime: u64) -> Option<&CachedProject> {
+        self.projects
+            .get(dir_name)
+            .filter(|entry| entry.dir_mtime == dir_mtime)
+    }
+
+    pub fn insert(&mut self, dir_name: String, entry: CachedProject) {
+        self.version = CACHE_VERSION;
+        self.projects.insert(dir_name, entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(dir_mtime: u64) -> CachedProject {
+        CachedProject {
+            dir_mtime,
+            project_path: Some("/Users/me/project".to_string()),
+            session_files: vec!["040f5516-2ff1-4738-8190-2b8248f631de.jsonl".to_string()],
+            subagent_count: 2,
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trip_and_mtime_check() {
+        let path = std::env::temp_dir()
+            .join(format!("agent-console-cache-{}", std::process::id()))
+            .join("projects.json");
+
+        let mut cache = ProjectCache::default();
+        cache.insert("-Users-me-project".to_string(), entry(42));
+        cache.save(&path).unwrap();
+
+        let loaded = ProjectCache::load(&path);
+        assert_eq!(loaded, cache);
+        assert_eq!(loaded.get("-Users-me-project", 42), Some(&entry(42)));
+        assert!(loaded.get("-Users-me-project", 43).is_none());
+        assert!(loaded.get("-Users-me-other", 42).is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(ProjectCache::load(&path), ProjectCache::default());
+    }
+}
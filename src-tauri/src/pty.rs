@@ -0,0 +1,682 @@
+//! Embedded terminals.
+//!
+//! Runs `claude` under a pseudo-terminal owned by the app instead of handing it
+//! off to an external emulator. Output is streamed to the frontend as
+//! "pty-output" events, input and resizes come back through commands, and each
+//! session the process starts is reported with a "pty-session" event so the
+//! log viewer can follow it. Sessions are attributed by the session file the
+//! process has open where the platform shows it, and otherwise only when no
+//! other Claude process in the project could have started them.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::claude_code::get_sessions_for_project;
+use crate::process::{get_active_sessions, ActiveSession};
+use crate::terminal::{claude_args, LaunchOptions};
+
+/// How often the project is checked for a new session file.
+const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Terminal size in character cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PtySize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// An embedded terminal, as reported to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyInfo {
+    pub id: u32,
+    pub pid: u32,
+    pub project_path: String,
+    /// Session the process is writing to, once known
+    pub session_id: Option<String>,
+    pub exited: bool,
+    pub exit_code: Option<i32>,
+}
+
+/// Event payload for terminal output.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyOutputPayload {
+    pub id: u32,
+    pub data: String,
+}
+
+/// Event payload sent when a terminal's process starts a session.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtySessionPayload {
+    pub id: u32,
+    pub project_path: String,
+    pub session_id: String,
+}
+
+/// Event payload sent when a terminal's process exits.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyExitPayload {
+    pub id: u32,
+    pub exit_code: Option<i32>,
+}
+
+struct Terminal {
+    pty: Pty,
+    info: Mutex<PtyInfo>,
+    /// Closed by the frontend; removed once its process has been reaped.
+    /// Set, and checked on exit, with `info` locked so closing can't miss the exit.
+    closed: AtomicBool,
+}
+
+type Terminals = Arc<Mutex<HashMap<u32, Arc<Terminal>>>>;
+
+/// State of all embedded terminals.
+pub struct PtyState {
+    terminals: Terminals,
+    next_id: AtomicU32,
+}
+
+impl PtyState {
+    pub fn new() -> Self {
+        Self {
+            terminals: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU32::new(1),
+        }
+    }
+
+    /// Start `claude` in a new embedded terminal.
+    pub fn spawn(
+        &self,
+        app_handle: AppHandle,
+        project_path: &str,
+        options: &LaunchOptions,
+        size: PtySize,
+    ) -> Result<PtyInfo, String> {
+        let args = claude_args(options)?;
+        // Sessions that exist before the process starts aren't the one it creates
+        let known: HashSet<String> = get_sessions_for_project(project_path)
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+
+        let pty = Pty::spawn(&args, project_path, size)?;
+        let mut reader = pty.reader()?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let info = PtyInfo {
+            id,
+            pid: pty.pid(),
+            project_path: project_path.to_string(),
+            session_id: options.resume_session_id.clone(),
+            exited: false,
+            exit_code: None,
+        };
+        let terminal = Arc::new(Terminal {
+            pty,
+            info: Mutex::new(info.clone()),
+            closed: AtomicBool::new(false),
+        });
+        self.terminals
+            .lock()
+            .map_err(|e| format!("Failed to lock terminals: {}", e))?
+            .insert(id, Arc::clone(&terminal));
+
+        // Stream output until the process closes the terminal
+        let output_terminal = Arc::clone(&terminal);
+        let output_app_handle = app_handle.clone();
+        let terminals = Arc::clone(&self.terminals);
+        thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            let mut pending = Vec::new();
+            // Linux reports EIO rather than EOF once the child side is closed
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                pending.extend_from_slice(&buf[..n]);
+                let data = decode_utf8(&mut pending);
+                if !data.is_empty() {
+                    let _ = output_app_handle.emit("pty-output", PtyOutputPayload { id, data });
+                }
+            }
+
+            let exit_code = output_terminal.pty.wait();
+            let closed = match output_terminal.info.lock() {
+                Ok(mut info) => {
+                    info.exited = true;
+                    info.exit_code = exit_code;
+                    output_terminal.closed.load(Ordering::Relaxed)
+                }
+                Err(_) => false,
+            };
+            // Locked after `info` is released, as `list` takes them the other way
+            if closed {
+                if let Ok(mut terminals) = terminals.lock() {
+                    terminals.remove(&id);
+                }
+            }
+            let _ = output_app_handle.emit("pty-exit", PtyExitPayload { id, exit_code });
+        });
+
+        // Follow the sessions the process starts (including after /clear)
+        let session_terminal = terminal;
+        let pid = info.pid;
+        let project_path = project_path.to_string();
+        thread::spawn(move || {
+            let mut known = known;
+            // New sessions not yet attributed to this or another process
+            let mut pending: HashSet<String> = HashSet::new();
+            loop {
+                thread::sleep(SESSION_POLL_INTERVAL);
+                if session_terminal
+                    .info
+                    .lock()
+                    .map(|i| i.exited)
+                    .unwrap_or(true)
+                {
+                    break;
+                }
+
+                // Sessions are sorted by last activity, newest first
+                let sessions = get_sessions_for_project(&project_path);
+                for session in &sessions {
+                    if known.insert(session.id.clone()) {
+                        pending.insert(session.id.clone());
+                    }
+                }
+                if pending.is_empty() {
+                    continue;
+                }
+
+                let processes = get_active_sessions().sessions;
+                // Sessions another process has open aren't this one's
+                pending.retain(|id| {
+                    !processes
+                        .iter()
+                        .any(|p| p.pid != pid && open_session(p) == Some(id.as_str()))
+                });
+                let new_sessions: Vec<String> = sessions
+                    .into_iter()
+                    .map(|s| s.id)
+                    .filter(|id| pending.contains(id))
+                    .collect();
+                let Some(session_id) = pick_session(pid, &project_path, &new_sessions, &processes)
+                else {
+                    continue;
+                };
+                pending.clear();
+
+                if let Ok(mut info) = session_terminal.info.lock() {
+                    info.session_id = Some(session_id.clone());
+                }
+                let _ = app_handle.emit(
+                    "pty-session",
+                    PtySessionPayload {
+                        id,
+                        project_path: project_path.clone(),
+                        session_id,
+                    },
+                );
+            }
+        });
+
+        Ok(info)
+    }
+
+    fn get(&self, id: u32) -> Result<Arc<Terminal>, String> {
+        self.terminals
+            .lock()
+            .map_err(|e| format!("Failed to lock terminals: {}", e))?
+            .get(&id)
+            .filter(|t| !t.closed.load(Ordering::Relaxed))
+            .cloned()
+            .ok_or_else(|| format!("Terminal not found: {}", id))
+    }
+
+    /// Send keyboard input to a terminal.
+    pub fn write(&self, id: u32, data: &str) -> Result<(), String> {
+        self.get(id)?.pty.write(data.as_bytes())
+    }
+
+    /// Resize a terminal.
+    pub fn resize(&self, id: u32, size: PtySize) -> Result<(), String> {
+        self.get(id)?.pty.resize(size)
+    }
+
+    /// Close a terminal, hanging up its process if it's still running. A
+    /// running terminal stays in the state, hidden, until its output thread
+    /// has reaped the process.
+    pub fn close(&self, id: u32) -> Result<(), String> {
+        let terminal = self.get(id)?;
+        let exited = {
+            let info = terminal
+                .info
+                .lock()
+                .map_err(|e| format!("Failed to lock terminal: {}", e))?;
+            terminal.closed.store(true, Ordering::Relaxed);
+            info.exited
+        };
+        if exited {
+            self.terminals
+                .lock()
+                .map_err(|e| format!("Failed to lock terminals: {}", e))?
+                .remove(&id);
+        } else {
+            terminal.pty.hangup();
+        }
+        Ok(())
+    }
+
+    /// All open terminals, oldest first.
+    pub fn list(&self) -> Vec<PtyInfo> {
+        let Ok(terminals) = self.terminals.lock() else {
+            return Vec::new();
+        };
+        let mut infos: Vec<PtyInfo> = terminals
+            .values()
+            .filter(|t| !t.closed.load(Ordering::Relaxed))
+            .filter_map(|t| t.info.lock().ok().map(|i| i.clone()))
+            .collect();
+        infos.sort_by_key(|i| i.id);
+        infos
+    }
+}
+
+impl Default for PtyState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pick which of the sessions that appeared in a project (newest first) the
+/// terminal process `pid` started.
+///
+/// Where the platform shows open files, that is the session file the process
+/// has open. Otherwise the newest session is taken, but only if no other
+/// Claude process runs in the project that could have started it.
+fn pick_session(
+    pid: u32,
+    project_path: &str,
+    new_sessions: &[String],
+    processes: &[ActiveSession],
+) -> Option<String> {
+    if let Some(open) = processes
+        .iter()
+        .find(|p| p.pid == pid)
+        .and_then(open_session)
+    {
+        return new_sessions.iter().find(|id| *id == open).cloned();
+    }
+    let others = processes
+        .iter()
+        .any(|p| p.pid != pid && p.cwd == project_path);
+    if others {
+        return None;
+    }
+    new_sessions.first().cloned()
+}
+
+/// The session a process has open, if its open files are known.
+fn open_session(process: &ActiveSession) -> Option<&str> {
+    process.session_file.as_ref()?;
+    process.session_id.as_deref()
+}
+
+/// Decode the complete UTF-8 prefix of `pending`, keeping a multi-byte
+/// character split across reads for the next call. Invalid bytes are replaced.
+fn decode_utf8(pending: &mut Vec<u8>) -> String {
+    let keep = incomplete_tail(pending);
+    let complete = pending.len() - keep;
+    let data = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    data
+}
+
+/// Length of a truncated UTF-8 sequence at the end of `bytes`.
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 == 0x80 {
+            // Continuation byte; look further back for the leading byte
+            continue;
+        }
+        let needed = match byte {
+            0xF0.. => 4,
+            0xE0.. => 3,
+            0xC0.. => 2,
+            _ => 1,
+        };
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
+
+#[cfg(unix)]
+use unix::Pty;
+
+#[cfg(not(unix))]
+use unsupported::Pty;
+
+#[cfg(unix)]
+mod unix {
+    use super::PtySize;
+    use std::fs::File;
+    use std::io::{self, Write};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::process::CommandExt;
+    use std::process::{Child, Command, Stdio};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Duration;
+
+    /// How often a running process is checked for exit.
+    const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// A process running under a pseudo-terminal.
+    pub struct Pty {
+        master: File,
+        child: Mutex<Child>,
+        pid: u32,
+    }
+
+    fn winsize(size: PtySize) -> libc::winsize {
+        libc::winsize {
+            ws_row: size.rows,
+            ws_col: size.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+
+    impl Pty {
+        /// Spawn `args` (program first) in `cwd` under a new pseudo-terminal.
+        pub fn spawn(args: &[String], cwd: &str, size: PtySize) -> Result<Self, String> {
+            let (program, rest) = args.split_first().ok_or("No command to run")?;
+
+            let mut master_fd = -1;
+            let mut slave_fd = -1;
+            let mut ws = winsize(size);
+            // SAFETY: openpty only writes the two descriptors it returns. The
+            // size is passed as *mut since macOS declares it that way.
+            let rc = unsafe {
+                libc::openpty(
+                    &mut master_fd,
+                    &mut slave_fd,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    &mut ws as *mut libc::winsize,
+                )
+            };
+            if rc != 0 {
+                return Err(format!(
+                    "Failed to open pseudo-terminal: {}",
+                    io::Error::last_os_error()
+                ));
+            }
+            // SAFETY: both descriptors were just opened and are owned here
+            let (master, slave) = unsafe {
+                (
+                    OwnedFd::from_raw_fd(master_fd),
+                    OwnedFd::from_raw_fd(slave_fd),
+                )
+            };
+            // Keep the child from inheriting the master side
+            // SAFETY: fcntl on a descriptor we own
+            unsafe { libc::fcntl(master.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) };
+
+            let stdio = |fd: &OwnedFd| -> Result<Stdio, String> {
+                fd.try_clone()
+                    .map(Stdio::from)
+                    .map_err(|e| format!("Failed to open pseudo-terminal: {}", e))
+            };
+
+            let mut command = Command::new(program);
+            command
+                .args(rest)
+                .current_dir(cwd)
+                .env("TERM", "xterm-256color")
+                .stdin(stdio(&slave)?)
+                .stdout(stdio(&slave)?)
+                .stderr(stdio(&slave)?);
+            // SAFETY: only async-signal-safe calls between fork and exec
+            unsafe {
+                command.pre_exec(|| {
+                    // New session, with the terminal as its controlling terminal
+                    if libc::setsid() == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+
+            let child = command
+                .spawn()
+                .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+            // The child holds its own copies of the slave side
+            drop(slave);
+
+            Ok(Pty {
+                master: File::from(master),
+                pid: child.id(),
+                child: Mutex::new(child),
+            })
+        }
+
+        pub fn pid(&self) -> u32 {
+            self.pid
+        }
+
+        /// A reader of the terminal's output.
+        pub fn reader(&self) -> Result<File, String> {
+            self.master
+                .try_clone()
+                .map_err(|e| format!("Failed to read pseudo-terminal: {}", e))
+        }
+
+        pub fn write(&self, data: &[u8]) -> Result<(), String> {
+            (&self.master)
+                .write_all(data)
+                .map_err(|e| format!("Failed to write to terminal: {}", e))
+        }
+
+        pub fn resize(&self, size: PtySize) -> Result<(), String> {
+            let ws = winsize(size);
+            // SAFETY: TIOCSWINSZ reads a winsize from the pointer
+            let rc = unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ as _, &ws) };
+            if rc == -1 {
+                return Err(format!(
+                    "Failed to resize terminal: {}",
+                    io::Error::last_os_error()
+                ));
+            }
+            Ok(())
+        }
+
+        /// Hang up the process, as closing a terminal window does.
+        pub fn hangup(&self) {
+            let Ok(mut child) = self.child.lock() else {
+                return;
+            };
+            // Until the child is reaped its pid can't be reused, and reaping
+            // needs the lock held here
+            if let Ok(None) = child.try_wait() {
+                // SAFETY: kill has no memory safety requirements
+                unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGHUP) };
+            }
+        }
+
+        /// Wait for the process to exit, returning its exit code.
+        ///
+        /// Polls rather than blocking in `wait` so `hangup` can take the lock.
+        pub fn wait(&self) -> Option<i32> {
+            loop {
+                if let Some(status) = self.child.lock().ok()?.try_wait().ok()? {
+                    return status.code();
+                }
+                thread::sleep(WAIT_POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod unsupported {
+    use super::PtySize;
+    use std::fs::File;
+
+    /// Pseudo-terminals aren't supported on this platform.
+    pub struct Pty;
+
+    impl Pty {
+        pub fn spawn(_args: &[String], _cwd: &str, _size: PtySize) -> Result<Self, String> {
+            Err("Embedded terminals are not supported on this platform".to_string())
+        }
+
+        pub fn pid(&self) -> u32 {
+            0
+        }
+
+        pub fn reader(&self) -> Result<File, String> {
+            Err("Embedded terminals are not supported on this platform".to_string())
+        }
+
+        pub fn write(&self, _data: &[u8]) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn resize(&self, _size: PtySize) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn hangup(&self) {}
+
+        pub fn wait(&self) -> Option<i32> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf8_keeps_split_characters() {
+        // "hé" with the é split across two reads
+        let mut pending = vec![b'h', 0xC3];
+        assert_eq!(decode_utf8(&mut pending), "h");
+        assert_eq!(pending, vec![0xC3]);
+
+        pending.push(0xA9);
+        assert_eq!(decode_utf8(&mut pending), "é");
+        assert!(pending.is_empty());
+
+        // Invalid bytes are replaced rather than held back forever
+        let mut pending = vec![0xFF, b'a'];
+        assert_eq!(decode_utf8(&mut pending), "\u{FFFD}a");
+        assert!(pending.is_empty());
+    }
+
+    fn process(pid: u32, cwd: &str, open: Option<&str>) -> ActiveSession {
+        ActiveSession {
+            pid,
+            cwd: cwd.to_string(),
+            session_id: open.map(String::from),
+            session_file: open.map(|id| format!("/home/u/.claude/projects/-p/{}.jsonl", id)),
+            started_at: None,
+            cpu_percent: None,
+            memory_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_pick_session_attributes_by_process() {
+        let new = vec!["theirs".to_string(), "ours".to_string()];
+
+        // The session file our process has open wins, even if older
+        let processes = [
+            process(10, "/p", Some("ours")),
+            process(20, "/p", Some("theirs")),
+        ];
+        assert_eq!(
+            pick_session(10, "/p", &new, &processes).as_deref(),
+            Some("ours")
+        );
+
+        // Without open files, another Claude in the project makes it ambiguous
+        let processes = [process(10, "/p", None), process(20, "/p", None)];
+        assert_eq!(pick_session(10, "/p", &new, &processes), None);
+
+        // ...but one elsewhere doesn't
+        let processes = [process(10, "/p", None), process(20, "/other", None)];
+        assert_eq!(
+            pick_session(10, "/p", &new, &processes).as_deref(),
+            Some("theirs")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pty_size_input_and_resize() {
+        let args: Vec<String> = [
+            "sh",
+            "-c",
+            "stty size; read line; stty size; echo \"got $line\"",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+        let pty = Pty::spawn(&args, "/", PtySize { cols: 80, rows: 24 }).unwrap();
+        let mut reader = pty.reader().unwrap();
+
+        let mut output = Vec::new();
+        let mut buf = [0u8; 1024];
+        let mut resized = false;
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..n]);
+            if !resized && String::from_utf8_lossy(&output).contains("24 80") {
+                pty.resize(PtySize {
+                    cols: 100,
+                    rows: 30,
+                })
+                .unwrap();
+                pty.write(b"hello\n").unwrap();
+                resized = true;
+            }
+        }
+        assert_eq!(pty.wait(), Some(0));
+
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("30 100"), "{}", output);
+        assert!(output.contains("got hello"), "{}", output);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hangup_stops_running_process() {
+        let args = vec!["sleep".to_string(), "30".to_string()];
+        let pty = Pty::spawn(&args, "/", PtySize { cols: 80, rows: 24 }).unwrap();
+
+        pty.hangup();
+        // Killed by the signal, so there's no exit code
+        assert_eq!(pty.wait(), None);
+        // Once reaped, hanging up again doesn't signal a reused pid
+        pty.hangup();
+        assert_eq!(pty.wait(), None);
+    }
+}
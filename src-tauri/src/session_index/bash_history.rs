@@ -0,0 +1,410 @@
+//! Bash command history.
+//!
+//! Lists the Bash tool calls of a session with their full command, output
+//! and outcome, read back from the session file at the lines recorded in the
+//! index. Commands are aggregated by executable and checked against the
+//! dangerous-command rules in [`super::bash_rules`].
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use crate::claude_code::truncate_string;
+
+use super::bash_rules::{check_command, parse_stages, DangerRule, RuleMatch, Severity};
+use super::queries::get_value_at_line;
+use super::tool_calls::duration_ms;
+use super::types::SessionIndex;
+
+/// Longest output kept per command.
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+/// A Bash tool call.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BashCommand {
+    pub tool_use_id: String,
+    pub command: String,
+    /// Description the model gave for the command
+    pub description: Option<String>,
+    /// Working directory of the session when the command ran
+    pub cwd: Option<String>,
+    pub started_at: Option<String>,
+    pub duration_ms: Option<u64>,
+    /// Exit code: parsed from "Exit code N" errors, 0 for successful
+    /// results, None while pending or when unknown
+    pub exit_code: Option<i32>,
+    pub is_error: bool,
+    /// Whether the command was interrupted (timeout or user)
+    pub interrupted: bool,
+    /// Output (stdout then stderr), truncated
+    pub output: String,
+    /// Programs run by the command (e.g., ["cd", "cargo"])
+    pub executables: Vec<String>,
+    /// Dangerous-command rules the command matched
+    pub flags: Vec<RuleMatch>,
+    pub tool_use_line: u32,
+    pub tool_result_line: Option<u32>,
+}
+
+/// Aggregates for one executable.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutableStats {
+    pub executable: String,
+    /// Commands that ran the executable
+    pub commands: u32,
+    /// Of those, commands that failed
+    pub errors: u32,
+    pub total_duration_ms: u64,
+}
+
+/// Number of commands flagged by a rule.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleCount {
+    pub rule_id: String,
+    pub description: String,
+    pub severity: Severity,
+    pub count: u32,
+}
+
+/// The Bash history of a session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BashHistory {
+    /// Commands in session order
+    pub commands: Vec<BashCommand>,
+    /// Aggregates per executable, most used first
+    pub executables: Vec<ExecutableStats>,
+    /// Rules that flagged at least one command, most severe first
+    pub flagged: Vec<RuleCount>,
+}
+
+/// Extract the Bash commands of a session.
+pub fn get_bash_history(
+    index: &SessionIndex,
+    session_file: &Path,
+    rules: &[DangerRule],
+) -> Result<BashHistory, String> {
+    let mut file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let mut calls: Vec<(&str, u32, Option<u32>)> = index
+        .tool_calls()
+        .filter(|(_, call)| call.name.as_deref() == Some("Bash"))
+        .filter_map(|(id, call)| Some((id, call.tool_use_line?, call.tool_result_line)))
+        .collect();
+    calls.sort_by_key(|&(id, line, _)| (line, id));
+
+    let mut commands = Vec::with_capacity(calls.len());
+    for (id, use_line, result_line) in calls {
+        let Some(entry) = get_value_at_line(index, &mut file, use_line)? else {
+            continue;
+        };
+        let Some(input) = find_block(&entry, "id", id).and_then(|b| b.get("input")) else {
+            continue;
+        };
+        let result = match result_line {
+            Some(line) => get_value_at_line(index, &mut file, line)?,
+            None => None,
+        };
+        commands.push(to_command(
+            id,
+            &entry,
+            input,
+            result.as_ref(),
+            use_line,
+            result_line,
+            rules,
+        ));
+    }
+
+    Ok(BashHistory {
+        executables: executable_stats(&commands),
+        flagged: rule_counts(&commands),
+        commands,
+    })
+}
+
+/// Find a content block of a message by id (tool_use) or tool_use_id.
+fn find_block<'a>(entry: &'a Value, key: &str, id: &str) -> Option<&'a Value> {
+    entry
+        .pointer("/message/content")?
+        .as_array()?
+        .iter()
+        .find(|block| block.get(key).and_then(|v| v.as_str()) == Some(id))
+}
+
+fn to_command(
+    id: &str,
+    entry: &Value,
+    input: &Value,
+    result: Option<&Value>,
+    tool_use_line: u32,
+    tool_result_line: Option<u32>,
+    rules: &[DangerRule],
+) -> BashCommand {
+    let str_field =
+        |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+
+    let command = str_field(input, "command").unwrap_or_default();
+    let stages = parse_stages(&command);
+    let mut executables: Vec<String> = Vec::new();
+    for stage in &stages {
+        for program in stage.wrappers.iter().chain([&stage.program]) {
+            if !executables.contains(program) {
+                executables.push(program.clone());
+            }
+        }
+    }
+
+    let block = result.and_then(|r| find_block(r, "tool_use_id", id));
+    let is_error = block
+        .and_then(|b| b.get("is_error"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let content_text = block.and_then(|b| b.get("content")).map(content_text);
+    let structured = result
+        .and_then(|r| r.get("toolUseResult"))
+        .filter(|v| v.is_object());
+
+    let output = match structured {
+        Some(r) => [str_field(r, "stdout"), str_field(r, "stderr")]
+            .into_iter()
+            .flatten()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => content_text.clone().unwrap_or_default(),
+    };
+    let exit_code = match (block, is_error) {
+        (None, _) => None,
+        (Some(_), false) => Some(0),
+        (Some(_), true) => content_text.as_deref().and_then(parse_exit_code),
+    };
+    let interrupted = structured
+        .and_then(|r| r.get("interrupted"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let started_at = str_field(entry, "timestamp");
+    let ended_at = result.and_then(|r| str_field(r, "timestamp"));
+
+    BashCommand {
+        tool_use_id: id.to_string(),
+        flags: check_command(&command, &stages, rules),
+        description: str_field(input, "description"),
+        cwd: str_field(entry, "cwd"),
+        duration_ms: duration_ms(started_at.as_deref(), ended_at.as_deref()),
+        started_at,
+        exit_code,
+        is_error,
+        interrupted,
+        output: truncate_string(&output, MAX_OUTPUT_CHARS),
+        executables,
+        command,
+        tool_use_line,
+        tool_result_line,
+    }
+}
+
+/// Text of a tool_result's content (a string or a list of blocks).
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Parse "Exit code N" (optionally prefixed by "Error: ") from an error result.
+fn parse_exit_code(text: &str) -> Option<i32> {
+    let text = text.trim_start();
+    let text = text.strip_prefix("Error: ").unwrap_or(text);
+    let rest = text.strip_prefix("Exit code ")?;
+    let digits: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '-')
+        .collect();
+    digits.parse().ok()
+}
+
+/// Aggregate commands per executable, most used first.
+fn executable_stats(commands: &[BashCommand]) -> Vec<ExecutableStats> {
+    let mut by_executable: HashMap<&str, ExecutableStats> = HashMap::new();
+    for command in commands {
+        for executable in &command.executables {
+            let stats = by_executable
+                .entry(executable)
+                .or_insert_with(|| ExecutableStats {
+                    executable: executable.clone(),
+                    ..Default::default()
+                });
+            stats.commands += 1;
+            if command.is_error {
+                stats.errors += 1;
+            }
+            stats.total_duration_ms += command.duration_ms.unwrap_or(0);
+        }
+    }
+
+    let mut stats: Vec<ExecutableStats> = by_executable.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.commands
+            .cmp(&a.commands)
+            .then_with(|| a.executable.cmp(&b.executable))
+    });
+    stats
+}
+
+/// Count flagged commands per rule, most severe (then most frequent) first.
+fn rule_counts(commands: &[BashCommand]) -> Vec<RuleCount> {
+    let mut counts: Vec<RuleCount> = Vec::new();
+    for flag in commands.iter().flat_map(|c| &c.flags) {
+        match counts.iter_mut().find(|c| c.rule_id == flag.rule_id) {
+            Some(count) => count.count += 1,
+            None => counts.push(RuleCount {
+                rule_id: flag.rule_id.clone(),
+                description: flag.description.clone(),
+                severity: flag.severity,
+                count: 1,
+            }),
+        }
+    }
+    counts.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| b.count.cmp(&a.count))
+            .then_with(|| a.rule_id.cmp(&b.rule_id))
+    });
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::bash_rules::default_rules;
+    use crate::session_index::test_util::{jsonl, write_session, write_session_raw};
+    use serde_json::json;
+
+    fn bash_use(ts: &str, id: &str, command: &str) -> Value {
+        json!({"type": "assistant", "timestamp": ts, "cwd": "/proj", "message": {"content": [
+            {"type": "tool_use", "id": id, "name": "Bash",
+             "input": {"command": command, "description": "Run it"}}
+        ]}})
+    }
+
+    fn bash_result(ts: &str, id: &str, content: &str, is_error: bool) -> Value {
+        json!({"type": "user", "timestamp": ts, "message": {"content": [
+            {"type": "tool_result", "tool_use_id": id, "content": content, "is_error": is_error}
+        ]}})
+    }
+
+    #[test]
+    fn test_bash_history() {
+        let mut ok = bash_result("2025-01-01T00:00:02Z", "b1", "ok", false);
+        ok["toolUseResult"] =
+            json!({"stdout": "test result: ok", "stderr": "", "interrupted": false});
+        let lines = [
+            bash_use("2025-01-01T00:00:00Z", "b1", "cd /proj && cargo test"),
+            ok,
+            bash_use("2025-01-01T00:00:03Z", "b2", "rm -rf target && cargo build"),
+            bash_result(
+                "2025-01-01T00:00:04Z",
+                "b2",
+                "Exit code 101\nerror: could not compile",
+                true,
+            ),
+            bash_use("2025-01-01T00:00:05Z", "b3", "sleep 100"),
+        ];
+        let session = write_session("bash-history", &lines);
+        let index = session.index("/proj");
+        let history = get_bash_history(&index, session.path(), &default_rules()).unwrap();
+
+        let commands = &history.commands;
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0].command, "cd /proj && cargo test");
+        assert_eq!(commands[0].description.as_deref(), Some("Run it"));
+        assert_eq!(commands[0].cwd.as_deref(), Some("/proj"));
+        assert_eq!(commands[0].output, "test result: ok");
+        assert_eq!(commands[0].exit_code, Some(0));
+        assert_eq!(commands[0].duration_ms, Some(2000));
+        assert_eq!(commands[1].exit_code, Some(101));
+        assert!(commands[1].is_error);
+        assert_eq!(commands[1].flags[0].rule_id, "rm-rf");
+        assert_eq!(commands[2].exit_code, None);
+
+        let cargo = &history.executables[0];
+        assert_eq!(cargo.executable, "cargo");
+        assert_eq!((cargo.commands, cargo.errors), (2, 1));
+        assert_eq!(history.flagged.len(), 1);
+        assert_eq!(history.flagged[0].count, 1);
+    }
+
+    #[test]
+    fn test_bash_history_malformed_and_missing_results() {
+        let lines = [
+            "not json at all".to_string(),
+            // Two calls in one message are told apart by id
+            json!({"type": "assistant", "timestamp": "2025-01-01T00:00:00Z",
+            "message": {"content": [
+                {"type": "tool_use", "id": "b1", "name": "Bash", "input": {"command": "ls"}},
+                {"type": "tool_use", "id": "b2", "name": "Bash", "input": {}},
+            ]}})
+            .to_string(),
+            json!({"type": "user", "timestamp": "2025-01-01T00:00:01Z", "message": {"content": [
+                {"type": "tool_result", "tool_use_id": "b1", "is_error": true, "content": [
+                    {"type": "text", "text": "Error: Exit code 2"},
+                    {"type": "text", "text": "ls: denied"},
+                ]},
+                {"type": "tool_result", "tool_use_id": "b2", "is_error": true,
+                 "content": "Command timed out"},
+            ]}})
+            .to_string(),
+            "{\"type\": \"user\", \"message\": ".to_string(),
+            bash_use("2025-01-01T00:00:02Z", "b3", "cargo build").to_string(),
+        ];
+        // A result still being written doesn't count
+        let partial = bash_result("2025-01-01T00:00:09Z", "b3", "ok", false).to_string();
+        let session = write_session_raw(
+            "bash-history-malformed",
+            format!("{}{}", jsonl(&lines), &partial[..20]),
+        );
+        let index = session.index("/proj");
+        let history = get_bash_history(&index, session.path(), &default_rules()).unwrap();
+
+        let commands = &history.commands;
+        let ids: Vec<&str> = commands.iter().map(|c| c.tool_use_id.as_str()).collect();
+        assert_eq!(ids, ["b1", "b2", "b3"]);
+        assert_eq!(commands[0].exit_code, Some(2));
+        assert_eq!(commands[0].output, "Error: Exit code 2\nls: denied");
+        assert_eq!(commands[0].duration_ms, Some(1000));
+        // No command, and an error without an exit code
+        assert_eq!(commands[1].command, "");
+        assert!(commands[1].executables.is_empty());
+        assert!(commands[1].is_error);
+        assert_eq!(commands[1].exit_code, None);
+        // No result yet
+        assert_eq!(commands[2].tool_result_line, None);
+        assert_eq!(
+            (commands[2].exit_code, commands[2].duration_ms),
+            (None, None)
+        );
+        assert!(!commands[2].is_error);
+        assert_eq!(commands[2].output, "");
+
+        let executables: Vec<(&str, u32)> = history
+            .executables
+            .iter()
+            .map(|e| (e.executable.as_str(), e.errors))
+            .collect();
+        assert_eq!(executables, [("cargo", 0), ("ls", 1)]);
+    }
+}
@@ -0,0 +1,326 @@
+//! Conversation branches.
+//!
+//! A session is a tree (strictly, a forest) of events linked by `parentUuid`
+//! (or, for compaction boundaries, `logicalParentUuid`). Rewinds, edited
+//! prompts and resumed sessions start a new child under an earlier event, so
+//! the file interleaves several branches. This module recovers that structure
+//! from the index.
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use super::intern::UuidId;
+use super::types::SessionIndex;
+
+/// The conversation DAG of a session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationGraph {
+    /// UUID of the leaf of the active branch
+    pub active_leaf: Option<String>,
+    /// Events with more than one child
+    pub branch_points: Vec<BranchPoint>,
+    /// One branch per leaf, ordered by leaf line
+    pub branches: Vec<ConversationBranch>,
+}
+
+/// An event where the conversation forks.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchPoint {
+    pub uuid: String,
+    pub line: u32,
+    /// Line numbers of the first event of each child branch, ascending
+    pub child_lines: Vec<u32>,
+}
+
+/// A root-to-leaf path through the conversation.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationBranch {
+    pub leaf_uuid: String,
+    pub leaf_line: u32,
+    /// Line of the event where this branch leaves the active branch
+    /// (None for the active branch and for disconnected roots)
+    pub fork_line: Option<u32>,
+    /// First line that belongs only to this branch
+    pub first_line: u32,
+    /// Number of events that belong only to this branch
+    pub event_count: u32,
+    /// Whether this is the active branch
+    pub is_active: bool,
+    /// Whether this branch was left behind (every branch but the active one)
+    pub abandoned: bool,
+}
+
+/// Build the conversation graph for a session.
+///
+/// The active leaf is the latest leaf below the event a summary names via
+/// `leafUuid` (Claude writes summaries at the top of the file, so their line
+/// doesn't matter). Without a summary, or when its leaf isn't in this file,
+/// it is the leaf of the most recently written event.
+pub fn get_conversation_graph(index: &SessionIndex) -> ConversationGraph {
+    let children = build_children(index);
+
+    let leaves: Vec<UuidId> = (0..index.uuid_count())
+        .filter(|&id| index.line_for_id(id).is_some() && children[id as usize].is_empty())
+        .collect();
+
+    let active_leaf = find_active_leaf(index, &leaves);
+    let active_path: HashSet<UuidId> = active_leaf
+        .map(|leaf| path_to_root(index, leaf).collect())
+        .unwrap_or_default();
+
+    let mut branch_points: Vec<BranchPoint> = (0..index.uuid_count())
+        .filter(|&id| children[id as usize].len() > 1)
+        .filter_map(|id| {
+            let mut child_lines: Vec<u32> = children[id as usize]
+                .iter()
+                .filter_map(|&child| index.line_for_id(child))
+                .collect();
+            child_lines.sort();
+            Some(BranchPoint {
+                uuid: index.uuid_str(id)?.to_string(),
+                line: index.line_for_id(id)?,
+                child_lines,
+            })
+        })
+        .collect();
+    branch_points.sort_by_key(|b| b.line);
+
+    let mut branches: Vec<ConversationBranch> = leaves
+        .iter()
+        .filter_map(|&leaf| {
+            let is_active = Some(leaf) == active_leaf;
+            let leaf_line = index.line_for_id(leaf)?;
+
+            // Walk up until we join the active branch (or reach a root)
+            let mut first_line = leaf_line;
+            let mut event_count = 0;
+            let mut fork_line = None;
+            for id in path_to_root(index, leaf) {
+                if !is_active && active_path.contains(&id) {
+                    fork_line = index.line_for_id(id);
+                    break;
+                }
+                first_line = index.line_for_id(id)?;
+                event_count += 1;
+            }
+
+            Some(ConversationBranch {
+                leaf_uuid: index.uuid_str(leaf)?.to_string(),
+                leaf_line,
+                fork_line,
+                first_line,
+                event_count,
+                is_active,
+                abandoned: !is_active,
+            })
+        })
+        .collect();
+    branches.sort_by_key(|b| b.leaf_line);
+
+    ConversationGraph {
+        active_leaf: active_leaf
+            .and_then(|id| index.uuid_str(id))
+            .map(String::from),
+        branch_points,
+        branches,
+    }
+}
+
+/// Get the line numbers of every event on the branch ending at `leaf_uuid`,
+/// in file order. Returns None if the UUID isn't in the index.
+pub fn get_branch_lines(index: &SessionIndex, leaf_uuid: &str) -> Option<Vec<u32>> {
+    index.line_for_uuid(leaf_uuid)?;
+    let leaf = index.uuid_id(leaf_uuid)?;
+
+    let mut lines: Vec<u32> = path_to_root(index, leaf)
+        .filter_map(|id| index.line_for_id(id))
+        .collect();
+    lines.reverse();
+    Some(lines)
+}
+
+/// Child ids of every interned UUID. Only events present in this file count
+/// as children, so a parent from another file becomes a root.
+fn build_children(index: &SessionIndex) -> Vec<Vec<UuidId>> {
+    let mut children = vec![Vec::new(); index.uuid_count() as usize];
+    for id in 0..index.uuid_count() {
+        if index.line_for_id(id).is_none() {
+            continue;
+        }
+        if let Some(parent) = index.parent_id(id) {
+            if index.line_for_id(parent).is_some() {
+                children[parent as usize].push(id);
+            }
+        }
+    }
+    children
+}
+
+/// Pick the active leaf (see `get_conversation_graph`).
+fn find_active_leaf(index: &SessionIndex, leaves: &[UuidId]) -> Option<UuidId> {
+    let latest_below = |root: Option<UuidId>| {
+        leaves
+            .iter()
+            .copied()
+            .filter(|&leaf| root.is_none_or(|root| path_to_root(index, leaf).any(|id| id == root)))
+            .max_by_key(|&id| index.line_for_id(id))
+    };
+
+    index
+        .latest_summary_leaf()
+        .and_then(|(_, leaf)| latest_below(Some(leaf)))
+        .or_else(|| latest_below(None))
+}
+
+/// Iterate from an event up through its ancestors present in the file.
+pub(super) fn path_to_root(
+    index: &SessionIndex,
+    leaf: UuidId,
+) -> impl Iterator<Item = UuidId> + '_ {
+    let mut seen = HashSet::new();
+    std::iter::successors(Some(leaf), move |&id| index.parent_id(id))
+        .take_while(move |&id| index.line_for_id(id).is_some() && seen.insert(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::write_session;
+    use serde_json::json;
+
+    /// Build an index from (uuid, parent) pairs, one line each.
+    fn index_from(events: &[(&str, Option<&str>)]) -> SessionIndex {
+        let mut index = SessionIndex::empty();
+        for (uuid, parent) in events {
+            let line = index.push_line(1);
+            index.record_uuid(uuid, *parent, line);
+        }
+        index
+    }
+
+    #[test]
+    fn test_rewind_creates_abandoned_branch() {
+        // a - b - c        (abandoned)
+        //      \
+        //       d - e      (active, written last)
+        let index = index_from(&[
+            ("a", None),
+            ("b", Some("a")),
+            ("c", Some("b")),
+            ("d", Some("b")),
+            ("e", Some("d")),
+        ]);
+
+        let graph = get_conversation_graph(&index);
+        assert_eq!(graph.active_leaf.as_deref(), Some("e"));
+        assert_eq!(graph.branch_points.len(), 1);
+        assert_eq!(graph.branch_points[0].uuid, "b");
+        assert_eq!(graph.branch_points[0].child_lines, vec![2, 3]);
+
+        let abandoned = &graph.branches[0];
+        assert_eq!(abandoned.leaf_uuid, "c");
+        assert!(abandoned.abandoned);
+        assert_eq!(abandoned.fork_line, Some(1));
+        assert_eq!(abandoned.first_line, 2);
+        assert_eq!(abandoned.event_count, 1);
+
+        let active = &graph.branches[1];
+        assert!(active.is_active && !active.abandoned);
+        assert_eq!(active.event_count, 4);
+
+        assert_eq!(get_branch_lines(&index, "c"), Some(vec![0, 1, 2]));
+        assert_eq!(get_branch_lines(&index, "e"), Some(vec![0, 1, 3, 4]));
+        assert_eq!(get_branch_lines(&index, "missing"), None);
+    }
+
+    #[test]
+    fn test_summary_leaf_selects_active_branch() {
+        let mut index = index_from(&[("a", None), ("b", Some("a")), ("c", Some("a"))]);
+        let line = index.push_line(1);
+        index.record_summary_leaf("b", line);
+
+        let graph = get_conversation_graph(&index);
+        assert_eq!(graph.active_leaf.as_deref(), Some("b"));
+
+        // The conversation continuing below the summary leaf extends it
+        let line = index.push_line(1);
+        index.record_uuid("d", Some("b"), line);
+        let graph = get_conversation_graph(&index);
+        assert_eq!(graph.active_leaf.as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn test_summary_at_top_selects_active_branch() {
+        // Claude writes the summary before the events it describes
+        let mut index = SessionIndex::empty();
+        let line = index.push_line(1);
+        index.record_summary_leaf("c", line);
+        for (uuid, parent) in [
+            ("a", None),
+            ("b", Some("a")),
+            ("c", Some("b")),
+            ("d", Some("a")),
+        ] {
+            let line = index.push_line(1);
+            index.record_uuid(uuid, parent, line);
+        }
+
+        let graph = get_conversation_graph(&index);
+        assert_eq!(graph.active_leaf.as_deref(), Some("c"));
+        let active: Vec<&str> = graph
+            .branches
+            .iter()
+            .filter(|b| b.is_active)
+            .map(|b| b.leaf_uuid.as_str())
+            .collect();
+        assert_eq!(active, vec!["c"]);
+
+        // A summary of a conversation from another file is ignored
+        let mut index = SessionIndex::empty();
+        let line = index.push_line(1);
+        index.record_summary_leaf("elsewhere", line);
+        let line = index.push_line(1);
+        index.record_uuid("a", None, line);
+        let graph = get_conversation_graph(&index);
+        assert_eq!(graph.active_leaf.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_missing_parent_starts_new_root() {
+        // A resumed session refers to a parent from another file
+        let index = index_from(&[("x", Some("elsewhere")), ("y", Some("x"))]);
+        let graph = get_conversation_graph(&index);
+        assert_eq!(graph.branches.len(), 1);
+        assert_eq!(get_branch_lines(&index, "y"), Some(vec![0, 1]));
+        assert_eq!(get_branch_lines(&index, "elsewhere"), None);
+    }
+
+    #[test]
+    fn test_compaction_continues_the_branch() {
+        let session = write_session(
+            "branches-compacted",
+            &[
+                json!({"type": "user", "uuid": "u1", "parentUuid": null,
+                       "message": {"role": "user", "content": "first"}}),
+                json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1",
+                       "message": {"role": "assistant", "content": []}}),
+                json!({"type": "system", "subtype": "compact_boundary", "uuid": "c1",
+                       "parentUuid": null, "logicalParentUuid": "a1"}),
+                json!({"type": "user", "uuid": "s1", "parentUuid": "c1", "isCompactSummary": true,
+                       "message": {"role": "user", "content": "summary"}}),
+                json!({"type": "user", "uuid": "u2", "parentUuid": "s1",
+                       "message": {"role": "user", "content": "second"}}),
+            ],
+        );
+        let index = session.index("/project");
+
+        let graph = get_conversation_graph(&index);
+        assert_eq!(graph.active_leaf.as_deref(), Some("u2"));
+        assert_eq!(graph.branches.len(), 1);
+        assert!(graph.branches.iter().all(|b| b.is_active && !b.abandoned));
+        assert_eq!(get_branch_lines(&index, "u2"), Some(vec![0, 1, 2, 3, 4]));
+    }
+}
@@ -4,14 +4,19 @@
 
 use serde::Deserialize;
 use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
+use std::hash::Hasher;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 
 use crate::claude_code::{FileEdit, FileEditType};
 
-use super::types::{EditMetadata, SessionIndex};
+use super::intern::UuidId;
+use super::stats::StatsEntry;
+use super::tool_calls::{output_size, summarize_input};
+use super::types::{EditMetadata, PrefixFingerprint, SessionIndex};
 
 /// Build a complete session index from a JSONL file.
 ///
@@ -21,6 +26,9 @@ use super::types::{EditMetadata, SessionIndex};
 /// - Parent chain for context walking
 /// - Human message boundaries
 /// - File edits
+///
+/// A trailing line without a newline is treated as still being written and is
+/// left for the next incremental update to pick up.
 pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<SessionIndex, String> {
     let metadata = fs::metadata(session_file)
         .map_err(|e| format!("Failed to read file metadata: {}", e))?;
@@ -28,11 +36,10 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
     let file = File::open(session_file)
         .map_err(|e| format!("Failed to open session file: {}", e))?;
 
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
     let mut index = SessionIndex::empty();
 
     // Track file state
-    index.file_size = metadata.len();
     index.last_modified = metadata
         .modified()
         .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
@@ -42,29 +49,37 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
     let mut files_with_prior_content: HashSet<String> = HashSet::new();
     let mut file_timestamps: HashMap<String, String> = HashMap::new();
 
-    let mut byte_offset: u64 = 0;
-
-    for (sequence, line_result) in reader.lines().enumerate() {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(_) => continue,
-        };
+    let mut buf = Vec::new();
 
-        let line_len = line.len() + 1; // +1 for newline
-        let seq = sequence as u32;
+    loop {
+        let line_len = read_complete_line(&mut reader, &mut buf)
+            .map_err(|e| format!("Failed to read session file: {}", e))?;
+        if line_len == 0 {
+            break;
+        }
 
-        // Record line offset
-        index.line_offsets.push((byte_offset, line_len));
+        // Record line offset (only complete lines are indexed; any
+        // unterminated tail stays pending)
+        let byte_offset = index.file_size;
+        let seq = index.push_line(line_len);
 
         // Parse the JSON entry
-        if let Ok(entry) = serde_json::from_str::<JsonEntry>(&line) {
-            // Extract UUID and parent UUID
-            if let Some(ref uuid) = entry.uuid {
-                index.uuid_to_line.insert(uuid.clone(), seq);
-
-                if let Some(ref parent) = entry.parent_uuid {
-                    index.parent_map.insert(uuid.clone(), parent.clone());
-                }
+        if let Ok(entry) = serde_json::from_slice::<JsonEntry>(trim_line_ending(&buf)) {
+            // Extract UUID and parent UUID. A compaction boundary has no
+            // parentUuid; its logicalParentUuid links it to the conversation
+            // it continues
+            let parent_uuid = entry
+                .parent_uuid
+                .as_deref()
+                .or(entry.logical_parent_uuid.as_deref());
+            let uuid_id = entry
+                .uuid
+                .as_deref()
+                .map(|uuid| index.record_uuid(uuid, parent_uuid, seq));
+
+            // Summaries point at the leaf of the conversation they describe
+            if let Some(ref leaf_uuid) = entry.leaf_uuid {
+                index.record_summary_leaf(leaf_uuid, seq);
             }
 
             // Check if this is a human message boundary
@@ -72,6 +87,32 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
                 index.human_message_lines.push(seq);
             }
 
+            // Track tool calls and their results
+            if let Some(content) = entry.message.as_ref().and_then(|m| m.content.as_ref()) {
+                record_tool_calls(
+                    &mut index,
+                    content,
+                    seq,
+                    entry.timestamp.as_deref(),
+                    entry.tool_use_result.as_ref(),
+                );
+            }
+
+            // Accumulate session statistics
+            index.stats.record(
+                seq,
+                &StatsEntry {
+                    entry_type: entry.entry_type.as_deref(),
+                    subtype: entry.subtype.as_deref(),
+                    timestamp: entry.timestamp.as_deref(),
+                    message_id: entry.message.as_ref().and_then(|m| m.id.as_deref()),
+                    content: entry.message.as_ref().and_then(|m| m.content.as_ref()),
+                    usage: entry.message.as_ref().and_then(|m| m.usage.as_ref()),
+                    compact_metadata: entry.compact_metadata.as_ref(),
+                    is_api_error: entry.is_api_error_message == Some(true),
+                },
+            );
+
             // Extract file edits from assistant messages
             if entry.entry_type.as_deref() == Some("assistant") {
                 if let Some(ref message) = entry.message {
@@ -83,8 +124,7 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
                                     project_path,
                                     seq,
                                     byte_offset,
-                                    entry.uuid.as_deref(),
-                                    entry.parent_uuid.as_deref(),
+                                    uuid_id,
                                     entry.timestamp.as_deref(),
                                     &mut index,
                                     &mut file_operations,
@@ -97,10 +137,11 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
                 }
             }
         }
-
-        byte_offset += line_len as u64;
     }
 
+    index.fingerprint = fingerprint_prefix(reader.get_mut(), &index)
+        .map_err(|e| format!("Failed to fingerprint session file: {}", e))?;
+
     // Build final file edits list
     finalize_file_edits(
         &mut index,
@@ -115,6 +156,109 @@ pub fn build_session_index(session_file: &Path, project_path: &str) -> Result<Se
     Ok(index)
 }
 
+/// Read the next newline-terminated line into `buf`.
+///
+/// Returns the number of bytes consumed including the newline, or 0 at EOF.
+/// An unterminated tail (a line still being written) also returns 0, so
+/// callers never record a partial line.
+pub(super) fn read_complete_line<R: BufRead>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> io::Result<usize> {
+    buf.clear();
+    let bytes_read = reader.read_until(b'\n', buf)?;
+    if buf.last() != Some(&b'\n') {
+        return Ok(0);
+    }
+    Ok(bytes_read)
+}
+
+/// Strip the trailing `\n` (and `\r`, if present) from a raw line.
+pub(super) fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Fingerprint the indexed prefix of a session file by hashing its first and
+/// last indexed lines, read back from disk using the recorded offsets.
+pub(super) fn fingerprint_prefix(
+    file: &mut File,
+    index: &SessionIndex,
+) -> io::Result<PrefixFingerprint> {
+    let last_line = match index.total_events().checked_sub(1) {
+        Some(line) => line,
+        None => return Ok(PrefixFingerprint::default()),
+    };
+    let (first, last) = match (index.line_offset(0), index.line_offset(last_line)) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(PrefixFingerprint::default()),
+    };
+
+    Ok(PrefixFingerprint {
+        first_line: hash_line_at(file, first)?,
+        last_line: hash_line_at(file, last)?,
+    })
+}
+
+/// Hash the raw bytes of one line given its (byte_offset, line_length).
+fn hash_line_at(file: &mut File, (offset, length): (u64, usize)) -> io::Result<u64> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buffer = vec![0u8; length];
+    file.read_exact(&mut buffer)?;
+
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&buffer);
+    Ok(hasher.finish())
+}
+
+/// Record the tool_use / tool_result content items on a line.
+///
+/// `tool_use_result` is the entry's `toolUseResult`, which names the sub-agent
+/// a Task call launched.
+pub(super) fn record_tool_calls(
+    index: &mut SessionIndex,
+    content: &Value,
+    line: u32,
+    timestamp: Option<&str>,
+    tool_use_result: Option<&Value>,
+) {
+    let Value::Array(items) = content else {
+        return;
+    };
+
+    for item in items {
+        match item.get("type").and_then(|v| v.as_str()) {
+            Some("tool_use") => {
+                if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
+                    let name = item.get("name").and_then(|v| v.as_str());
+                    let call = index.record_tool_call(id, line);
+                    call.tool_use_line = Some(line);
+                    call.name = name.map(String::from);
+                    call.input_summary = summarize_input(
+                        name.unwrap_or(""),
+                        item.get("input").unwrap_or(&Value::Null),
+                    );
+                    call.started_at = timestamp.map(String::from);
+                }
+            }
+            Some("tool_result") => {
+                if let Some(id) = item.get("tool_use_id").and_then(|v| v.as_str()) {
+                    let call = index.record_tool_call(id, line);
+                    call.tool_result_line = Some(line);
+                    call.ended_at = timestamp.map(String::from);
+                    call.is_error = item.get("is_error").and_then(|v| v.as_bool()) == Some(true);
+                    call.output_bytes = item.get("content").map(output_size).unwrap_or(0);
+                    call.agent_id = tool_use_result
+                        .and_then(|r| r.get("agentId"))
+                        .and_then(|v| v.as_str())
+                        .map(String::from);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 /// Check if an entry is a human message (actual user input, not tool results).
 fn is_human_message(entry: &JsonEntry) -> bool {
     // Must be a user message
@@ -154,14 +298,32 @@ fn is_human_message(entry: &JsonEntry) -> bool {
     true
 }
 
+/// The (old_string, new_string) pairs of an Edit, or of each edit of a
+/// MultiEdit.
+pub(super) fn edit_pairs<'a>(tool_name: &str, input: &'a Value) -> Vec<(&'a str, &'a str)> {
+    let pair = |edit: &'a Value| {
+        let text = |key: &str| edit.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        (text("old_string"), text("new_string"))
+    };
+    match tool_name {
+        "MultiEdit" => input
+            .get("edits")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(pair)
+            .collect(),
+        _ => vec![pair(input)],
+    }
+}
+
 /// Process a potential tool_use entry for file edits.
 fn process_tool_use(
     item: &Value,
     project_path: &str,
     sequence: u32,
     _byte_offset: u64,
-    uuid: Option<&str>,
-    _parent_uuid: Option<&str>,
+    uuid: Option<UuidId>,
     timestamp: Option<&str>,
     index: &mut SessionIndex,
     file_operations: &mut HashMap<String, FileEditType>,
@@ -184,15 +346,17 @@ fn process_tool_use(
     };
 
     match tool_name {
-        "Edit" => {
+        "Edit" | "MultiEdit" => {
             if let Some(file_path) = input.get("file_path").and_then(|v| v.as_str()) {
                 let rel_path = make_relative_path(file_path, project_path);
 
-                // Check if this edit has old_string content (indicates existing file)
-                if let Some(old_str) = input.get("old_string").and_then(|v| v.as_str()) {
+                for (old_str, new_str) in edit_pairs(tool_name, input) {
+                    // Check if this edit has old_string content (indicates existing file)
                     if !old_str.is_empty() {
                         files_with_prior_content.insert(rel_path.clone());
                     }
+
+                    index.record_line_changes(&rel_path, old_str, new_str);
                 }
 
                 // Mark as modified
@@ -204,12 +368,7 @@ fn process_tool_use(
                 }
 
                 // Record edit metadata
-                index.edit_metadata.insert(
-                    sequence,
-                    EditMetadata {
-                        uuid: uuid.map(String::from),
-                    },
-                );
+                index.edit_metadata.insert(sequence, EditMetadata { uuid });
 
                 // Track line for this file
                 index
@@ -228,18 +387,16 @@ fn process_tool_use(
                     file_operations.insert(rel_path.clone(), FileEditType::Added);
                 }
 
+                let content = input.get("content").and_then(|v| v.as_str());
+                index.record_line_changes(&rel_path, "", content.unwrap_or(""));
+
                 // Track timestamp
                 if let Some(ts) = timestamp {
                     file_timestamps.insert(rel_path.clone(), ts.to_string());
                 }
 
                 // Record edit metadata
-                index.edit_metadata.insert(
-                    sequence,
-                    EditMetadata {
-                        uuid: uuid.map(String::from),
-                    },
-                );
+                index.edit_metadata.insert(sequence, EditMetadata { uuid });
 
                 // Track line for this file
                 index
@@ -272,6 +429,8 @@ fn finalize_file_edits(
                 path,
                 edit_type,
                 last_edited_at,
+                lines_added: 0,
+                lines_removed: 0,
             }
         })
         .collect();
@@ -279,10 +438,11 @@ fn finalize_file_edits(
     // Sort by path for consistent display
     edits.sort_by(|a, b| a.path.cmp(&b.path));
     index.file_edits = edits;
+    index.apply_line_changes();
 }
 
 /// Convert an absolute file path to a relative path from the project root.
-fn make_relative_path(file_path: &str, project_path: &str) -> String {
+pub(super) fn make_relative_path(file_path: &str, project_path: &str) -> String {
     let project = project_path.trim_end_matches('/');
     if file_path.starts_with(project) {
         file_path[project.len()..]
@@ -302,6 +462,10 @@ struct JsonEntry {
     uuid: Option<String>,
     #[serde(rename = "parentUuid")]
     parent_uuid: Option<String>,
+    #[serde(rename = "logicalParentUuid")]
+    logical_parent_uuid: Option<String>,
+    #[serde(rename = "leafUuid")]
+    leaf_uuid: Option<String>,
     #[serde(rename = "userType")]
     user_type: Option<String>,
     #[serde(rename = "isCompactSummary")]
@@ -310,9 +474,18 @@ struct JsonEntry {
     is_meta: Option<bool>,
     message: Option<JsonMessage>,
     timestamp: Option<String>,
+    #[serde(rename = "toolUseResult")]
+    tool_use_result: Option<Value>,
+    subtype: Option<String>,
+    #[serde(rename = "compactMetadata")]
+    compact_metadata: Option<Value>,
+    #[serde(rename = "isApiErrorMessage")]
+    is_api_error_message: Option<bool>,
 }
 
 #[derive(Deserialize)]
 struct JsonMessage {
+    id: Option<String>,
     content: Option<Value>,
+    usage: Option<Value>,
 }
@@ -0,0 +1,224 @@
+//! Shared cache of session indices.
+//!
+//! Indices are handed out as immutable `Arc` snapshots, so readers never copy
+//! the index. Updates go through `Arc::make_mut`, which only clones when a
+//! reader is still holding the previous snapshot.
+//!
+//! Indices for sessions that are no longer watched stay cached so reopening a
+//! session only needs an incremental update, but at most
+//! `MAX_UNWATCHED_INDICES` of them are kept (least recently used are evicted).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::types::SessionIndex;
+
+/// Maximum number of unwatched session indices kept in memory.
+pub const MAX_UNWATCHED_INDICES: usize = 4;
+
+struct CacheEntry {
+    index: Arc<SessionIndex>,
+    /// Whether a watcher is currently keeping this index up to date
+    watched: bool,
+    /// Logical timestamp of the last access (for LRU eviction)
+    last_used: u64,
+}
+
+/// Session indices keyed by "project_path:session_id".
+pub struct IndexCache {
+    entries: HashMap<String, CacheEntry>,
+    /// Monotonic access counter
+    clock: u64,
+    max_unwatched: usize,
+}
+
+impl IndexCache {
+    pub fn new() -> Self {
+        Self::with_max_unwatched(MAX_UNWATCHED_INDICES)
+    }
+
+    /// Create a cache that keeps at most `max_unwatched` unwatched indices.
+    pub fn with_max_unwatched(max_unwatched: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            clock: 0,
+            max_unwatched,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Get a shared snapshot of an index.
+    pub fn get(&mut self, key: &str) -> Option<Arc<SessionIndex>> {
+        let now = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = now;
+        Some(Arc::clone(&entry.index))
+    }
+
+    /// Get an index for updating in place (copy-on-write if a snapshot is held).
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut SessionIndex> {
+        let now = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = now;
+        Some(Arc::make_mut(&mut entry.index))
+    }
+
+    /// Insert (or replace) the index for a session.
+    ///
+    /// Inserting an unwatched index may evict the least recently used
+    /// unwatched indices.
+    pub fn insert(&mut self, key: String, index: SessionIndex, watched: bool) {
+        let now = self.tick();
+        self.entries.insert(
+            key,
+            CacheEntry {
+                index: Arc::new(index),
+                watched,
+                last_used: now,
+            },
+        );
+        if !watched {
+            self.evict_unwatched();
+        }
+    }
+
+    /// Replace an index with an updated copy, but only if the cache still
+    /// holds `previous` (it was not updated or evicted in the meantime).
+    ///
+    /// Returns whether the index was replaced.
+    pub fn replace_snapshot(
+        &mut self,
+        key: &str,
+        previous: &Arc<SessionIndex>,
+        index: SessionIndex,
+    ) -> bool {
+        let now = self.tick();
+        match self.entries.get_mut(key) {
+            Some(entry) if Arc::ptr_eq(&entry.index, previous) => {
+                entry.index = Arc::new(index);
+                entry.last_used = now;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Mark a cached index as watched or unwatched.
+    ///
+    /// Returns false if there is no cached index for the key. Unwatching may
+    /// evict the least recently used unwatched indices.
+    pub fn set_watched(&mut self, key: &str, watched: bool) -> bool {
+        let now = self.tick();
+        let Some(entry) = self.entries.get_mut(key) else {
+            return false;
+        };
+        entry.watched = watched;
+        entry.last_used = now;
+
+        if !watched {
+            self.evict_unwatched();
+        }
+        true
+    }
+
+    /// Drop least recently used unwatched indices beyond the limit.
+    fn evict_unwatched(&mut self) {
+        let mut unwatched: Vec<(u64, String)> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.watched)
+            .map(|(key, entry)| (entry.last_used, key.clone()))
+            .collect();
+
+        if unwatched.len() <= self.max_unwatched {
+            return;
+        }
+
+        unwatched.sort();
+        let excess = unwatched.len() - self.max_unwatched;
+        for (_, key) in unwatched.into_iter().take(excess) {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshots_are_shared_until_mutated() {
+        let mut cache = IndexCache::new();
+        cache.insert("a".to_string(), SessionIndex::empty(), true);
+
+        let first = cache.get("a").unwrap();
+        let second = cache.get("a").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Mutating while a snapshot is held must not affect the snapshot
+        cache.get_mut("a").unwrap().push_line(10);
+        assert_eq!(first.total_events(), 0);
+        assert_eq!(cache.get("a").unwrap().total_events(), 1);
+
+        // Without outstanding snapshots the index is updated in place
+        drop((first, second));
+        let before = Arc::as_ptr(&cache.get("a").unwrap());
+        cache.get_mut("a").unwrap().push_line(10);
+        assert_eq!(Arc::as_ptr(&cache.get("a").unwrap()), before);
+    }
+
+    #[test]
+    fn test_unwatched_indices_are_evicted_lru() {
+        let mut cache = IndexCache::with_max_unwatched(2);
+        for key in ["a", "b", "c", "d"] {
+            cache.insert(key.to_string(), SessionIndex::empty(), true);
+        }
+
+        assert!(cache.set_watched("a", false));
+        assert!(cache.set_watched("b", false));
+        // Touch "a" so "b" becomes the least recently used
+        assert!(cache.get("a").is_some());
+        assert!(cache.set_watched("c", false));
+
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        // Watched indices are never evicted
+        assert!(cache.get("d").is_some());
+
+        // Re-watching keeps the cached index
+        assert!(cache.set_watched("a", true));
+        assert!(!cache.set_watched("missing", true));
+    }
+
+    #[test]
+    fn test_unwatched_insert_is_evictable() {
+        let mut cache = IndexCache::with_max_unwatched(1);
+        // An index whose session was unwatched while it was being built
+        cache.insert("a".to_string(), SessionIndex::empty(), false);
+        cache.insert("b".to_string(), SessionIndex::empty(), false);
+
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+    }
+
+    #[test]
+    fn test_replace_snapshot_only_if_unchanged() {
+        let mut cache = IndexCache::new();
+        cache.insert("a".to_string(), SessionIndex::empty(), true);
+        let snapshot = cache.get("a").unwrap();
+
+        let mut updated = (*snapshot).clone();
+        updated.push_line(10);
+        assert!(cache.replace_snapshot("a", &snapshot, updated.clone()));
+        assert_eq!(cache.get("a").unwrap().total_events(), 1);
+
+        // The cached index moved on, so the stale update is dropped
+        assert!(!cache.replace_snapshot("a", &snapshot, updated));
+        assert!(!cache.replace_snapshot("missing", &snapshot, SessionIndex::empty()));
+    }
+}
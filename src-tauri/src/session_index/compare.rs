@@ -0,0 +1,462 @@
+//! Side-by-side comparison of two sessions.
+//!
+//! Useful for A/B-testing prompts or models on the same task: prompts are
+//! aligned by their text (longest common subsequence, so an extra prompt on
+//! one side doesn't shift the rest), and the edits of each session are
+//! replayed to compare the files they end up with.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::Path;
+
+use super::edits::{get_edit_records, prompt_text, EditRecord};
+use super::hunks::changed_lines;
+use super::queries::get_value_at_line;
+use super::stats::{get_session_stats, SessionStats};
+use super::types::SessionIndex;
+
+/// One side of a comparison.
+pub struct SessionSide<'a> {
+    pub index: &'a SessionIndex,
+    pub session_file: &'a Path,
+    pub project_path: &'a str,
+    pub session_id: &'a str,
+}
+
+/// A session being compared.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedSession {
+    pub project_path: String,
+    pub session_id: String,
+    pub stats: SessionStats,
+}
+
+/// A human prompt and the tools called in response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTurn {
+    pub line: u32,
+    /// Prompt text, whitespace-normalized
+    pub text: String,
+    /// Names of the tools called before the next prompt, in order
+    pub tool_calls: Vec<String>,
+}
+
+/// Prompts aligned across the two sessions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptPair {
+    /// None if the prompt only appears on the right
+    pub left: Option<PromptTurn>,
+    /// None if the prompt only appears on the left
+    pub right: Option<PromptTurn>,
+    pub same_prompt: bool,
+    /// Whether both sides called the same tools in the same order
+    pub same_tool_calls: bool,
+}
+
+/// Calls of one tool on each side.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCountDiff {
+    pub tool_name: String,
+    pub left: u32,
+    pub right: u32,
+}
+
+/// How a file ended up on each side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileStatus {
+    Identical,
+    Different,
+    /// Only edited in the left session
+    LeftOnly,
+    /// Only edited in the right session
+    RightOnly,
+    /// The edits couldn't be replayed on one side
+    Unknown,
+}
+
+/// A file edited by either session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileComparison {
+    /// Path relative to its project
+    pub path: String,
+    pub status: FileStatus,
+    pub left_edits: u32,
+    pub right_edits: u32,
+    /// Content after the left session's edits
+    pub left_content: Option<String>,
+    /// Content after the right session's edits
+    pub right_content: Option<String>,
+    /// Lines the right side has that the left doesn't
+    pub lines_added: u64,
+    /// Lines the left side has that the right doesn't
+    pub lines_removed: u64,
+}
+
+/// Structured diff of two sessions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionComparison {
+    pub left: ComparedSession,
+    pub right: ComparedSession,
+    pub prompts: Vec<PromptPair>,
+    /// Every tool either side called, by name
+    pub tools: Vec<ToolCountDiff>,
+    /// Every file either side edited, by path
+    pub files: Vec<FileComparison>,
+}
+
+/// Compare two sessions.
+pub fn compare_sessions(
+    left: &SessionSide,
+    right: &SessionSide,
+) -> Result<SessionComparison, String> {
+    let left_stats = get_session_stats(left.index);
+    let right_stats = get_session_stats(right.index);
+
+    let prompts = align_prompts(prompt_turns(left)?, prompt_turns(right)?);
+
+    let mut counts: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+    for tool in &left_stats.tools {
+        counts.entry(&tool.tool_name).or_default().0 = tool.calls;
+    }
+    for tool in &right_stats.tools {
+        counts.entry(&tool.tool_name).or_default().1 = tool.calls;
+    }
+    let tools = counts
+        .into_iter()
+        .map(|(tool_name, (left, right))| ToolCountDiff {
+            tool_name: tool_name.to_string(),
+            left,
+            right,
+        })
+        .collect();
+
+    let paths: BTreeSet<&String> = left
+        .index
+        .file_to_edit_lines
+        .keys()
+        .chain(right.index.file_to_edit_lines.keys())
+        .collect();
+    let left_records = get_edit_records(left.index, left.session_file, left.project_path)?;
+    let right_records = get_edit_records(right.index, right.session_file, right.project_path)?;
+    let files = paths
+        .into_iter()
+        .map(|path| compare_file(left, right, &left_records, &right_records, path))
+        .collect();
+
+    Ok(SessionComparison {
+        left: ComparedSession {
+            project_path: left.project_path.to_string(),
+            session_id: left.session_id.to_string(),
+            stats: left_stats,
+        },
+        right: ComparedSession {
+            project_path: right.project_path.to_string(),
+            session_id: right.session_id.to_string(),
+            stats: right_stats,
+        },
+        prompts,
+        tools,
+        files,
+    })
+}
+
+/// Read the human prompts of a session with the tools called after each.
+fn prompt_turns(side: &SessionSide) -> Result<Vec<PromptTurn>, String> {
+    let mut file =
+        File::open(side.session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let mut turns = Vec::with_capacity(side.index.human_message_lines.len());
+    for &line in &side.index.human_message_lines {
+        let text = get_value_at_line(side.index, &mut file, line)?
+            .and_then(|entry| entry.pointer("/message/content").map(prompt_text))
+            .unwrap_or_default();
+        turns.push(PromptTurn {
+            line,
+            text,
+            tool_calls: Vec::new(),
+        });
+    }
+
+    let mut calls: Vec<(u32, &str)> = side
+        .index
+        .tool_calls()
+        .filter_map(|(_, call)| Some((call.tool_use_line?, call.name.as_deref()?)))
+        .collect();
+    calls.sort();
+    for (line, name) in calls {
+        // Calls before the first prompt (e.g. from a resumed session) are dropped
+        let at = turns.partition_point(|t| t.line <= line);
+        if at > 0 {
+            turns[at - 1].tool_calls.push(name.to_string());
+        }
+    }
+
+    Ok(turns)
+}
+
+/// Align prompts by their text. Matching prompts anchor the alignment;
+/// unmatched prompts between two anchors are paired in order.
+fn align_prompts(left: Vec<PromptTurn>, right: Vec<PromptTurn>) -> Vec<PromptPair> {
+    let (n, m) = (left.len(), right.len());
+
+    // lcs[i][j] = length of the LCS of left[i..] and right[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if left[i].text == right[j].text {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut anchors = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if left[i].text == right[j].text {
+            anchors.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    anchors.push((n, m));
+
+    let mut left = left.into_iter().map(Some).collect::<Vec<_>>();
+    let mut right = right.into_iter().map(Some).collect::<Vec<_>>();
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (anchor_i, anchor_j) in anchors {
+        while i < anchor_i || j < anchor_j {
+            let l = if i < anchor_i { left[i].take() } else { None };
+            let r = if j < anchor_j { right[j].take() } else { None };
+            i += usize::from(i < anchor_i);
+            j += usize::from(j < anchor_j);
+            pairs.push(make_pair(l, r));
+        }
+        if anchor_i < n {
+            pairs.push(make_pair(left[i].take(), right[j].take()));
+            i += 1;
+            j += 1;
+        }
+    }
+    pairs
+}
+
+fn make_pair(left: Option<PromptTurn>, right: Option<PromptTurn>) -> PromptPair {
+    let (same_prompt, same_tool_calls) = match (&left, &right) {
+        (Some(l), Some(r)) => (l.text == r.text, l.tool_calls == r.tool_calls),
+        _ => (false, false),
+    };
+    PromptPair {
+        left,
+        right,
+        same_prompt,
+        same_tool_calls,
+    }
+}
+
+/// Compare the final content of a file on both sides.
+fn compare_file(
+    left: &SessionSide,
+    right: &SessionSide,
+    left_records: &[EditRecord],
+    right_records: &[EditRecord],
+    path: &str,
+) -> FileComparison {
+    let left_edits = file_edits(left_records, path);
+    let right_edits = file_edits(right_records, path);
+    let left_content = final_content(left, path, &left_edits);
+    let right_content = final_content(right, path, &right_edits);
+
+    let (status, lines_removed, lines_added) = match (left_edits.is_empty(), right_edits.is_empty())
+    {
+        // Indexed as edited, but neither side has edits that can be replayed
+        (true, true) => (FileStatus::Unknown, 0, 0),
+        (true, false) => (FileStatus::RightOnly, 0, 0),
+        (false, true) => (FileStatus::LeftOnly, 0, 0),
+        (false, false) => match (&left_content, &right_content) {
+            (Some(l), Some(r)) if l == r => (FileStatus::Identical, 0, 0),
+            (Some(l), Some(r)) => {
+                let (removed, added) = changed_lines(l, r);
+                (FileStatus::Different, removed, added)
+            }
+            _ => (FileStatus::Unknown, 0, 0),
+        },
+    };
+
+    FileComparison {
+        path: path.to_string(),
+        status,
+        left_edits: left_edits.len() as u32,
+        right_edits: right_edits.len() as u32,
+        left_content,
+        right_content,
+        lines_added,
+        lines_removed,
+    }
+}
+
+/// The edits of a file, in order.
+fn file_edits<'a>(records: &'a [EditRecord], path: &str) -> Vec<&'a EditRecord> {
+    records
+        .iter()
+        .filter(|record| record.path == path)
+        .collect()
+}
+
+/// Replay a file's edits over its content at git HEAD.
+///
+/// Returns None if an edit's old string isn't found, e.g. when the file
+/// changed outside the session or the session started from uncommitted
+/// changes.
+fn final_content(side: &SessionSide, path: &str, edits: &[&EditRecord]) -> Option<String> {
+    if edits.is_empty() {
+        return None;
+    }
+
+    let mut content = crate::git::get_git_file_diff(side.project_path, path)
+        .ok()
+        .filter(|diff| diff.exists_at_head)
+        .map(|diff| diff.original);
+
+    for edit in edits {
+        content = Some(edit.apply(content.as_deref())?);
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::write_session;
+    use serde_json::json;
+
+    fn turn(text: &str) -> PromptTurn {
+        PromptTurn {
+            line: 0,
+            text: text.to_string(),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_align_prompts() {
+        let left = vec![turn("a"), turn("b"), turn("c")];
+        let right = vec![turn("a"), turn("x"), turn("y"), turn("c")];
+        let pairs = align_prompts(left, right);
+
+        let texts: Vec<(Option<&str>, Option<&str>)> = pairs
+            .iter()
+            .map(|p| {
+                (
+                    p.left.as_ref().map(|t| t.text.as_str()),
+                    p.right.as_ref().map(|t| t.text.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                (Some("a"), Some("a")),
+                (Some("b"), Some("x")),
+                (None, Some("y")),
+                (Some("c"), Some("c")),
+            ]
+        );
+        assert!(pairs[0].same_prompt && !pairs[1].same_prompt);
+    }
+
+    #[test]
+    fn test_compare_sessions() {
+        let project = "/nonexistent-project";
+
+        let session = |name: &str, edits: &[(&str, &str)]| {
+            let mut lines = vec![
+                json!({"type": "user", "userType": "external", "uuid": "u1", "parentUuid": null,
+                "timestamp": "2025-01-01T10:00:00Z",
+                "message": {"role": "user", "content": "  Create   the file "}}),
+            ];
+            lines.push(json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1",
+                "timestamp": "2025-01-01T10:00:05Z",
+                "message": {"content": [{"type": "tool_use", "id": "t1", "name": "Write",
+                    "input": {"file_path": format!("{}/main.rs", project), "content": "fn main() {}\n"}}]}}));
+            for (i, (old, new)) in edits.iter().enumerate() {
+                lines.push(json!({"type": "assistant", "uuid": format!("e{}", i), "parentUuid": "a1",
+                    "timestamp": "2025-01-01T10:00:10Z",
+                    "message": {"content": [{"type": "tool_use", "id": format!("te{}", i), "name": "Edit",
+                        "input": {"file_path": format!("{}/main.rs", project),
+                                  "old_string": old, "new_string": new}}]}}));
+            }
+            let session = write_session(name, &lines);
+            let index = session.index(project);
+            (session, index)
+        };
+
+        let (left, left_index) = session("compare-left", &[("{}", "{ run(); }")]);
+        let (right, right_index) = session("compare-right", &[]);
+        let side = |index, path| SessionSide {
+            index,
+            session_file: path,
+            project_path: project,
+            session_id: "s",
+        };
+        let comparison = compare_sessions(
+            &side(&left_index, left.path()),
+            &side(&right_index, right.path()),
+        )
+        .unwrap();
+
+        assert_eq!(comparison.prompts.len(), 1);
+        let pair = &comparison.prompts[0];
+        assert!(pair.same_prompt);
+        assert!(!pair.same_tool_calls);
+        assert_eq!(pair.left.as_ref().unwrap().text, "Create the file");
+        assert_eq!(pair.left.as_ref().unwrap().tool_calls, ["Write", "Edit"]);
+
+        assert_eq!(
+            comparison.tools,
+            [
+                ToolCountDiff {
+                    tool_name: "Edit".to_string(),
+                    left: 1,
+                    right: 0
+                },
+                ToolCountDiff {
+                    tool_name: "Write".to_string(),
+                    left: 1,
+                    right: 1
+                },
+            ]
+        );
+
+        let file = &comparison.files[0];
+        assert_eq!(file.path, "main.rs");
+        assert_eq!(file.status, FileStatus::Different);
+        assert_eq!(file.left_edits, 2);
+        assert_eq!(file.left_content.as_deref(), Some("fn main() { run(); }\n"));
+        assert_eq!(file.right_content.as_deref(), Some("fn main() {}\n"));
+        assert_eq!((file.lines_removed, file.lines_added), (1, 1));
+
+        // Neither side has records to replay for a path
+        let left_side = side(&left_index, left.path());
+        let right_side = side(&right_index, right.path());
+        let records = get_edit_records(&left_index, left.path(), project).unwrap();
+        let ghost = compare_file(&left_side, &right_side, &records, &[], "ghost.rs");
+        assert_eq!(ghost.status, FileStatus::Unknown);
+        let left_only = compare_file(&left_side, &right_side, &records, &[], "main.rs");
+        assert_eq!(left_only.status, FileStatus::LeftOnly);
+    }
+}
@@ -0,0 +1,406 @@
+//! Compaction-aware context reconstruction.
+//!
+//! After a compaction, Claude Code starts a new chain: a `compact_boundary`
+//! system entry with no `parentUuid` (its `logicalParentUuid` points at the
+//! last pre-compaction event), followed by a user message carrying the
+//! summary (`isCompactSummary`). The index links the boundary to its logical
+//! parent so branches continue across compactions; the context walk stops at
+//! the latest boundary instead, and what it collects is what the model saw:
+//! the summary plus the events since.
+//!
+//! Token counts are estimates (about four characters per token), since the
+//! session file only records actual usage on assistant messages.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::path::Path;
+
+use super::branches::path_to_root;
+use super::queries::get_value_at_line;
+use super::types::SessionIndex;
+
+/// Rough characters per token for English text and code.
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// Estimated tokens for an image block (dimensions aren't recorded).
+const IMAGE_TOKENS: u64 = 1600;
+
+/// How far past a boundary to look for its compact summary.
+const SUMMARY_SEARCH_LINES: u32 = 20;
+
+/// A run of events in the context window.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSegment {
+    /// Lines of the events, in conversation order
+    pub lines: Vec<u32>,
+    pub estimated_tokens: u64,
+}
+
+/// A compaction with token counts before and after.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionBoundary {
+    /// Line of the compact_boundary entry
+    pub line: u32,
+    pub timestamp: Option<String>,
+    /// "manual" (/compact) or "auto"
+    pub trigger: Option<String>,
+    /// Context size before compacting, as reported by Claude Code
+    pub pre_tokens: Option<u64>,
+    /// Estimated size of the context that was compacted
+    pub estimated_before_tokens: u64,
+    /// Line of the compact summary message
+    pub summary_line: Option<u32>,
+    /// Estimated size of the context right after compacting (the summary)
+    pub estimated_after_tokens: u64,
+}
+
+/// The context window at an event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextWindow {
+    /// Line the window was reconstructed for
+    pub line: u32,
+    /// Line of the compaction boundary the window starts at (None if the
+    /// session hasn't been compacted before this event)
+    pub boundary_line: Option<u32>,
+    /// The compact summary the window starts with
+    pub summary: Option<ContextSegment>,
+    /// Events since the boundary (or since the start of the session)
+    pub events: ContextSegment,
+    pub estimated_tokens: u64,
+    /// Context size reported by the API for the latest assistant message in
+    /// the window (input, cache and output tokens)
+    pub reported_tokens: Option<u64>,
+    /// Line of the assistant message `reported_tokens` comes from
+    pub reported_line: Option<u32>,
+    /// Every compaction in the session, in file order
+    pub compactions: Vec<CompactionBoundary>,
+}
+
+/// Reconstruct the context window the model had at a line.
+///
+/// Lines without a UUID (summaries, snapshots) use the closest event before
+/// them.
+pub fn get_context_window(
+    index: &SessionIndex,
+    session_file: &Path,
+    line: u32,
+) -> Result<ContextWindow, String> {
+    if line >= index.total_events() {
+        return Err(format!("Line {} is out of range", line));
+    }
+    let mut file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+
+    let mut uuid = None;
+    for candidate in (0..=line).rev() {
+        let entry = get_value_at_line(index, &mut file, candidate)?;
+        uuid = entry
+            .as_ref()
+            .and_then(|e| str_field(e, "uuid").map(String::from));
+        if uuid.is_some() {
+            break;
+        }
+    }
+    let uuid = uuid.ok_or_else(|| format!("No event at or before line {}", line))?;
+
+    let mut window = ContextWindow {
+        line,
+        boundary_line: None,
+        summary: None,
+        events: ContextSegment::default(),
+        estimated_tokens: 0,
+        reported_tokens: None,
+        reported_line: None,
+        compactions: Vec::new(),
+    };
+
+    for (event_line, entry) in read_chain(index, &mut file, &uuid)? {
+        let tokens = estimate_entry_tokens(&entry);
+        window.estimated_tokens += tokens;
+
+        if is_compact_boundary(&entry) {
+            window.boundary_line = Some(event_line);
+        } else if entry.get("isCompactSummary").and_then(|v| v.as_bool()) == Some(true) {
+            let summary = window.summary.get_or_insert_with(ContextSegment::default);
+            summary.lines.push(event_line);
+            summary.estimated_tokens += tokens;
+        } else {
+            window.events.lines.push(event_line);
+            window.events.estimated_tokens += tokens;
+        }
+
+        if let Some(reported) = reported_context_tokens(&entry) {
+            window.reported_tokens = Some(reported);
+            window.reported_line = Some(event_line);
+        }
+    }
+
+    window.compactions = compaction_boundaries(index, &mut file)?;
+    Ok(window)
+}
+
+/// List every compaction with estimated token counts before and after.
+fn compaction_boundaries(
+    index: &SessionIndex,
+    file: &mut File,
+) -> Result<Vec<CompactionBoundary>, String> {
+    let mut boundaries = Vec::new();
+
+    for compaction in index.stats.compactions() {
+        let Some(entry) = get_value_at_line(index, file, compaction.line)? else {
+            continue;
+        };
+
+        // The compacted context is the chain ending at the logical parent
+        let estimated_before_tokens = match str_field(&entry, "logicalParentUuid") {
+            Some(parent) => read_chain(index, file, parent)?
+                .iter()
+                .map(|(_, e)| estimate_entry_tokens(e))
+                .sum(),
+            None => 0,
+        };
+
+        // The summary follows the boundary as its child; a boundary whose
+        // summary was never written has an ordinary message there instead
+        let mut summary = None;
+        if let Some(uuid) = str_field(&entry, "uuid") {
+            let last = (compaction.line + SUMMARY_SEARCH_LINES).min(index.total_events());
+            for candidate in compaction.line + 1..last {
+                let Some(next) = get_value_at_line(index, file, candidate)? else {
+                    continue;
+                };
+                if str_field(&next, "parentUuid") == Some(uuid) {
+                    if next.get("isCompactSummary").and_then(|v| v.as_bool()) == Some(true) {
+                        summary = Some((candidate, estimate_entry_tokens(&next)));
+                    }
+                    break;
+                }
+            }
+        }
+
+        boundaries.push(CompactionBoundary {
+            line: compaction.line,
+            timestamp: compaction.timestamp.clone(),
+            trigger: compaction.trigger.clone(),
+            pre_tokens: compaction.pre_tokens,
+            estimated_before_tokens,
+            summary_line: summary.map(|(line, _)| line),
+            estimated_after_tokens: estimate_entry_tokens(&entry)
+                + summary.map_or(0, |(_, tokens)| tokens),
+        });
+    }
+
+    Ok(boundaries)
+}
+
+/// Read the chain of events ending at a UUID, back to the latest compaction
+/// boundary (or the root), root first.
+fn read_chain(
+    index: &SessionIndex,
+    file: &mut File,
+    uuid: &str,
+) -> Result<Vec<(u32, Value)>, String> {
+    let Some(id) = index.uuid_id(uuid) else {
+        return Ok(Vec::new());
+    };
+    let lines: Vec<u32> = path_to_root(index, id)
+        .filter_map(|id| index.line_for_id(id))
+        .collect();
+
+    let mut chain = Vec::with_capacity(lines.len());
+    for line in lines {
+        if let Some(entry) = get_value_at_line(index, file, line)? {
+            let boundary = is_compact_boundary(&entry);
+            chain.push((line, entry));
+            if boundary {
+                break;
+            }
+        }
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+fn str_field<'a>(entry: &'a Value, key: &str) -> Option<&'a str> {
+    entry.get(key).and_then(|v| v.as_str())
+}
+
+fn is_compact_boundary(entry: &Value) -> bool {
+    str_field(entry, "type") == Some("system")
+        && str_field(entry, "subtype") == Some("compact_boundary")
+}
+
+/// Context size from an assistant message's usage.
+fn reported_context_tokens(entry: &Value) -> Option<u64> {
+    if str_field(entry, "type") != Some("assistant") {
+        return None;
+    }
+    let usage = entry.pointer("/message/usage")?;
+    let field = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    Some(
+        field("input_tokens")
+            + field("cache_read_input_tokens")
+            + field("cache_creation_input_tokens")
+            + field("output_tokens"),
+    )
+}
+
+/// Estimate the tokens an entry contributes to the context.
+fn estimate_entry_tokens(entry: &Value) -> u64 {
+    match entry.pointer("/message/content") {
+        Some(content) => estimate_content_tokens(content),
+        // System entries contribute their text, if any
+        None => estimate_text(entry.get("content")),
+    }
+}
+
+/// Estimate the tokens of message content (a string or a list of blocks).
+fn estimate_content_tokens(content: &Value) -> u64 {
+    let blocks = match content {
+        Value::String(text) => return (text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN),
+        Value::Array(blocks) => blocks,
+        _ => return 0,
+    };
+
+    blocks
+        .iter()
+        .map(|block| match str_field(block, "type") {
+            Some("image") => IMAGE_TOKENS,
+            Some("text") => estimate_text(block.get("text")),
+            Some("thinking") => estimate_text(block.get("thinking")),
+            Some("tool_use") => estimate_text(block.get("input")),
+            Some("tool_result") => match block.get("content") {
+                Some(content) => estimate_content_tokens(content),
+                None => 0,
+            },
+            _ => estimate_text(Some(block)),
+        })
+        .sum()
+}
+
+/// Estimate the tokens of a string, or of any other value's JSON.
+fn estimate_text(value: Option<&Value>) -> u64 {
+    let chars = match value {
+        Some(Value::String(text)) => text.chars().count(),
+        Some(Value::Null) | None => 0,
+        Some(other) => other.to_string().chars().count(),
+    };
+    (chars as u64).div_ceil(CHARS_PER_TOKEN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::write_session;
+    use serde_json::json;
+
+    #[test]
+    fn test_context_window_after_compaction() {
+        let text = |n: usize| "x".repeat(n);
+        let lines = [
+            json!({"type": "user", "uuid": "u1", "parentUuid": null,
+                   "message": {"content": text(4000)}}),
+            json!({"type": "assistant", "uuid": "a1", "parentUuid": "u1",
+                   "message": {"content": [{"type": "text", "text": text(400)}],
+                               "usage": {"input_tokens": 10, "cache_read_input_tokens": 1000, "output_tokens": 100}}}),
+            json!({"type": "system", "subtype": "compact_boundary", "uuid": "c1", "parentUuid": null,
+                   "logicalParentUuid": "a1", "content": "Conversation compacted",
+                   "compactMetadata": {"trigger": "manual", "preTokens": 1110}}),
+            json!({"type": "user", "uuid": "s1", "parentUuid": "c1", "isCompactSummary": true,
+                   "message": {"content": text(200)}}),
+            json!({"type": "user", "uuid": "u2", "parentUuid": "s1",
+                   "message": {"content": text(40)}}),
+            json!({"type": "summary", "summary": "Fixing things", "leafUuid": "u2"}),
+        ];
+        let session = write_session("context", &lines);
+        let path = session.path();
+        let index = session.index("/proj");
+
+        // Before the compaction, the window is everything so far
+        let before = get_context_window(&index, path, 1).unwrap();
+        assert_eq!(before.boundary_line, None);
+        assert_eq!(before.events.lines, [0, 1]);
+        assert_eq!(before.estimated_tokens, 1000 + 100);
+        assert_eq!(before.reported_tokens, Some(1110));
+
+        // The summary line has no UUID, so it resolves to u2
+        let after = get_context_window(&index, path, 5).unwrap();
+        assert_eq!(after.boundary_line, Some(2));
+        let summary = after.summary.unwrap();
+        assert_eq!(summary.lines, [3]);
+        assert_eq!(summary.estimated_tokens, 50);
+        assert_eq!(after.events.lines, [4]);
+        assert_eq!(after.events.estimated_tokens, 10);
+        assert_eq!(after.reported_tokens, None);
+
+        let compaction = &after.compactions[0];
+        assert_eq!(compaction.line, 2);
+        assert_eq!(compaction.pre_tokens, Some(1110));
+        assert_eq!(compaction.estimated_before_tokens, 1100);
+        assert_eq!(compaction.summary_line, Some(3));
+        // "Conversation compacted" (6 tokens) plus the summary
+        assert_eq!(compaction.estimated_after_tokens, 6 + 50);
+    }
+
+    #[test]
+    fn test_context_window_at_compaction_boundaries() {
+        let text = |n: usize| "x".repeat(n);
+        let lines = [
+            json!({"type": "user", "uuid": "u1", "parentUuid": null,
+                   "message": {"content": text(400)}}),
+            json!({"type": "system", "subtype": "compact_boundary", "uuid": "c1", "parentUuid": null,
+                   "logicalParentUuid": "u1", "content": "Conversation compacted",
+                   "compactMetadata": {"trigger": "auto", "preTokens": 500}}),
+            json!({"type": "user", "uuid": "s1", "parentUuid": "c1", "isCompactSummary": true,
+                   "message": {"content": text(40)}}),
+            json!({"type": "user", "uuid": "u2", "parentUuid": "s1",
+                   "message": {"content": text(80)}}),
+            // Compacted again right away, without a logical parent or summary
+            json!({"type": "system", "subtype": "compact_boundary", "uuid": "c2", "parentUuid": null,
+                   "content": "Conversation compacted"}),
+            json!({"type": "user", "uuid": "u3", "parentUuid": "c2",
+                   "message": {"content": text(8)}}),
+        ];
+        let session = write_session("context-boundaries", &lines);
+        let path = session.path();
+        let index = session.index("/proj");
+        let window = |line: u32| get_context_window(&index, path, line).unwrap();
+
+        // At a boundary, the window is just the boundary
+        let at_boundary = window(1);
+        assert_eq!(at_boundary.boundary_line, Some(1));
+        assert!(at_boundary.summary.is_none());
+        assert!(at_boundary.events.lines.is_empty());
+        assert_eq!(at_boundary.estimated_tokens, 6);
+
+        let between = window(3);
+        assert_eq!(between.boundary_line, Some(1));
+        assert_eq!(between.summary.unwrap().lines, [2]);
+        assert_eq!(between.events.lines, [3]);
+        assert_eq!(between.estimated_tokens, 6 + 10 + 20);
+
+        // Only the latest compaction bounds the window
+        let after_second = window(5);
+        assert_eq!(after_second.boundary_line, Some(4));
+        assert!(after_second.summary.is_none());
+        assert_eq!(after_second.events.lines, [5]);
+        assert_eq!(after_second.estimated_tokens, 6 + 2);
+
+        assert!(get_context_window(&index, path, 6).is_err());
+
+        let compactions = &after_second.compactions;
+        assert_eq!(compactions.len(), 2);
+        assert_eq!(compactions[0].trigger.as_deref(), Some("auto"));
+        assert_eq!(compactions[0].estimated_before_tokens, 100);
+        assert_eq!(compactions[0].summary_line, Some(2));
+        assert_eq!(compactions[0].estimated_after_tokens, 6 + 10);
+        // The message after the second boundary isn't a summary
+        assert_eq!(compactions[1].estimated_before_tokens, 0);
+        assert_eq!(compactions[1].summary_line, None);
+        assert_eq!(compactions[1].estimated_after_tokens, 6);
+    }
+}
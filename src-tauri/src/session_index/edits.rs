@@ -0,0 +1,236 @@
+//! File edits of a session with their content and triggering prompt.
+//!
+//! The index only records which lines edit which file; this module reads
+//! those lines back to recover each Edit, MultiEdit and Write with the human
+//! prompt that led to it.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::path::Path;
+
+use crate::claude_code::truncate_string;
+
+use super::builder::make_relative_path;
+use super::hunks::changed_lines;
+use super::queries::get_value_at_line;
+use super::types::SessionIndex;
+
+/// Prompts longer than this are truncated.
+const MAX_PROMPT_CHARS: usize = 500;
+
+/// An Edit or Write tool call, or one edit of a MultiEdit.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditRecord {
+    /// Path relative to the project
+    pub path: String,
+    /// Line of the assistant message containing the tool_use
+    pub line: u32,
+    pub tool_use_id: Option<String>,
+    /// "Edit", "MultiEdit" or "Write"
+    pub tool_name: String,
+    pub timestamp: Option<String>,
+    /// The text that was replaced (empty for Write)
+    pub old_string: String,
+    pub new_string: String,
+    /// The Edit replaces every occurrence of `old_string`, not just the first
+    pub replace_all: bool,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    /// Line of the human prompt the edit answers
+    pub prompt_line: Option<u32>,
+    /// Text of that prompt, whitespace-normalized and truncated
+    pub prompt: Option<String>,
+}
+
+impl EditRecord {
+    /// Apply the edit to a file's content (None if the file doesn't exist).
+    ///
+    /// A Write replaces the whole file. An Edit replaces the first occurrence
+    /// of its old text, or every one with `replace_all`; returns None if that
+    /// text isn't there, e.g. when the file changed outside the session.
+    pub fn apply(&self, content: Option<&str>) -> Option<String> {
+        if self.tool_name == "Write" || self.old_string.is_empty() {
+            return Some(self.new_string.clone());
+        }
+        let content = content?;
+        if self.replace_all {
+            return content
+                .contains(&self.old_string)
+                .then(|| content.replace(&self.old_string, &self.new_string));
+        }
+        let at = content.find(&self.old_string)?;
+        let mut updated = content.to_string();
+        updated.replace_range(at..at + self.old_string.len(), &self.new_string);
+        Some(updated)
+    }
+}
+
+/// Read every Edit, MultiEdit and Write of a session, in file order.
+pub fn get_edit_records(
+    index: &SessionIndex,
+    session_file: &Path,
+    project_path: &str,
+) -> Result<Vec<EditRecord>, String> {
+    let mut lines: Vec<u32> = index
+        .file_to_edit_lines
+        .values()
+        .flatten()
+        .copied()
+        .collect();
+    lines.sort();
+    lines.dedup();
+
+    let mut file =
+        File::open(session_file).map_err(|e| format!("Failed to open session file: {}", e))?;
+    let mut records = Vec::new();
+    let mut prompt: Option<(u32, Option<String>)> = None;
+
+    for line in lines {
+        let Some(entry) = get_value_at_line(index, &mut file, line)? else {
+            continue;
+        };
+        let Some(Value::Array(items)) = entry.pointer("/message/content") else {
+            continue;
+        };
+
+        // Edits of one turn share a prompt; read it once
+        let prompt_line = index.find_human_boundary(line);
+        if prompt.as_ref().map(|(l, _)| *l) != prompt_line {
+            prompt = match prompt_line {
+                Some(l) => Some((l, read_prompt(index, &mut file, l)?)),
+                None => None,
+            };
+        }
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        for item in items {
+            if item.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
+                continue;
+            }
+            let Some(input) = item.get("input") else {
+                continue;
+            };
+            let field = |key: &str| input.get(key).and_then(|v| v.as_str());
+            let Some(file_path) = field("file_path") else {
+                continue;
+            };
+
+            // A MultiEdit's edits are recorded one by one, in order
+            let (tool_name, edits): (&str, Vec<(&str, &str, bool)>) =
+                match item.get("name").and_then(|v| v.as_str()) {
+                    Some("Edit") => ("Edit", vec![edit_fields(input)]),
+                    Some("MultiEdit") => (
+                        "MultiEdit",
+                        input
+                            .get("edits")
+                            .and_then(|v| v.as_array())
+                            .into_iter()
+                            .flatten()
+                            .map(edit_fields)
+                            .collect(),
+                    ),
+                    Some("Write") => ("Write", vec![("", field("content").unwrap_or(""), false)]),
+                    _ => continue,
+                };
+
+            for (old_string, new_string, replace_all) in edits {
+                let (lines_removed, lines_added) = changed_lines(old_string, new_string);
+                records.push(EditRecord {
+                    path: make_relative_path(file_path, project_path),
+                    line,
+                    tool_use_id: item.get("id").and_then(|v| v.as_str()).map(String::from),
+                    tool_name: tool_name.to_string(),
+                    timestamp: timestamp.clone(),
+                    old_string: old_string.to_string(),
+                    new_string: new_string.to_string(),
+                    replace_all,
+                    lines_added,
+                    lines_removed,
+                    prompt_line,
+                    prompt: prompt.as_ref().and_then(|(_, text)| text.clone()),
+                });
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+/// Old string, new string and `replace_all` of an Edit (or a MultiEdit's edit).
+fn edit_fields(edit: &Value) -> (&str, &str, bool) {
+    let text = |key: &str| edit.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let replace_all = edit.get("replace_all").and_then(|v| v.as_bool()) == Some(true);
+    (text("old_string"), text("new_string"), replace_all)
+}
+
+/// Read the text of the human prompt at a line.
+fn read_prompt(index: &SessionIndex, file: &mut File, line: u32) -> Result<Option<String>, String> {
+    Ok(get_value_at_line(index, file, line)?
+        .and_then(|entry| entry.pointer("/message/content").map(prompt_text)))
+}
+
+/// Text of a prompt: the string content, or its text blocks joined, with
+/// whitespace normalized.
+pub(super) fn prompt_text(content: &Value) -> String {
+    let text = match content {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|v| v.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|v| v.as_str()))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => String::new(),
+    };
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate_string(&normalized, MAX_PROMPT_CHARS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(old: &str, new: &str, replace_all: bool) -> EditRecord {
+        EditRecord {
+            path: "main.rs".to_string(),
+            line: 0,
+            tool_use_id: None,
+            tool_name: "Edit".to_string(),
+            timestamp: None,
+            old_string: old.to_string(),
+            new_string: new.to_string(),
+            replace_all,
+            lines_added: 0,
+            lines_removed: 0,
+            prompt_line: None,
+            prompt: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_edit() {
+        let content = Some("let a = 1;\nlet b = a;\n");
+        assert_eq!(
+            edit("a", "x", false).apply(content).as_deref(),
+            Some("let x = 1;\nlet b = a;\n")
+        );
+        assert_eq!(
+            edit("a", "x", true).apply(content).as_deref(),
+            Some("let x = 1;\nlet b = x;\n")
+        );
+        assert_eq!(edit("c", "x", true).apply(content), None);
+        assert_eq!(edit("a", "x", false).apply(None), None);
+
+        let write = EditRecord {
+            tool_name: "Write".to_string(),
+            ..edit("", "fn main() {}\n", false)
+        };
+        assert_eq!(write.apply(None).as_deref(), Some("fn main() {}\n"));
+        assert_eq!(write.apply(content).as_deref(), Some("fn main() {}\n"));
+    }
+}
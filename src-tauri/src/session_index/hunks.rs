@@ -0,0 +1,409 @@
+//! Line-level diff hunks for file edits.
+//!
+//! Edits only record the replaced and replacement text. Diffing them here
+//! (with libgit2's line diff) gives the `+N −M` counts and hunks without the
+//! frontend diffing every edit. The session's edits to a file are replayed
+//! over its content at git HEAD so hunks can be placed at line numbers of
+//! the reconstructed file; when an edit can't be located (the file changed
+//! outside the session), its hunks stay relative to the edit's own text.
+
+use git2::{DiffOptions, Patch};
+use serde::Serialize;
+use std::path::Path;
+
+use super::edits::{get_edit_records, EditRecord};
+use super::types::SessionIndex;
+
+/// Context lines around each hunk (as in `git diff`).
+const CONTEXT_LINES: u32 = 3;
+
+/// Kind of a line in a hunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// A line in a hunk.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkLine {
+    pub kind: LineKind,
+    /// Line number before the edit (None for added lines)
+    pub old_line: Option<u32>,
+    /// Line number after the edit (None for removed lines)
+    pub new_line: Option<u32>,
+    /// Text without the trailing newline
+    pub text: String,
+}
+
+/// A run of changed lines with surrounding context.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// First line before the edit (1-based)
+    pub old_start: u32,
+    pub old_lines: u32,
+    /// First line after the edit (1-based)
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<HunkLine>,
+}
+
+/// Hunks of one Edit or Write.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditHunks {
+    /// Line of the assistant message containing the tool_use
+    pub line: u32,
+    /// "Edit", "MultiEdit" or "Write"
+    pub tool_name: String,
+    pub timestamp: Option<String>,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    /// Whether line numbers are in the reconstructed file (otherwise they are
+    /// relative to the edit's text)
+    pub located: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Hunks of every edit to a file in a session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHunks {
+    /// Path relative to the project
+    pub path: String,
+    /// Edits in session order
+    pub edits: Vec<EditHunks>,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+}
+
+/// Lines (removed, added) when replacing `old` with `new`.
+pub fn changed_lines(old: &str, new: &str) -> (u64, u64) {
+    let (old, new) = (with_final_newline(old), with_final_newline(new));
+    let stats = Patch::from_buffers(old.as_bytes(), None, new.as_bytes(), None, None)
+        .and_then(|patch| patch.line_stats());
+    match stats {
+        Ok((_, added, removed)) => (removed as u64, added as u64),
+        // Fall back to treating every line as changed
+        Err(_) => (old.lines().count() as u64, new.lines().count() as u64),
+    }
+}
+
+/// Diff the session's edits to a file.
+///
+/// A Write replaces the whole file, so it is diffed against the content
+/// before it when that is known (and counts as all added otherwise, as in
+/// the session statistics).
+pub fn get_file_hunks(
+    index: &SessionIndex,
+    session_file: &Path,
+    project_path: &str,
+    file_path: &str,
+) -> Result<FileHunks, String> {
+    let path = Path::new(file_path)
+        .strip_prefix(project_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file_path.to_string());
+    let records: Vec<EditRecord> = get_edit_records(index, session_file, project_path)?
+        .into_iter()
+        .filter(|record| record.path == path)
+        .collect();
+
+    // Content of the file as the session goes, if it can be followed
+    let mut content = crate::git::get_git_file_diff(project_path, &path)
+        .ok()
+        .filter(|diff| diff.exists_at_head)
+        .map(|diff| diff.original);
+
+    let mut edits = Vec::with_capacity(records.len());
+    for record in records {
+        let (old_text, start_line) = if record.tool_name == "Write" {
+            let previous = content.replace(record.new_string.clone());
+            (previous.unwrap_or_default(), Some(1))
+        } else {
+            // Hunks are placed at the first occurrence of the old text
+            let start_line = content.as_deref().and_then(|current| {
+                let at = current.find(&record.old_string)?;
+                Some(current[..at].matches('\n').count() as u32 + 1)
+            });
+            // Once an edit can't be located, the content is unknown
+            content = match start_line {
+                Some(_) => record.apply(content.as_deref()),
+                None => None,
+            };
+            (record.old_string.clone(), start_line)
+        };
+
+        let mut hunks = diff_hunks(&old_text, &record.new_string)?;
+        if let Some(start) = start_line {
+            for hunk in &mut hunks {
+                shift_hunk(hunk, start - 1);
+            }
+        }
+        let count = |kind: LineKind| {
+            hunks
+                .iter()
+                .flat_map(|h| &h.lines)
+                .filter(|l| l.kind == kind)
+                .count() as u64
+        };
+        let (lines_added, lines_removed) = (count(LineKind::Added), count(LineKind::Removed));
+
+        edits.push(EditHunks {
+            line: record.line,
+            tool_name: record.tool_name,
+            timestamp: record.timestamp,
+            lines_added,
+            lines_removed,
+            located: start_line.is_some(),
+            hunks,
+        });
+    }
+
+    Ok(FileHunks {
+        path,
+        lines_added: edits.iter().map(|e| e.lines_added).sum(),
+        lines_removed: edits.iter().map(|e| e.lines_removed).sum(),
+        edits,
+    })
+}
+
+/// Diff two texts into hunks with line numbers relative to the texts.
+fn diff_hunks(old: &str, new: &str) -> Result<Vec<DiffHunk>, String> {
+    let (old, new) = (with_final_newline(old), with_final_newline(new));
+    let mut options = DiffOptions::new();
+    options.context_lines(CONTEXT_LINES);
+    let patch = Patch::from_buffers(
+        old.as_bytes(),
+        None,
+        new.as_bytes(),
+        None,
+        Some(&mut options),
+    )
+    .map_err(|e| format!("Failed to diff edit: {}", e))?;
+
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch
+            .hunk(hunk_index)
+            .map_err(|e| format!("Failed to read hunk: {}", e))?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_index in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_index, line_index)
+                .map_err(|e| format!("Failed to read hunk line: {}", e))?;
+            let kind = match line.origin() {
+                ' ' => LineKind::Context,
+                '+' => LineKind::Added,
+                '-' => LineKind::Removed,
+                // End-of-file newline markers
+                _ => continue,
+            };
+            lines.push(HunkLine {
+                kind,
+                old_line: line.old_lineno(),
+                new_line: line.new_lineno(),
+                text: String::from_utf8_lossy(line.content())
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+            });
+        }
+        hunks.push(DiffHunk {
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Move a hunk down by `offset` lines.
+fn shift_hunk(hunk: &mut DiffHunk, offset: u32) {
+    hunk.old_start += offset;
+    hunk.new_start += offset;
+    for line in &mut hunk.lines {
+        line.old_line = line.old_line.map(|n| n + offset);
+        line.new_line = line.new_line.map(|n| n + offset);
+    }
+}
+
+/// Edit strings usually lack a final newline; add one so a line isn't
+/// reported as changed just because it became the last.
+fn with_final_newline(text: &str) -> String {
+    if text.is_empty() || text.ends_with('\n') {
+        text.to_string()
+    } else {
+        format!("{}\n", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::write_session;
+    use serde_json::json;
+
+    #[test]
+    fn test_changed_lines() {
+        assert_eq!(changed_lines("a\nb\nc", "a\nB\nc"), (1, 1));
+        assert_eq!(changed_lines("a\nc", "a\nb\nc"), (0, 1));
+        assert_eq!(changed_lines("", "x\ny"), (0, 2));
+        assert_eq!(changed_lines("same", "same"), (0, 0));
+        // Lines kept between two changes aren't counted; trimming only the
+        // shared first and last lines would count (2, 3)
+        assert_eq!(changed_lines("a\nb\nc\nd", "a\nX\nc\nY\nd"), (1, 2));
+    }
+
+    #[test]
+    fn test_file_hunks_follow_reconstructed_file() {
+        let tool_use = |id: &str, name: &str, input: serde_json::Value| {
+            json!({"type": "assistant", "uuid": id, "message": {"content": [
+                {"type": "tool_use", "id": id, "name": name, "input": input}]}})
+        };
+        let lines = [
+            tool_use(
+                "w1",
+                "Write",
+                json!({"file_path": "/nonexistent-project/notes.txt",
+                       "content": "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\n"}),
+            ),
+            tool_use(
+                "e1",
+                "Edit",
+                json!({"file_path": "/nonexistent-project/notes.txt",
+                       "old_string": "seven\neight", "new_string": "SEVEN\neight\nnine"}),
+            ),
+            tool_use(
+                "e2",
+                "Edit",
+                json!({"file_path": "/nonexistent-project/notes.txt",
+                       "old_string": "missing", "new_string": "x"}),
+            ),
+        ];
+        let session = write_session("hunks", &lines);
+        let path = session.path();
+        let index = session.index("/nonexistent-project");
+        let hunks = get_file_hunks(&index, path, "/nonexistent-project", "notes.txt").unwrap();
+
+        assert_eq!(hunks.edits.len(), 3);
+        let write = &hunks.edits[0];
+        assert!(write.located);
+        assert_eq!((write.lines_removed, write.lines_added), (0, 8));
+
+        // "seven" is line 7 of the written file
+        let edit = &hunks.edits[1];
+        assert!(edit.located);
+        assert_eq!((edit.lines_removed, edit.lines_added), (1, 2));
+        let hunk = &edit.hunks[0];
+        assert_eq!((hunk.old_start, hunk.new_start), (7, 7));
+        assert_eq!(
+            hunk.lines[0],
+            HunkLine {
+                kind: LineKind::Removed,
+                old_line: Some(7),
+                new_line: None,
+                text: "seven".to_string(),
+            }
+        );
+
+        // The old string isn't in the file, so line numbers stay relative
+        let unlocated = &hunks.edits[2];
+        assert!(!unlocated.located);
+        assert_eq!(unlocated.hunks[0].new_start, 1);
+        assert_eq!((hunks.lines_removed, hunks.lines_added), (2, 11));
+    }
+
+    #[test]
+    fn test_overlapping_and_crlf_hunks() {
+        let tool_use = |id: &str, name: &str, input: serde_json::Value| {
+            json!({"type": "assistant", "uuid": id, "message": {"content": [
+                {"type": "tool_use", "id": id, "name": name, "input": input}]}})
+        };
+        let edit = |id: &str, file: &str, old: &str, new: &str| {
+            tool_use(
+                id,
+                "Edit",
+                json!({"file_path": format!("/nonexistent-project/{}", file),
+                       "old_string": old, "new_string": new}),
+            )
+        };
+        let lines = [
+            tool_use(
+                "w1",
+                "Write",
+                json!({"file_path": "/nonexistent-project/notes.txt",
+                       "content": "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n"}),
+            ),
+            // Changes further apart than twice the context are separate hunks
+            edit(
+                "e1",
+                "notes.txt",
+                "a\nb\nc\nd\ne\nf\ng\nh\ni",
+                "A\nb\nc\nd\ne\nf\ng\nh\nI",
+            ),
+            // Closer ones share their context and merge into one
+            edit("e2", "notes.txt", "A\nb\nc\nd\ne", "A\nB\nc\nD\ne"),
+            // Overlaps the text the previous edit wrote
+            edit("e3", "notes.txt", "D\ne\nf", "D\nE\nf"),
+            tool_use(
+                "w2",
+                "Write",
+                json!({"file_path": "/nonexistent-project/crlf.txt",
+                       "content": "one\r\ntwo\r\nthree\r\n"}),
+            ),
+            edit("e4", "crlf.txt", "two\r\nthree", "2\r\nthree"),
+        ];
+        let session = write_session("hunks-overlap", &lines);
+        let path = session.path();
+        let index = session.index("/nonexistent-project");
+        let notes = get_file_hunks(&index, path, "/nonexistent-project", "notes.txt").unwrap();
+        let crlf = get_file_hunks(&index, path, "/nonexistent-project", "crlf.txt").unwrap();
+
+        let starts = |edit: &EditHunks| -> Vec<(u32, u32)> {
+            edit.hunks
+                .iter()
+                .map(|h| (h.old_start, h.old_lines))
+                .collect()
+        };
+        assert_eq!(starts(&notes.edits[1]), [(1, 4), (6, 4)]);
+        assert_eq!(starts(&notes.edits[2]), [(1, 5)]);
+        assert_eq!(
+            (notes.edits[2].lines_removed, notes.edits[2].lines_added),
+            (2, 2)
+        );
+
+        let overlapping = &notes.edits[3];
+        assert!(overlapping.located);
+        let removed: Vec<(Option<u32>, &str)> = overlapping.hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.kind == LineKind::Removed)
+            .map(|l| (l.old_line, l.text.as_str()))
+            .collect();
+        assert_eq!(removed, [(Some(5), "e")]);
+
+        // Carriage returns don't end up in the line text
+        let edit = &crlf.edits[1];
+        assert!(edit.located);
+        assert_eq!((edit.lines_removed, edit.lines_added), (1, 1));
+        let lines: Vec<(LineKind, Option<u32>, Option<u32>, &str)> = edit.hunks[0]
+            .lines
+            .iter()
+            .map(|l| (l.kind, l.old_line, l.new_line, l.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (LineKind::Removed, Some(2), None, "two"),
+                (LineKind::Added, None, Some(2), "2"),
+                (LineKind::Context, Some(3), Some(3), "three"),
+            ]
+        );
+    }
+}
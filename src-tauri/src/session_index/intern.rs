@@ -0,0 +1,64 @@
+//! UUID interning for the session index.
+//!
+//! Every event UUID appears at least twice in a session (its own `uuid` and
+//! its child's `parentUuid`). Interning stores each string once and lets the
+//! rest of the index refer to UUIDs by a dense `u32` id.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Dense id assigned to an interned UUID.
+pub type UuidId = u32;
+
+/// Bidirectional UUID string ↔ id table.
+///
+/// Ids are assigned in first-seen order, so they can index plain `Vec`s.
+#[derive(Debug, Clone, Default)]
+pub struct UuidInterner {
+    /// UUID string → id (shares its allocation with `strings`)
+    ids: HashMap<Arc<str>, UuidId>,
+    /// id → UUID string
+    strings: Vec<Arc<str>>,
+}
+
+impl UuidInterner {
+    /// Get the id for a UUID, assigning a new one if it hasn't been seen.
+    pub fn intern(&mut self, uuid: &str) -> UuidId {
+        if let Some(&id) = self.ids.get(uuid) {
+            return id;
+        }
+        let id = self.strings.len() as UuidId;
+        let shared: Arc<str> = Arc::from(uuid);
+        self.strings.push(Arc::clone(&shared));
+        self.ids.insert(shared, id);
+        id
+    }
+
+    /// Look up the id for a UUID without interning it.
+    pub fn get(&self, uuid: &str) -> Option<UuidId> {
+        self.ids.get(uuid).copied()
+    }
+
+    /// Get the UUID string for an id.
+    pub fn resolve(&self, id: UuidId) -> Option<&str> {
+        self.strings.get(id as usize).map(|s| &**s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_is_idempotent() {
+        let mut interner = UuidInterner::default();
+        let a = interner.intern("aaaa");
+        let b = interner.intern("bbbb");
+        assert_ne!(a, b);
+        assert_eq!(interner.intern("aaaa"), a);
+        assert_eq!(interner.resolve(a), Some("aaaa"));
+        assert_eq!(interner.resolve(b), Some("bbbb"));
+        assert_eq!(interner.get("cccc"), None);
+        assert_eq!(interner.resolve(99), None);
+    }
+}
@@ -10,8 +10,18 @@
 //! - O(1) UUID lookups
 //! - O(1) file edit retrieval
 //! - O(k) parent chain walking (for edit context)
+//! - Conversation branches (rewinds, edited prompts, resumes)
+//! - Tool calls paired with their results (timeline and per-tool aggregates)
+//! - Bash command history with dangerous-command flags
+//! - Session statistics (active time, turns, compactions, lines changed)
+//! - The context window at any event, across compactions
+//! - File edits with their triggering prompts and line-level diff hunks
+//! - Side-by-side comparison of two sessions
 //! - Pre-computed line offsets for fast pagination
 //!
+//! Indices live in an [`IndexCache`] and are shared as `Arc` snapshots, so
+//! commands never copy an index to answer a query.
+//!
 //! ## Usage
 //!
 //! ```ignore
@@ -30,13 +40,36 @@
 //! let context = get_edit_context(&index, &session_file, edit_line)?;
 //! ```
 
+mod bash_history;
+mod bash_rules;
+mod branches;
 mod builder;
+mod cache;
+mod compare;
+mod context;
+mod edits;
+mod hunks;
+mod intern;
 mod queries;
+mod stats;
+#[cfg(test)]
+pub(crate) mod test_util;
+mod tool_calls;
 mod types;
 mod updater;
 
 // Re-export public API
+pub use bash_history::{get_bash_history, BashHistory};
+pub use bash_rules::{load_rules as load_bash_rules, parse_stages};
+pub use branches::{get_branch_lines, get_conversation_graph, ConversationGraph};
 pub use builder::build_session_index;
-pub use queries::{get_edit_context, EditContext};
+pub use cache::IndexCache;
+pub use compare::{compare_sessions, SessionComparison, SessionSide};
+pub use context::{get_context_window, ContextWindow};
+pub use edits::{get_edit_records, EditRecord};
+pub use hunks::{changed_lines, get_file_hunks, FileHunks};
+pub use queries::{get_edit_context, get_event_at_line, EditContext};
+pub use stats::{get_session_stats, ActiveTime, AssistantTurns, SessionStats, TokenUsage};
+pub use tool_calls::{query_tool_calls, ToolCallPage, ToolCallQuery};
 pub use types::{IndexStatus, SessionIndex};
 pub use updater::{update_index_incremental, UpdateResult};
@@ -39,19 +39,19 @@ pub fn get_edit_context(
 
     // Walk backwards via parent chain to find the triggering human message
     let mut lines_in_context: Vec<u32> = vec![edit_line];
-    let mut current_uuid = edit_meta.uuid.clone();
+    let mut current_uuid = edit_meta.uuid;
 
     // Walk parent chain
-    while let Some(ref uuid) = current_uuid {
-        if let Some(parent_uuid) = index.parent_of(uuid) {
-            if let Some(parent_line) = index.line_for_uuid(parent_uuid) {
+    while let Some(uuid) = current_uuid {
+        if let Some(parent_uuid) = index.parent_id(uuid) {
+            if let Some(parent_line) = index.line_for_id(parent_uuid) {
                 // Check if this parent is a human message boundary
                 if index.is_human_message(parent_line) {
                     lines_in_context.push(parent_line);
                     break;
                 }
                 lines_in_context.push(parent_line);
-                current_uuid = Some(parent_uuid.clone());
+                current_uuid = Some(parent_uuid);
             } else {
                 break;
             }
@@ -83,6 +83,18 @@ pub fn get_edit_context(
     })
 }
 
+/// Load the SessionEvent at a single line number.
+pub fn get_event_at_line(
+    index: &SessionIndex,
+    file: &mut File,
+    line: u32,
+) -> Result<Option<SessionEvent>, String> {
+    match index.line_offset(line) {
+        Some((offset, _length)) => read_event_at_offset(file, offset, line),
+        None => Ok(None),
+    }
+}
+
 /// Load SessionEvent objects for specific line numbers.
 fn load_events_for_lines(
     index: &SessionIndex,
@@ -95,22 +107,41 @@ fn load_events_for_lines(
     let mut events = Vec::with_capacity(lines.len());
 
     for &line in lines {
-        if let Some((offset, _length)) = index.line_offsets.get(line as usize) {
-            if let Some(event) = read_event_at_offset(&mut file, *offset, line)? {
-                events.push(event);
-            }
+        if let Some(event) = get_event_at_line(index, &mut file, line)? {
+            events.push(event);
         }
     }
 
     Ok(events)
 }
 
+/// Load the raw JSON entry at a single line number.
+pub(super) fn get_value_at_line(
+    index: &SessionIndex,
+    file: &mut File,
+    line: u32,
+) -> Result<Option<serde_json::Value>, String> {
+    match index.line_offset(line) {
+        Some((offset, _length)) => {
+            let text = read_line_at_offset(file, offset)?;
+            Ok(serde_json::from_str(&text).ok())
+        }
+        None => Ok(None),
+    }
+}
+
 /// Read a single event at a byte offset.
 fn read_event_at_offset(
     file: &mut File,
     offset: u64,
     sequence: u32,
 ) -> Result<Option<SessionEvent>, String> {
+    let line = read_line_at_offset(file, offset)?;
+    Ok(parse_session_event(&line, sequence, offset))
+}
+
+/// Read the line starting at a byte offset, without its line ending.
+fn read_line_at_offset(file: &mut File, offset: u64) -> Result<String, String> {
     file.seek(SeekFrom::Start(offset))
         .map_err(|e| format!("Failed to seek: {}", e))?;
 
@@ -129,5 +160,5 @@ fn read_event_at_offset(
         line.pop();
     }
 
-    Ok(parse_session_event(&line, sequence, offset))
+    Ok(line)
 }
@@ -0,0 +1,368 @@
+//! Session statistics.
+//!
+//! Counters accumulated line by line while the index is built or updated,
+//! combined with the tool call table into a summary for comparing sessions.
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::hunks::changed_lines;
+use super::tool_calls::{to_entry, tool_stats, ToolStats};
+use super::types::SessionIndex;
+
+/// Gaps between events longer than this count as idle, not active time.
+pub const IDLE_THRESHOLD_MS: i64 = 5 * 60 * 1000;
+
+/// Counters accumulated while indexing.
+#[derive(Debug, Clone, Default)]
+pub struct StatsCounters {
+    first_timestamp: Option<(DateTime<FixedOffset>, String)>,
+    last_timestamp: Option<(DateTime<FixedOffset>, String)>,
+    active: ActiveTime,
+    active_ms: u64,
+    turns: AssistantTurns,
+    assistant_turns: u32,
+    api_errors: u32,
+    compactions: Vec<Compaction>,
+    tokens: TokenUsage,
+    lines_added: u64,
+    lines_removed: u64,
+}
+
+/// Token usage summed over assistant messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Add a message's `usage` object.
+    pub fn add(&mut self, usage: &Value) {
+        let field = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        self.input_tokens += field("input_tokens");
+        self.output_tokens += field("output_tokens");
+        self.cache_read_tokens += field("cache_read_input_tokens");
+        self.cache_creation_tokens += field("cache_creation_input_tokens");
+    }
+}
+
+/// Active time between events: gaps up to `IDLE_THRESHOLD_MS` count, longer
+/// ones are idle.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActiveTime {
+    last: Option<DateTime<FixedOffset>>,
+}
+
+impl ActiveTime {
+    /// Record an event and return the active time since the latest one.
+    pub fn record(&mut self, time: DateTime<FixedOffset>) -> u64 {
+        let gap = self.last.map_or(0, |last| (time - last).num_milliseconds());
+        // Sidechain lines can be out of order; only move forward
+        if self.last.is_none_or(|last| time > last) {
+            self.last = Some(time);
+        }
+        if gap > 0 && gap <= IDLE_THRESHOLD_MS {
+            gap as u64
+        } else {
+            0
+        }
+    }
+}
+
+/// Assistant turns, told apart by message id.
+///
+/// Every line of a multi-block response repeats its message id and usage,
+/// so usage is only counted on the line that starts a turn.
+#[derive(Debug, Clone, Default)]
+pub struct AssistantTurns {
+    /// message.id of the latest assistant line
+    last_message: Option<String>,
+}
+
+impl AssistantTurns {
+    /// Record an assistant line; returns whether it starts a new turn.
+    pub fn record(&mut self, message_id: Option<&str>) -> bool {
+        let new_turn = message_id.is_none() || message_id != self.last_message.as_deref();
+        if new_turn {
+            self.last_message = message_id.map(String::from);
+        }
+        new_turn
+    }
+}
+
+/// A context compaction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Compaction {
+    /// Line of the compact_boundary entry
+    pub line: u32,
+    pub timestamp: Option<String>,
+    /// "manual" (/compact) or "auto"
+    pub trigger: Option<String>,
+    /// Context size in tokens before compacting
+    pub pre_tokens: Option<u64>,
+}
+
+/// Summary of a session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStats {
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    /// Wall-clock time from the first to the last event
+    pub duration_ms: u64,
+    /// Time between events, excluding gaps longer than `idle_threshold_ms`
+    pub active_ms: u64,
+    pub idle_threshold_ms: u64,
+    pub human_prompts: u32,
+    pub assistant_turns: u32,
+    pub tool_calls: u32,
+    /// Aggregates per tool, most used first
+    pub tools: Vec<ToolStats>,
+    /// Sub-agents launched (Task calls)
+    pub sub_agents: u32,
+    pub compactions: Vec<Compaction>,
+    /// Tool calls whose result was an error
+    pub tool_errors: u32,
+    /// API errors (failed requests, overloads, rate limits)
+    pub api_errors: u32,
+    /// Token usage summed over assistant turns
+    pub tokens: TokenUsage,
+    /// Files edited or written
+    pub files_touched: u32,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+}
+
+/// The fields of a session entry the counters look at.
+pub(super) struct StatsEntry<'a> {
+    pub entry_type: Option<&'a str>,
+    pub subtype: Option<&'a str>,
+    pub timestamp: Option<&'a str>,
+    pub message_id: Option<&'a str>,
+    pub content: Option<&'a Value>,
+    pub usage: Option<&'a Value>,
+    pub compact_metadata: Option<&'a Value>,
+    pub is_api_error: bool,
+}
+
+impl StatsCounters {
+    /// Compactions recorded so far, in file order.
+    pub(super) fn compactions(&self) -> &[Compaction] {
+        &self.compactions
+    }
+
+    /// Record one parsed line.
+    pub(super) fn record(&mut self, line: u32, entry: &StatsEntry) {
+        if let Some(timestamp) = entry.timestamp {
+            self.record_timestamp(timestamp);
+        }
+        if entry.is_api_error {
+            self.api_errors += 1;
+        }
+
+        match entry.entry_type {
+            Some("assistant") => {
+                if self.turns.record(entry.message_id) {
+                    self.assistant_turns += 1;
+                    if let Some(usage) = entry.usage {
+                        self.tokens.add(usage);
+                    }
+                }
+                if let Some(Value::Array(items)) = entry.content {
+                    for item in items {
+                        self.record_line_changes(item);
+                    }
+                }
+            }
+            Some("system") if entry.subtype == Some("compact_boundary") => {
+                let metadata = entry.compact_metadata;
+                self.compactions.push(Compaction {
+                    line,
+                    timestamp: entry.timestamp.map(String::from),
+                    trigger: metadata
+                        .and_then(|m| m.get("trigger"))
+                        .and_then(|v| v.as_str())
+                        .map(String::from),
+                    pre_tokens: metadata
+                        .and_then(|m| m.get("preTokens"))
+                        .and_then(|v| v.as_u64()),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn record_timestamp(&mut self, timestamp: &str) {
+        let Ok(time) = DateTime::parse_from_rfc3339(timestamp) else {
+            return;
+        };
+
+        self.active_ms += self.active.record(time);
+        if self
+            .first_timestamp
+            .as_ref()
+            .is_none_or(|(first, _)| time < *first)
+        {
+            self.first_timestamp = Some((time, timestamp.to_string()));
+        }
+        if self
+            .last_timestamp
+            .as_ref()
+            .is_none_or(|(last, _)| time > *last)
+        {
+            self.last_timestamp = Some((time, timestamp.to_string()));
+        }
+    }
+
+    /// Count lines added and removed by an Edit, MultiEdit or Write tool_use.
+    fn record_line_changes(&mut self, item: &Value) {
+        if item.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
+            return;
+        }
+        let Some(input) = item.get("input") else {
+            return;
+        };
+        let text = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+
+        match item.get("name").and_then(|v| v.as_str()) {
+            Some("Edit") => self.add_change(&text(input, "old_string"), &text(input, "new_string")),
+            Some("MultiEdit") => {
+                let edits = input.get("edits").and_then(|v| v.as_array());
+                for edit in edits.into_iter().flatten() {
+                    self.add_change(&text(edit, "old_string"), &text(edit, "new_string"));
+                }
+            }
+            // The previous content isn't known, so a Write counts as all added
+            Some("Write") => self.add_change("", &text(input, "content")),
+            _ => {}
+        }
+    }
+
+    fn add_change(&mut self, old: &str, new: &str) {
+        let (removed, added) = changed_lines(old, new);
+        self.lines_removed += removed;
+        self.lines_added += added;
+    }
+}
+
+/// Summarize a session from its index.
+pub fn get_session_stats(index: &SessionIndex) -> SessionStats {
+    let counters = &index.stats;
+
+    let entries: Vec<_> = index
+        .tool_calls()
+        .filter(|(_, call)| call.tool_use_line.is_some())
+        .map(|(id, call)| to_entry(id, call))
+        .collect();
+    let sub_agents = entries
+        .iter()
+        .filter(|e| e.tool_name.as_deref() == Some("Task") || e.agent_id.is_some())
+        .count() as u32;
+    let tool_errors = entries.iter().filter(|e| e.is_error).count() as u32;
+
+    let duration_ms = match (&counters.first_timestamp, &counters.last_timestamp) {
+        (Some((first, _)), Some((last, _))) => (*last - *first).num_milliseconds().max(0) as u64,
+        _ => 0,
+    };
+
+    SessionStats {
+        started_at: counters.first_timestamp.as_ref().map(|(_, s)| s.clone()),
+        ended_at: counters.last_timestamp.as_ref().map(|(_, s)| s.clone()),
+        duration_ms,
+        active_ms: counters.active_ms,
+        idle_threshold_ms: IDLE_THRESHOLD_MS as u64,
+        human_prompts: index.human_message_lines.len() as u32,
+        assistant_turns: counters.assistant_turns,
+        tool_calls: entries.len() as u32,
+        tools: tool_stats(&entries),
+        sub_agents,
+        compactions: counters.compactions.clone(),
+        tool_errors,
+        api_errors: counters.api_errors,
+        tokens: counters.tokens,
+        files_touched: index.file_to_edit_lines.len() as u32,
+        lines_added: counters.lines_added,
+        lines_removed: counters.lines_removed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::{jsonl, write_session};
+    use crate::session_index::update_index_incremental;
+    use serde_json::json;
+
+    #[test]
+    fn test_session_stats() {
+        let lines = [
+            json!({"type": "user", "userType": "external", "timestamp": "2025-01-01T10:00:00Z",
+                   "message": {"content": "fix the bug"}}),
+            json!({"type": "assistant", "timestamp": "2025-01-01T10:00:10Z",
+                   "message": {"id": "m1", "usage": {"input_tokens": 5, "output_tokens": 50}, "content": [{"type": "text", "text": "On it"}]}}),
+            json!({"type": "assistant", "timestamp": "2025-01-01T10:00:20Z",
+                   "message": {"id": "m1", "usage": {"input_tokens": 5, "output_tokens": 50}, "content": [{"type": "tool_use", "id": "t1", "name": "Edit",
+                       "input": {"file_path": "/proj/src/a.rs", "old_string": "a\nb\nc", "new_string": "a\nB\nB2\nc"}}]}}),
+            json!({"type": "user", "timestamp": "2025-01-01T10:00:30Z",
+                   "message": {"content": [{"type": "tool_result", "tool_use_id": "t1", "content": "ok"}]}}),
+            // An hour away from the keyboard
+            json!({"type": "user", "userType": "external", "timestamp": "2025-01-01T11:00:30Z",
+                   "message": {"content": "now add tests"}}),
+        ];
+        let session = write_session("stats", &lines);
+        let mut index = session.index("/proj");
+
+        let appended = [
+            json!({"type": "system", "subtype": "compact_boundary", "timestamp": "2025-01-01T11:01:00Z",
+                   "compactMetadata": {"trigger": "auto", "preTokens": 155000}}),
+            json!({"type": "assistant", "timestamp": "2025-01-01T11:01:30Z", "isApiErrorMessage": true,
+                   "message": {"id": "m2", "content": [{"type": "text", "text": "API Error: overloaded"}]}}),
+            json!({"type": "assistant", "timestamp": "2025-01-01T11:02:00Z",
+                   "message": {"id": "m3", "content": [
+                       {"type": "tool_use", "id": "t2", "name": "Task", "input": {"description": "explore"}},
+                       {"type": "tool_use", "id": "t3", "name": "Bash", "input": {"command": "false"}}]}}),
+            json!({"type": "user", "timestamp": "2025-01-01T11:02:05Z",
+                   "message": {"content": [{"type": "tool_result", "tool_use_id": "t3", "content": "Exit code 1", "is_error": true}]}}),
+        ];
+        session.append(&jsonl(&appended));
+        update_index_incremental(&mut index, session.path(), "/proj").unwrap();
+        let rebuilt = session.index("/proj");
+
+        let stats = get_session_stats(&index);
+        assert_eq!(stats.started_at.as_deref(), Some("2025-01-01T10:00:00Z"));
+        assert_eq!(stats.duration_ms, (62 * 60 + 5) * 1000);
+        // Everything but the hour-long gap
+        assert_eq!(stats.active_ms, (2 * 60 + 5) * 1000);
+        assert_eq!(stats.human_prompts, 2);
+        assert_eq!(stats.assistant_turns, 3);
+        assert_eq!(stats.tool_calls, 3);
+        assert_eq!(stats.sub_agents, 1);
+        assert_eq!(stats.tool_errors, 1);
+        assert_eq!(stats.api_errors, 1);
+        assert_eq!(stats.compactions.len(), 1);
+        assert_eq!(stats.compactions[0].pre_tokens, Some(155000));
+        assert_eq!(stats.compactions[0].trigger.as_deref(), Some("auto"));
+        assert_eq!(stats.files_touched, 1);
+        assert_eq!((stats.lines_added, stats.lines_removed), (2, 1));
+        // Usage repeated on both lines of m1 counts once
+        assert_eq!(stats.tokens.output_tokens, 50);
+
+        // Incremental updates accumulate the same counters as a full build
+        let full = get_session_stats(&rebuilt);
+        assert_eq!(full.active_ms, stats.active_ms);
+        assert_eq!(full.assistant_turns, stats.assistant_turns);
+        assert_eq!(full.compactions, stats.compactions);
+    }
+}
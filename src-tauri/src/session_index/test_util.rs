@@ -0,0 +1,63 @@
+//! Session file fixtures for tests.
+
+use serde_json::Value;
+use std::fmt::Display;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::builder::build_session_index;
+use super::types::SessionIndex;
+
+/// A session file in the temp directory, removed when dropped so a failed
+/// assertion doesn't leak it.
+pub struct TempSession {
+    path: PathBuf,
+}
+
+impl TempSession {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Build the session's index as part of `project_path`.
+    pub fn index(&self, project_path: &str) -> SessionIndex {
+        build_session_index(&self.path, project_path).unwrap()
+    }
+
+    /// Append raw bytes (e.g. the rest of a partially written line).
+    pub fn append(&self, bytes: impl AsRef<[u8]>) {
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .unwrap();
+        file.write_all(bytes.as_ref()).unwrap();
+    }
+}
+
+impl Drop for TempSession {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Lines joined into JSONL, each terminated by a newline.
+pub fn jsonl<T: Display>(lines: &[T]) -> String {
+    lines.iter().map(|l| format!("{}\n", l)).collect()
+}
+
+/// Write a session file of `lines`, named after `name` and the process.
+pub fn write_session(name: &str, lines: &[Value]) -> TempSession {
+    write_session_raw(name, jsonl(lines))
+}
+
+/// Write a session file with raw content (malformed or partial lines).
+pub fn write_session_raw(name: &str, content: impl AsRef<[u8]>) -> TempSession {
+    let path = std::env::temp_dir().join(format!(
+        "agent-console-{}-{}.jsonl",
+        name,
+        std::process::id()
+    ));
+    fs::write(&path, content).unwrap();
+    TempSession { path }
+}
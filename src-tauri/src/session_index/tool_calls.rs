@@ -0,0 +1,412 @@
+//! Tool call timeline.
+//!
+//! Pages through the tool calls recorded in a session index (each tool_use
+//! paired with its tool_result by id), with per-tool aggregates for spotting
+//! slow or frequently failing tools.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::claude_code::truncate_string;
+
+use super::types::{SessionIndex, ToolCall};
+
+/// Longest input summary kept in the index.
+const MAX_SUMMARY_CHARS: usize = 120;
+
+/// Filter, sort and paging options for `query_tool_calls`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallQuery {
+    /// Page start (number of calls to skip)
+    pub offset: Option<u32>,
+    /// Page size (defaults to 200)
+    pub limit: Option<u32>,
+    /// Only include this tool (e.g., "Bash")
+    pub tool_name: Option<String>,
+    /// Only include calls whose result was an error
+    pub errors_only: Option<bool>,
+    /// Sort key (defaults to session order)
+    pub sort_by: Option<ToolCallSortKey>,
+    /// Sort descending instead of ascending
+    pub descending: Option<bool>,
+}
+
+/// Sort keys for tool call queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolCallSortKey {
+    #[default]
+    Sequence,
+    DurationMs,
+    OutputBytes,
+    ToolName,
+}
+
+/// A tool call in the timeline.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallEntry {
+    pub tool_use_id: String,
+    pub tool_name: Option<String>,
+    pub input_summary: String,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    /// Time from tool_use to tool_result (None while pending)
+    pub duration_ms: Option<u64>,
+    pub is_error: bool,
+    pub output_bytes: u64,
+    /// Sub-agent launched by the call (Task tool)
+    pub agent_id: Option<String>,
+    pub tool_use_line: Option<u32>,
+    pub tool_result_line: Option<u32>,
+}
+
+/// Aggregates for one tool.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolStats {
+    pub tool_name: String,
+    pub calls: u32,
+    pub errors: u32,
+    /// Calls without a result yet
+    pub pending: u32,
+    pub total_duration_ms: u64,
+    pub avg_duration_ms: Option<u64>,
+    pub max_duration_ms: Option<u64>,
+    pub total_output_bytes: u64,
+}
+
+/// A page of tool calls.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallPage {
+    /// Calls for the requested page
+    pub calls: Vec<ToolCallEntry>,
+    /// Number of calls matching the filters
+    pub total_count: u32,
+    /// Offset of this page
+    pub offset: u32,
+    /// Whether there are more calls after this page
+    pub has_more: bool,
+    /// Aggregates per tool over the whole session (ignoring filters),
+    /// most used first
+    pub tools: Vec<ToolStats>,
+}
+
+/// Summarize a tool's input for display (e.g., the command of a Bash call).
+pub(super) fn summarize_input(tool_name: &str, input: &Value) -> String {
+    let key = match tool_name {
+        "Bash" => "command",
+        "Read" | "Edit" | "MultiEdit" | "Write" | "NotebookEdit" => "file_path",
+        "Grep" | "Glob" => "pattern",
+        "WebFetch" => "url",
+        "WebSearch" => "query",
+        "Task" => "description",
+        _ => "",
+    };
+    let summary = input
+        .get(key)
+        .and_then(|v| v.as_str())
+        .or_else(|| input.as_object()?.values().find_map(|v| v.as_str()))
+        .map(String::from)
+        .unwrap_or_else(|| match input {
+            Value::Null => String::new(),
+            other => other.to_string(),
+        });
+    truncate_string(summary.trim(), MAX_SUMMARY_CHARS)
+}
+
+/// Size in bytes of a tool_result's content (a string or a list of blocks).
+pub(super) fn output_size(content: &Value) -> u64 {
+    match content {
+        Value::String(s) => s.len() as u64,
+        Value::Array(blocks) => blocks
+            .iter()
+            .map(|block| match block.get("text").and_then(|t| t.as_str()) {
+                Some(text) => text.len() as u64,
+                None => block
+                    .pointer("/source/data")
+                    .and_then(|d| d.as_str())
+                    .map_or(0, |d| d.len() as u64),
+            })
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Milliseconds between two RFC 3339 timestamps.
+pub(super) fn duration_ms(started_at: Option<&str>, ended_at: Option<&str>) -> Option<u64> {
+    let start = chrono::DateTime::parse_from_rfc3339(started_at?).ok()?;
+    let end = chrono::DateTime::parse_from_rfc3339(ended_at?).ok()?;
+    u64::try_from((end - start).num_milliseconds()).ok()
+}
+
+pub(super) fn to_entry(id: &str, call: &ToolCall) -> ToolCallEntry {
+    ToolCallEntry {
+        tool_use_id: id.to_string(),
+        tool_name: call.name.clone(),
+        input_summary: call.input_summary.clone(),
+        started_at: call.started_at.clone(),
+        ended_at: call.ended_at.clone(),
+        duration_ms: duration_ms(call.started_at.as_deref(), call.ended_at.as_deref()),
+        is_error: call.is_error,
+        output_bytes: call.output_bytes,
+        agent_id: call.agent_id.clone(),
+        tool_use_line: call.tool_use_line,
+        tool_result_line: call.tool_result_line,
+    }
+}
+
+/// Page through the tool calls of a session.
+///
+/// Calls whose tool_use isn't in the session (a result for a call made
+/// before a resume, for instance) are skipped.
+pub fn query_tool_calls(index: &SessionIndex, query: &ToolCallQuery) -> ToolCallPage {
+    let mut entries: Vec<ToolCallEntry> = index
+        .tool_calls()
+        .filter(|(_, call)| call.tool_use_line.is_some())
+        .map(|(id, call)| to_entry(id, call))
+        .collect();
+
+    let tools = tool_stats(&entries);
+
+    entries.retain(|e| {
+        query
+            .tool_name
+            .as_ref()
+            .is_none_or(|name| e.tool_name.as_ref() == Some(name))
+            && (!query.errors_only.unwrap_or(false) || e.is_error)
+    });
+
+    let sort_by = query.sort_by.unwrap_or_default();
+    entries.sort_by(|a, b| {
+        let ordering = match sort_by {
+            ToolCallSortKey::Sequence => std::cmp::Ordering::Equal,
+            ToolCallSortKey::DurationMs => a.duration_ms.cmp(&b.duration_ms),
+            ToolCallSortKey::OutputBytes => a.output_bytes.cmp(&b.output_bytes),
+            ToolCallSortKey::ToolName => a.tool_name.cmp(&b.tool_name),
+        }
+        // Break ties by session order (then id) so paging is stable
+        .then_with(|| a.tool_use_line.cmp(&b.tool_use_line))
+        .then_with(|| a.tool_use_id.cmp(&b.tool_use_id));
+        if query.descending.unwrap_or(false) {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    let total_count = entries.len() as u32;
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(200);
+    let calls: Vec<ToolCallEntry> = entries
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    let has_more = (offset as usize + calls.len()) < total_count as usize;
+
+    ToolCallPage {
+        calls,
+        total_count,
+        offset,
+        has_more,
+        tools,
+    }
+}
+
+/// Aggregate calls per tool, most used first.
+pub(super) fn tool_stats(entries: &[ToolCallEntry]) -> Vec<ToolStats> {
+    let mut by_tool: HashMap<&str, ToolStats> = HashMap::new();
+    let mut timed: HashMap<&str, u32> = HashMap::new();
+
+    for entry in entries {
+        let name = entry.tool_name.as_deref().unwrap_or("unknown");
+        let stats = by_tool.entry(name).or_insert_with(|| ToolStats {
+            tool_name: name.to_string(),
+            ..Default::default()
+        });
+        stats.calls += 1;
+        stats.total_output_bytes += entry.output_bytes;
+        if entry.is_error {
+            stats.errors += 1;
+        }
+        if entry.tool_result_line.is_none() {
+            stats.pending += 1;
+        }
+        if let Some(duration) = entry.duration_ms {
+            stats.total_duration_ms += duration;
+            stats.max_duration_ms = stats.max_duration_ms.max(Some(duration));
+            *timed.entry(name).or_default() += 1;
+        }
+    }
+
+    let mut tools: Vec<ToolStats> = by_tool
+        .into_iter()
+        .map(|(name, mut stats)| {
+            if let Some(&count) = timed.get(name) {
+                stats.avg_duration_ms = Some(stats.total_duration_ms / count as u64);
+            }
+            stats
+        })
+        .collect();
+    tools.sort_by(|a, b| {
+        b.calls
+            .cmp(&a.calls)
+            .then_with(|| a.tool_name.cmp(&b.tool_name))
+    });
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::{jsonl, write_session, write_session_raw};
+    use crate::session_index::update_index_incremental;
+    use serde_json::json;
+
+    fn tool_use(ts: &str, id: &str, name: &str, input: Value) -> Value {
+        json!({"type": "assistant", "timestamp": ts, "message": {"content": [
+            {"type": "tool_use", "id": id, "name": name, "input": input}
+        ]}})
+    }
+
+    fn tool_result(ts: &str, id: &str, content: Value, is_error: bool) -> Value {
+        json!({"type": "user", "timestamp": ts, "message": {"content": [
+            {"type": "tool_result", "tool_use_id": id, "content": content, "is_error": is_error}
+        ]}})
+    }
+
+    #[test]
+    fn test_tool_call_timeline() {
+        let lines = [
+            tool_use(
+                "2025-01-01T00:00:00Z",
+                "t1",
+                "Bash",
+                json!({"command": "cargo test"}),
+            ),
+            tool_result("2025-01-01T00:00:05Z", "t1", json!("ok"), false),
+            tool_use(
+                "2025-01-01T00:00:06Z",
+                "t2",
+                "Bash",
+                json!({"command": "false"}),
+            ),
+            tool_result("2025-01-01T00:00:06.250Z", "t2", json!("exit 1"), true),
+            tool_use(
+                "2025-01-01T00:00:07Z",
+                "t3",
+                "Task",
+                json!({"description": "explore", "prompt": "..."}),
+            ),
+        ];
+        let session = write_session("tool-calls", &lines);
+        let path = session.path();
+        let mut index = session.index("/proj");
+
+        // The Task result arrives later, with the sub-agent it launched
+        let mut result = tool_result(
+            "2025-01-01T00:01:07Z",
+            "t3",
+            json!([{"type": "text", "text": "done"}]),
+            false,
+        );
+        result["toolUseResult"] = json!({"agentId": "a1b2", "status": "completed"});
+        session.append(&format!("{}\n", result));
+        update_index_incremental(&mut index, path, "/proj").unwrap();
+
+        let page = query_tool_calls(&index, &ToolCallQuery::default());
+        assert_eq!(page.total_count, 3);
+        let ids: Vec<&str> = page.calls.iter().map(|c| c.tool_use_id.as_str()).collect();
+        assert_eq!(ids, ["t1", "t2", "t3"]);
+        assert_eq!(page.calls[0].input_summary, "cargo test");
+        assert_eq!(page.calls[0].duration_ms, Some(5000));
+        assert_eq!(page.calls[1].duration_ms, Some(250));
+        assert!(page.calls[1].is_error);
+        assert_eq!(page.calls[2].agent_id.as_deref(), Some("a1b2"));
+        assert_eq!(page.calls[2].output_bytes, 4);
+
+        let bash = &page.tools[0];
+        assert_eq!(bash.tool_name, "Bash");
+        assert_eq!((bash.calls, bash.errors), (2, 1));
+        assert_eq!(bash.avg_duration_ms, Some(2625));
+        assert_eq!(bash.max_duration_ms, Some(5000));
+
+        let slowest = query_tool_calls(
+            &index,
+            &ToolCallQuery {
+                sort_by: Some(ToolCallSortKey::DurationMs),
+                descending: Some(true),
+                limit: Some(1),
+                ..Default::default()
+            },
+        );
+        assert_eq!(slowest.calls[0].tool_use_id, "t3");
+        assert!(slowest.has_more);
+
+        let errors = query_tool_calls(
+            &index,
+            &ToolCallQuery {
+                errors_only: Some(true),
+                ..Default::default()
+            },
+        );
+        assert_eq!(errors.total_count, 1);
+        assert_eq!(errors.tools.len(), 2);
+    }
+
+    #[test]
+    fn test_malformed_lines_and_missing_results() {
+        let t1_result = tool_result("2025-01-01T00:00:02Z", "t1", json!("ok"), false).to_string();
+        let (head, tail) = t1_result.split_at(t1_result.len() / 2);
+        let lines = [
+            "{not json".to_string(),
+            tool_use(
+                "2025-01-01T00:00:00Z",
+                "t1",
+                "Bash",
+                json!({"command": "ls"}),
+            )
+            .to_string(),
+            // A tool_use without an id can't be paired with anything
+            json!({"type": "assistant", "message": {"content": [
+                {"type": "tool_use", "name": "Bash", "input": {"command": "pwd"}}
+            ]}})
+            .to_string(),
+            // A result for a call made before the session was resumed
+            tool_result("2025-01-01T00:00:01Z", "orphan", json!("?"), false).to_string(),
+            tool_use("not a time", "t2", "Read", json!({"file_path": "a.rs"})).to_string(),
+            tool_result("2025-01-01T00:00:03Z", "t2", json!("fn main() {}"), false).to_string(),
+        ];
+        // The last line is still being written
+        let session =
+            write_session_raw("tool-calls-malformed", format!("{}{}", jsonl(&lines), head));
+        let path = session.path();
+        let mut index = session.index("/proj");
+
+        let page = query_tool_calls(&index, &ToolCallQuery::default());
+        let ids: Vec<&str> = page.calls.iter().map(|c| c.tool_use_id.as_str()).collect();
+        assert_eq!(ids, ["t1", "t2"]);
+        assert_eq!(page.calls[0].tool_result_line, None);
+        assert_eq!(page.calls[0].duration_ms, None);
+        // Unparseable timestamps leave the duration unknown
+        assert_eq!(page.calls[1].tool_result_line, Some(5));
+        assert_eq!(page.calls[1].duration_ms, None);
+        let bash = page.tools.iter().find(|t| t.tool_name == "Bash").unwrap();
+        assert_eq!((bash.calls, bash.pending), (1, 1));
+        assert_eq!(bash.avg_duration_ms, None);
+
+        // The rest of the line arrives
+        session.append(&format!("{}\n", tail));
+        update_index_incremental(&mut index, path, "/proj").unwrap();
+
+        let page = query_tool_calls(&index, &ToolCallQuery::default());
+        assert_eq!(page.total_count, 2);
+        assert_eq!(page.calls[0].tool_result_line, Some(6));
+        assert_eq!(page.calls[0].duration_ms, Some(2000));
+        assert_eq!(page.tools.iter().map(|t| t.pending).sum::<u32>(), 0);
+    }
+}
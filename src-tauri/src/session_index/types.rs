@@ -9,29 +9,60 @@ use std::time::SystemTime;
 
 use crate::claude_code::FileEdit;
 
+use super::hunks::changed_lines;
+use super::intern::{UuidId, UuidInterner};
+use super::stats::StatsCounters;
+
+/// Marker for "no value" in the dense per-UUID tables.
+const NONE: u32 = u32::MAX;
+
 /// Index for a single session's JSONL file.
 ///
 /// Built once when a session is opened, updated incrementally on file changes.
 /// Provides O(1) lookups for UUIDs, file edits, and parent chain walking.
+///
+/// Kept compact because very large sessions can have millions of lines:
+/// line offsets are stored as start positions only, and UUIDs are interned
+/// once and referenced everywhere else by a dense id.
 #[derive(Debug, Clone)]
 pub struct SessionIndex {
     // === File State (for incremental updates) ===
-    /// Size of file when index was last built/updated
+    /// Bytes indexed so far (end of the last complete line).
+    /// May trail the on-disk size while a line is still being written.
     pub file_size: u64,
     /// Modification time when index was last built/updated
     pub last_modified: SystemTime,
+    /// Fingerprint of the indexed prefix, checked before appending so a
+    /// rewritten or replaced file is rebuilt instead of reusing stale offsets
+    pub fingerprint: PrefixFingerprint,
 
     // === Line Index (for pagination) ===
-    /// (byte_offset, line_length) for each line in the file
-    pub line_offsets: Vec<(u64, usize)>,
+    /// Byte offset where each line starts. Lines are contiguous, so a line's
+    /// length is the next line's start (or `file_size`) minus its own.
+    line_starts: Vec<u64>,
 
     // === UUID Lookups (for chain walking) ===
-    /// UUID → sequence number (line index)
-    pub uuid_to_line: HashMap<String, u32>,
+    /// Interned UUID strings
+    uuids: UuidInterner,
+    /// UUID id → sequence number (NONE if only seen as a parent)
+    uuid_lines: Vec<u32>,
 
     // === Parent Chain (for edit context) ===
-    /// UUID → parentUuid (for walking the conversation chain)
-    pub parent_map: HashMap<String, String>,
+    /// UUID id → parentUuid id (NONE if no parent)
+    parent_ids: Vec<UuidId>,
+
+    // === Branches (for conversation graph) ===
+    /// (line, leafUuid id) of the most recent summary entry
+    latest_summary_leaf: Option<(u32, UuidId)>,
+
+    // === Tool Calls (for the timeline and joining policy telemetry) ===
+    /// Interned tool_use ids (kept apart from event UUIDs)
+    tool_use_ids: UuidInterner,
+    /// Tool call id → the tool_use paired with its tool_result
+    tool_calls: Vec<ToolCall>,
+    /// Sequence number → ids of the tool calls whose tool_use or tool_result
+    /// is on that line
+    tool_call_lines: HashMap<u32, Vec<UuidId>>,
 
     // === Human Message Boundaries (for edit context) ===
     /// Sequence numbers of "me" messages (actual human input, not tool results)
@@ -43,18 +74,57 @@ pub struct SessionIndex {
     pub file_edits: Vec<FileEdit>,
     /// file_path → sequence numbers of edits to that file
     pub file_to_edit_lines: HashMap<String, Vec<u32>>,
+    /// file_path → (lines removed, lines added) over all its edits
+    pub file_line_changes: HashMap<String, (u64, u64)>,
 
     // === Edit Metadata (for context feature) ===
     /// Sequence number → (byte_offset, messageId) for edits
     /// Allows looking up the message context for any edit
     pub edit_metadata: HashMap<u32, EditMetadata>,
+
+    // === Statistics (for the session summary) ===
+    /// Counters accumulated line by line
+    pub stats: StatsCounters,
+}
+
+/// Hashes of the first and last indexed lines (including their newlines).
+///
+/// Cheap to verify (two small reads) and catches files that were replaced
+/// with same-or-larger content, which a size check alone would miss.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrefixFingerprint {
+    pub first_line: u64,
+    pub last_line: u64,
+}
+
+/// A tool_use paired with its tool_result.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolCall {
+    /// Line of the assistant message containing the tool_use
+    pub tool_use_line: Option<u32>,
+    /// Line of the user message containing the tool_result
+    pub tool_result_line: Option<u32>,
+    /// Tool name (e.g., "Bash")
+    pub name: Option<String>,
+    /// Short description of the input (command, file path, pattern...)
+    pub input_summary: String,
+    /// Timestamp of the tool_use
+    pub started_at: Option<String>,
+    /// Timestamp of the tool_result
+    pub ended_at: Option<String>,
+    /// Whether the result was an error
+    pub is_error: bool,
+    /// Size of the result content in bytes
+    pub output_bytes: u64,
+    /// Sub-agent launched by the call (Task tool)
+    pub agent_id: Option<String>,
 }
 
 /// Metadata for a single file edit event.
 #[derive(Debug, Clone)]
 pub struct EditMetadata {
-    /// UUID of this event (for parent chain walking)
-    pub uuid: Option<String>,
+    /// Interned UUID of this event (for parent chain walking)
+    pub uuid: Option<UuidId>,
 }
 
 /// Status of the session index, returned to frontend.
@@ -79,29 +149,153 @@ impl SessionIndex {
         Self {
             file_size: 0,
             last_modified: SystemTime::UNIX_EPOCH,
-            line_offsets: Vec::new(),
-            uuid_to_line: HashMap::new(),
-            parent_map: HashMap::new(),
+            fingerprint: PrefixFingerprint::default(),
+            line_starts: Vec::new(),
+            uuids: UuidInterner::default(),
+            uuid_lines: Vec::new(),
+            parent_ids: Vec::new(),
+            latest_summary_leaf: None,
+            tool_use_ids: UuidInterner::default(),
+            tool_calls: Vec::new(),
+            tool_call_lines: HashMap::new(),
             human_message_lines: Vec::new(),
             file_edits: Vec::new(),
             file_to_edit_lines: HashMap::new(),
+            file_line_changes: HashMap::new(),
             edit_metadata: HashMap::new(),
+            stats: StatsCounters::default(),
         }
     }
 
     /// Get total number of events (lines) in the session.
     pub fn total_events(&self) -> u32 {
-        self.line_offsets.len() as u32
+        self.line_starts.len() as u32
+    }
+
+    /// Get the (byte_offset, line_length) of a line.
+    pub fn line_offset(&self, line: u32) -> Option<(u64, usize)> {
+        let start = *self.line_starts.get(line as usize)?;
+        let end = self
+            .line_starts
+            .get(line as usize + 1)
+            .copied()
+            .unwrap_or(self.file_size);
+        Some((start, (end - start) as usize))
+    }
+
+    /// Record a complete line of `length` bytes at the end of the indexed
+    /// prefix, returning its sequence number.
+    pub fn push_line(&mut self, length: usize) -> u32 {
+        let line = self.total_events();
+        self.line_starts.push(self.file_size);
+        self.file_size += length as u64;
+        line
+    }
+
+    /// Record an event's UUID (and its parent) at a line, returning its id.
+    pub fn record_uuid(&mut self, uuid: &str, parent_uuid: Option<&str>, line: u32) -> UuidId {
+        let id = self.intern(uuid);
+        self.uuid_lines[id as usize] = line;
+        if let Some(parent) = parent_uuid {
+            let parent_id = self.intern(parent);
+            self.parent_ids[id as usize] = parent_id;
+        }
+        id
+    }
+
+    /// Record the `leafUuid` of a summary entry at a line.
+    pub fn record_summary_leaf(&mut self, leaf_uuid: &str, line: u32) {
+        let id = self.intern(leaf_uuid);
+        self.latest_summary_leaf = Some((line, id));
+    }
+
+    /// Get the (line, leaf id) of the most recent summary entry.
+    pub fn latest_summary_leaf(&self) -> Option<(u32, UuidId)> {
+        self.latest_summary_leaf
+    }
+
+    /// Get the tool call for a tool_use id, creating it if needed, and note
+    /// that it appears on a line.
+    pub fn record_tool_call(&mut self, tool_use_id: &str, line: u32) -> &mut ToolCall {
+        let id = self.tool_use_ids.intern(tool_use_id);
+        if id as usize == self.tool_calls.len() {
+            self.tool_calls.push(ToolCall::default());
+        }
+        let ids = self.tool_call_lines.entry(line).or_default();
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+        &mut self.tool_calls[id as usize]
+    }
+
+    /// Look up the tool call for a tool_use id.
+    pub fn tool_call(&self, tool_use_id: &str) -> Option<&ToolCall> {
+        let id = self.tool_use_ids.get(tool_use_id)?;
+        self.tool_calls.get(id as usize)
+    }
+
+    /// Iterate over every tool call with its tool_use id, in first-seen order.
+    pub fn tool_calls(&self) -> impl Iterator<Item = (&str, &ToolCall)> + '_ {
+        self.tool_calls
+            .iter()
+            .enumerate()
+            .filter_map(|(id, call)| Some((self.tool_use_ids.resolve(id as UuidId)?, call)))
+    }
+
+    /// Get the tool_use ids whose tool_use or tool_result is on a line.
+    pub fn tool_use_ids_at(&self, line: u32) -> Vec<&str> {
+        self.tool_call_lines
+            .get(&line)
+            .into_iter()
+            .flatten()
+            .filter_map(|&id| self.tool_use_ids.resolve(id))
+            .collect()
+    }
+
+    /// Number of interned UUIDs (ids are `0..uuid_count()`).
+    pub fn uuid_count(&self) -> u32 {
+        self.uuid_lines.len() as u32
+    }
+
+    /// Look up the interned id for a UUID.
+    pub fn uuid_id(&self, uuid: &str) -> Option<UuidId> {
+        self.uuids.get(uuid)
+    }
+
+    /// Get the UUID string for an interned id.
+    pub fn uuid_str(&self, id: UuidId) -> Option<&str> {
+        self.uuids.resolve(id)
+    }
+
+    /// Intern a UUID, growing the per-UUID tables for new ids.
+    fn intern(&mut self, uuid: &str) -> UuidId {
+        let id = self.uuids.intern(uuid);
+        if id as usize == self.uuid_lines.len() {
+            self.uuid_lines.push(NONE);
+            self.parent_ids.push(NONE);
+        }
+        id
     }
 
     /// Look up the line number for a UUID.
     pub fn line_for_uuid(&self, uuid: &str) -> Option<u32> {
-        self.uuid_to_line.get(uuid).copied()
+        self.uuid_id(uuid).and_then(|id| self.line_for_id(id))
     }
 
-    /// Get the parent UUID for a given UUID.
-    pub fn parent_of(&self, uuid: &str) -> Option<&String> {
-        self.parent_map.get(uuid)
+    /// Look up the line number for an interned UUID.
+    pub fn line_for_id(&self, id: UuidId) -> Option<u32> {
+        self.uuid_lines
+            .get(id as usize)
+            .copied()
+            .filter(|&line| line != NONE)
+    }
+
+    /// Get the parent of an interned UUID.
+    pub fn parent_id(&self, id: UuidId) -> Option<UuidId> {
+        self.parent_ids
+            .get(id as usize)
+            .copied()
+            .filter(|&parent| parent != NONE)
     }
 
     /// Check if a line is a human message boundary.
@@ -118,6 +312,27 @@ impl SessionIndex {
         }
     }
 
+    /// Add the lines an edit changes to its file's totals.
+    pub fn record_line_changes(&mut self, path: &str, old: &str, new: &str) {
+        let (removed, added) = changed_lines(old, new);
+        let totals = self.file_line_changes.entry(path.to_string()).or_default();
+        totals.0 += removed;
+        totals.1 += added;
+    }
+
+    /// Copy the per-file line totals onto `file_edits`.
+    pub fn apply_line_changes(&mut self) {
+        for edit in &mut self.file_edits {
+            let (removed, added) = self
+                .file_line_changes
+                .get(&edit.path)
+                .copied()
+                .unwrap_or_default();
+            edit.lines_removed = removed;
+            edit.lines_added = added;
+        }
+    }
+
     /// Create IndexStatus for frontend.
     pub fn to_status(&self) -> IndexStatus {
         IndexStatus {
@@ -153,3 +368,39 @@ impl IndexStatus {
         }
     }
 }
+
+#[cfg(test)]
+impl SessionIndex {
+    /// Rough heap footprint in bytes (for benchmarks).
+    pub fn approx_heap_bytes(&self) -> usize {
+        use std::mem::size_of;
+
+        let uuid_count = self.uuid_lines.len();
+        let uuid_bytes: usize = (0..uuid_count as UuidId)
+            .filter_map(|id| self.uuids.resolve(id))
+            .map(|s| s.len() + 2 * size_of::<usize>())
+            .sum();
+        let uuid_tables = uuid_count
+            * (size_of::<usize>() * 2 // Vec<Arc<str>> entry
+                + size_of::<usize>() * 2 + size_of::<UuidId>() // map entry
+                + size_of::<u32>() * 2); // line + parent tables
+        let edit_lines: usize = self
+            .file_to_edit_lines
+            .iter()
+            .map(|(path, lines)| path.len() + lines.len() * size_of::<u32>())
+            .sum();
+        let line_changes: usize = self
+            .file_line_changes
+            .keys()
+            .map(|path| path.len() + 2 * size_of::<u64>())
+            .sum();
+
+        self.line_starts.capacity() * size_of::<u64>()
+            + uuid_bytes
+            + uuid_tables
+            + self.human_message_lines.capacity() * size_of::<u32>()
+            + edit_lines
+            + line_changes
+            + self.edit_metadata.len() * (size_of::<u32>() + size_of::<EditMetadata>())
+    }
+}
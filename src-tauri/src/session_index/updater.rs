@@ -6,19 +6,24 @@ use serde::Deserialize;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::io::{BufReader, Seek, SeekFrom};
 use std::path::Path;
 
 use crate::claude_code::FileEditType;
 
-use super::builder::build_session_index;
+use super::builder::{
+    build_session_index, edit_pairs, fingerprint_prefix, read_complete_line, record_tool_calls,
+    trim_line_ending,
+};
+use super::intern::UuidId;
+use super::stats::StatsEntry;
 use super::types::{EditMetadata, SessionIndex};
 
 /// Result of an incremental update.
 pub enum UpdateResult {
     /// Index was updated incrementally (fast path)
     Updated,
-    /// Index was rebuilt from scratch (file was truncated or corrupted)
+    /// Index was rebuilt from scratch (file was truncated, rewritten or replaced)
     Rebuilt,
     /// No update needed (file unchanged)
     Unchanged,
@@ -27,7 +32,12 @@ pub enum UpdateResult {
 /// Update an existing session index incrementally.
 ///
 /// If the file has grown (append-only), only parse new lines.
-/// If the file has shrunk or been modified, rebuild entirely.
+/// If the file has shrunk, or the indexed prefix no longer matches its
+/// fingerprint (the file was rewritten or replaced), rebuild entirely.
+///
+/// Only newline-terminated lines are indexed. If the file ends mid-line
+/// (the watcher fired while Claude Code was still writing), the tail is left
+/// pending and re-read on the next update once its newline has arrived.
 pub fn update_index_incremental(
     index: &mut SessionIndex,
     session_file: &Path,
@@ -54,40 +64,57 @@ pub fn update_index_incremental(
     let mut file = File::open(session_file)
         .map_err(|e| format!("Failed to open session file: {}", e))?;
 
+    // Verify the indexed prefix is still what we indexed. A missing or
+    // changed first/last line means the file was replaced, not appended to.
+    let still_matches = fingerprint_prefix(&mut file, index)
+        .map(|fingerprint| fingerprint == index.fingerprint)
+        .unwrap_or(false);
+    if !still_matches {
+        *index = build_session_index(session_file, project_path)?;
+        return Ok(UpdateResult::Rebuilt);
+    }
+
     // Seek to where we left off
     file.seek(SeekFrom::Start(index.file_size))
         .map_err(|e| format!("Failed to seek in file: {}", e))?;
 
-    let reader = BufReader::new(file);
-    let mut byte_offset = index.file_size;
-    let start_sequence = index.line_offsets.len() as u32;
+    let mut reader = BufReader::new(file);
+    let start_sequence = index.total_events();
+    let mut buf = Vec::new();
 
     // Track new file edits
     let mut new_file_operations: HashMap<String, FileEditType> = HashMap::new();
     let mut new_files_with_prior_content: HashSet<String> = HashSet::new();
     let mut new_file_timestamps: HashMap<String, String> = HashMap::new();
 
-    for (rel_seq, line_result) in reader.lines().enumerate() {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(_) => continue,
-        };
-
-        let line_len = line.len() + 1; // +1 for newline
-        let sequence = start_sequence + rel_seq as u32;
+    loop {
+        let line_len = read_complete_line(&mut reader, &mut buf)
+            .map_err(|e| format!("Failed to read session file: {}", e))?;
+        if line_len == 0 {
+            break;
+        }
 
-        // Record line offset
-        index.line_offsets.push((byte_offset, line_len));
+        // Record line offset (advances file_size past this line)
+        let byte_offset = index.file_size;
+        let sequence = index.push_line(line_len);
 
         // Parse the JSON entry
-        if let Ok(entry) = serde_json::from_str::<JsonEntry>(&line) {
-            // Extract UUID and parent UUID
-            if let Some(ref uuid) = entry.uuid {
-                index.uuid_to_line.insert(uuid.clone(), sequence);
-
-                if let Some(ref parent) = entry.parent_uuid {
-                    index.parent_map.insert(uuid.clone(), parent.clone());
-                }
+        if let Ok(entry) = serde_json::from_slice::<JsonEntry>(trim_line_ending(&buf)) {
+            // Extract UUID and parent UUID. A compaction boundary has no
+            // parentUuid; its logicalParentUuid links it to the conversation
+            // it continues
+            let parent_uuid = entry
+                .parent_uuid
+                .as_deref()
+                .or(entry.logical_parent_uuid.as_deref());
+            let uuid_id = entry
+                .uuid
+                .as_deref()
+                .map(|uuid| index.record_uuid(uuid, parent_uuid, sequence));
+
+            // Summaries point at the leaf of the conversation they describe
+            if let Some(ref leaf_uuid) = entry.leaf_uuid {
+                index.record_summary_leaf(leaf_uuid, sequence);
             }
 
             // Check if this is a human message boundary
@@ -99,6 +126,32 @@ pub fn update_index_incremental(
                 }
             }
 
+            // Track tool calls and their results
+            if let Some(content) = entry.message.as_ref().and_then(|m| m.content.as_ref()) {
+                record_tool_calls(
+                    index,
+                    content,
+                    sequence,
+                    entry.timestamp.as_deref(),
+                    entry.tool_use_result.as_ref(),
+                );
+            }
+
+            // Accumulate session statistics
+            index.stats.record(
+                sequence,
+                &StatsEntry {
+                    entry_type: entry.entry_type.as_deref(),
+                    subtype: entry.subtype.as_deref(),
+                    timestamp: entry.timestamp.as_deref(),
+                    message_id: entry.message.as_ref().and_then(|m| m.id.as_deref()),
+                    content: entry.message.as_ref().and_then(|m| m.content.as_ref()),
+                    usage: entry.message.as_ref().and_then(|m| m.usage.as_ref()),
+                    compact_metadata: entry.compact_metadata.as_ref(),
+                    is_api_error: entry.is_api_error_message == Some(true),
+                },
+            );
+
             // Extract file edits from assistant messages
             if entry.entry_type.as_deref() == Some("assistant") {
                 if let Some(ref message) = entry.message {
@@ -110,8 +163,7 @@ pub fn update_index_incremental(
                                     project_path,
                                     sequence,
                                     byte_offset,
-                                    entry.uuid.as_deref(),
-                                    entry.parent_uuid.as_deref(),
+                                    uuid_id,
                                     entry.timestamp.as_deref(),
                                     index,
                                     &mut new_file_operations,
@@ -124,8 +176,6 @@ pub fn update_index_incremental(
                 }
             }
         }
-
-        byte_offset += line_len as u64;
     }
 
     // Merge new file edits into existing
@@ -136,10 +186,17 @@ pub fn update_index_incremental(
         new_file_timestamps,
     );
 
-    // Update file state
-    index.file_size = current_size;
+    // Update file state (file_size already covers the last complete line)
     index.last_modified = current_mtime;
 
+    if index.total_events() == start_sequence {
+        // Nothing but a partial line was appended
+        return Ok(UpdateResult::Unchanged);
+    }
+
+    index.fingerprint = fingerprint_prefix(reader.get_mut(), index)
+        .map_err(|e| format!("Failed to fingerprint session file: {}", e))?;
+
     Ok(UpdateResult::Updated)
 }
 
@@ -182,8 +239,7 @@ fn process_tool_use_incremental(
     project_path: &str,
     sequence: u32,
     _byte_offset: u64,
-    uuid: Option<&str>,
-    _parent_uuid: Option<&str>,
+    uuid: Option<UuidId>,
     timestamp: Option<&str>,
     index: &mut SessionIndex,
     new_file_operations: &mut HashMap<String, FileEditType>,
@@ -205,14 +261,15 @@ fn process_tool_use_incremental(
     };
 
     match tool_name {
-        "Edit" => {
+        "Edit" | "MultiEdit" => {
             if let Some(file_path) = input.get("file_path").and_then(|v| v.as_str()) {
                 let rel_path = make_relative_path(file_path, project_path);
 
-                if let Some(old_str) = input.get("old_string").and_then(|v| v.as_str()) {
+                for (old_str, new_str) in edit_pairs(tool_name, input) {
                     if !old_str.is_empty() {
                         new_files_with_prior_content.insert(rel_path.clone());
                     }
+                    index.record_line_changes(&rel_path, old_str, new_str);
                 }
 
                 new_file_operations.insert(rel_path.clone(), FileEditType::Modified);
@@ -221,12 +278,7 @@ fn process_tool_use_incremental(
                     new_file_timestamps.insert(rel_path.clone(), ts.to_string());
                 }
 
-                index.edit_metadata.insert(
-                    sequence,
-                    EditMetadata {
-                        uuid: uuid.map(String::from),
-                    },
-                );
+                index.edit_metadata.insert(sequence, EditMetadata { uuid });
 
                 index
                     .file_to_edit_lines
@@ -245,16 +297,14 @@ fn process_tool_use_incremental(
                     new_file_operations.insert(rel_path.clone(), FileEditType::Added);
                 }
 
+                let content = input.get("content").and_then(|v| v.as_str());
+                index.record_line_changes(&rel_path, "", content.unwrap_or(""));
+
                 if let Some(ts) = timestamp {
                     new_file_timestamps.insert(rel_path.clone(), ts.to_string());
                 }
 
-                index.edit_metadata.insert(
-                    sequence,
-                    EditMetadata {
-                        uuid: uuid.map(String::from),
-                    },
-                );
+                index.edit_metadata.insert(sequence, EditMetadata { uuid });
 
                 index
                     .file_to_edit_lines
@@ -299,12 +349,15 @@ fn merge_new_file_edits(
                 path: path.clone(),
                 edit_type: final_type,
                 last_edited_at: new_file_timestamps.get(&path).cloned(),
+                lines_added: 0,
+                lines_removed: 0,
             });
         }
     }
 
     // Re-sort file edits
     index.file_edits.sort_by(|a, b| a.path.cmp(&b.path));
+    index.apply_line_changes();
 }
 
 /// Convert an absolute file path to a relative path from the project root.
@@ -328,6 +381,10 @@ struct JsonEntry {
     uuid: Option<String>,
     #[serde(rename = "parentUuid")]
     parent_uuid: Option<String>,
+    #[serde(rename = "logicalParentUuid")]
+    logical_parent_uuid: Option<String>,
+    #[serde(rename = "leafUuid")]
+    leaf_uuid: Option<String>,
     #[serde(rename = "userType")]
     user_type: Option<String>,
     #[serde(rename = "isCompactSummary")]
@@ -336,9 +393,270 @@ struct JsonEntry {
     is_meta: Option<bool>,
     message: Option<JsonMessage>,
     timestamp: Option<String>,
+    #[serde(rename = "toolUseResult")]
+    tool_use_result: Option<Value>,
+    subtype: Option<String>,
+    #[serde(rename = "compactMetadata")]
+    compact_metadata: Option<Value>,
+    #[serde(rename = "isApiErrorMessage")]
+    is_api_error_message: Option<bool>,
 }
 
 #[derive(Deserialize)]
 struct JsonMessage {
+    id: Option<String>,
     content: Option<Value>,
+    usage: Option<Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_index::test_util::write_session_raw;
+    use std::io::Write;
+
+    const PROJECT: &str = "/proj";
+
+    fn user_line(uuid: &str, text: &str) -> String {
+        format!(
+            "{}\n",
+            serde_json::json!({
+                "type": "user",
+                "userType": "external",
+                "uuid": uuid,
+                "message": { "content": text },
+            })
+        )
+    }
+
+    fn assert_same_index(a: &SessionIndex, b: &SessionIndex) {
+        assert_eq!(a.file_size, b.file_size);
+        assert_eq!(a.fingerprint, b.fingerprint);
+        assert_eq!(a.total_events(), b.total_events());
+        for line in 0..a.total_events() {
+            assert_eq!(a.line_offset(line), b.line_offset(line));
+        }
+        for uuid in ["u1", "u2", "u3", "b1", "b2", "b3"] {
+            assert_eq!(a.line_for_uuid(uuid), b.line_for_uuid(uuid));
+        }
+        assert_eq!(a.human_message_lines, b.human_message_lines);
+        assert_eq!(a.file_line_changes, b.file_line_changes);
+    }
+
+    #[test]
+    fn test_partial_line_stays_pending() {
+        let session = write_session_raw("updater-partial", "");
+        let path = session.path();
+        session.append(user_line("u1", "first"));
+        let mut index = build_session_index(path, PROJECT).unwrap();
+        assert_eq!(index.total_events(), 1);
+
+        let second = user_line("u2", "second");
+        let (head, tail) = second.as_bytes().split_at(10);
+
+        session.append(head);
+        let result = update_index_incremental(&mut index, path, PROJECT).unwrap();
+        assert!(matches!(result, UpdateResult::Unchanged));
+        assert_eq!(index.total_events(), 1);
+        assert!(index.line_for_uuid("u2").is_none());
+
+        session.append(tail);
+        let result = update_index_incremental(&mut index, path, PROJECT).unwrap();
+        assert!(matches!(result, UpdateResult::Updated));
+        assert_eq!(index.total_events(), 2);
+        assert_eq!(index.line_for_uuid("u2"), Some(1));
+        assert_eq!(
+            index.line_offset(1),
+            Some((index.line_offset(0).unwrap().1 as u64, second.len()))
+        );
+        assert_eq!(index.file_size, fs::metadata(path).unwrap().len());
+    }
+
+    #[test]
+    fn test_build_ignores_unterminated_tail() {
+        let session = write_session_raw("updater-unterminated", "");
+        let path = session.path();
+        let first = user_line("u1", "first");
+        session.append(&first);
+        session.append(br#"{"type":"user","uuid":"u2""#);
+
+        let index = build_session_index(path, PROJECT).unwrap();
+        assert_eq!(index.total_events(), 1);
+        assert_eq!(index.file_size, first.len() as u64);
+        assert!(index.line_for_uuid("u2").is_none());
+    }
+
+    #[test]
+    fn test_split_at_every_byte_boundary() {
+        // Multi-byte characters: 2-byte 'é', 3-byte '─', 4-byte '🎉'
+        let lines = [
+            user_line("u1", "héllo ─── world"),
+            user_line("u2", "party 🎉🎊 time"),
+            user_line("u3", "plain ascii"),
+        ];
+        let appended: String = lines[1..].concat();
+
+        let expected = write_session_raw("updater-split-expected", lines.concat()).index(PROJECT);
+
+        for split in 0..=appended.len() {
+            let session = write_session_raw(&format!("updater-split-{}", split), &lines[0]);
+            let path = session.path();
+            let mut index = build_session_index(path, PROJECT).unwrap();
+
+            let (head, tail) = appended.as_bytes().split_at(split);
+            session.append(head);
+            update_index_incremental(&mut index, path, PROJECT).unwrap();
+
+            // Never index past the last newline in what has been written so far
+            let written = lines[0].len() + split;
+            assert!(index.file_size as usize <= written, "split at {}", split);
+
+            session.append(tail);
+            update_index_incremental(&mut index, path, PROJECT).unwrap();
+            assert_same_index(&index, &expected);
+        }
+    }
+
+    #[test]
+    fn test_replaced_with_larger_file_is_rebuilt() {
+        let session = write_session_raw("updater-replaced", "");
+        let path = session.path();
+        session.append(user_line("a1", "original first"));
+        session.append(user_line("a2", "original second"));
+        let mut index = build_session_index(path, PROJECT).unwrap();
+
+        // Replace the file with different, larger content
+        let replacement = [
+            user_line("b1", "replacement first, a bit longer"),
+            user_line("b2", "replacement second, also longer"),
+            user_line("b3", "replacement third"),
+        ]
+        .concat();
+        fs::write(path, &replacement).unwrap();
+
+        let result = update_index_incremental(&mut index, path, PROJECT).unwrap();
+        assert!(matches!(result, UpdateResult::Rebuilt));
+        assert_eq!(index.total_events(), 3);
+        assert!(index.line_for_uuid("a1").is_none());
+        assert_eq!(index.line_for_uuid("b3"), Some(2));
+        assert_same_index(&index, &build_session_index(path, PROJECT).unwrap());
+    }
+
+    #[test]
+    fn test_rewritten_last_line_is_rebuilt() {
+        let session = write_session_raw("updater-rewritten", "");
+        let path = session.path();
+        let first = user_line("a1", "kept as-is");
+        session.append(&first);
+        session.append(user_line("a2", "before"));
+        let mut index = build_session_index(path, PROJECT).unwrap();
+
+        // Same first line and same length, but the last indexed line changed
+        let rewritten = [first, user_line("x2", "after!"), user_line("a3", "new")].concat();
+        fs::write(path, &rewritten).unwrap();
+
+        let result = update_index_incremental(&mut index, path, PROJECT).unwrap();
+        assert!(matches!(result, UpdateResult::Rebuilt));
+        assert!(index.line_for_uuid("a2").is_none());
+        assert_eq!(index.line_for_uuid("x2"), Some(1));
+        assert_eq!(index.line_for_uuid("a3"), Some(2));
+    }
+
+    #[test]
+    fn test_append_keeps_fingerprint_in_sync() {
+        let session = write_session_raw("updater-append", "");
+        let path = session.path();
+        session.append(user_line("u1", "first"));
+        let mut index = build_session_index(path, PROJECT).unwrap();
+
+        for (i, uuid) in ["u2", "u3", "u4"].iter().enumerate() {
+            session.append(user_line(uuid, "more"));
+            let result = update_index_incremental(&mut index, path, PROJECT).unwrap();
+            assert!(matches!(result, UpdateResult::Updated), "append {}", i);
+        }
+        assert_same_index(&index, &build_session_index(path, PROJECT).unwrap());
+    }
+
+    /// Assistant line with an Edit tool_use, chained to `parent`.
+    fn edit_line(uuid: &str, parent: &str, file: &str) -> String {
+        format!(
+            "{}\n",
+            serde_json::json!({
+                "type": "assistant",
+                "uuid": uuid,
+                "parentUuid": parent,
+                "timestamp": "2025-01-01T00:00:00Z",
+                "message": { "content": [{
+                    "type": "tool_use",
+                    "name": "Edit",
+                    "input": {
+                        "file_path": format!("{}/src/{}", PROJECT, file),
+                        "old_string": "a",
+                        "new_string": "b",
+                    },
+                }] },
+            })
+        )
+    }
+
+    /// Write `count` synthetic events: every 10th line is a human message,
+    /// the rest are chained edits spread over 100 files.
+    fn write_synthetic_session(path: &Path, start: usize, count: usize) {
+        let file = fs::OpenOptions::new().append(true).open(path).unwrap();
+        let mut file = std::io::BufWriter::new(file);
+        for i in start..start + count {
+            let uuid = format!("{:08x}-0000-4000-8000-{:012x}", i, i);
+            let line = if i % 10 == 0 {
+                user_line(&uuid, "next step")
+            } else {
+                let parent = format!("{:08x}-0000-4000-8000-{:012x}", i - 1, i - 1);
+                edit_line(&uuid, &parent, &format!("file_{}.rs", i % 100))
+            };
+            file.write_all(line.as_bytes()).unwrap();
+        }
+    }
+
+    /// Benchmark against a synthetic 1M-line session.
+    ///
+    /// Run with `cargo test --release bench_ -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_synthetic_1m_line_session() {
+        use std::sync::Arc;
+        use std::time::Instant;
+
+        const LINES: usize = 1_000_000;
+        let session = write_session_raw("updater-bench", "");
+        let path = session.path();
+        write_synthetic_session(path, 0, LINES);
+        let file_bytes = fs::metadata(path).unwrap().len();
+
+        let started = Instant::now();
+        let mut index = build_session_index(path, PROJECT).unwrap();
+        let build_time = started.elapsed();
+        assert_eq!(index.total_events(), LINES as u32);
+
+        write_synthetic_session(path, LINES, 1_000);
+        let started = Instant::now();
+        update_index_incremental(&mut index, path, PROJECT).unwrap();
+        let update_time = started.elapsed();
+        assert_eq!(index.total_events(), LINES as u32 + 1_000);
+
+        let shared = Arc::new(index);
+        let started = Instant::now();
+        let snapshot = Arc::clone(&shared);
+        let snapshot_time = started.elapsed();
+        let started = Instant::now();
+        let deep = SessionIndex::clone(&snapshot);
+        let clone_time = started.elapsed();
+        assert_eq!(deep.total_events(), shared.total_events());
+
+        println!("file size:          {} MiB", file_bytes / (1024 * 1024));
+        let heap_mib = shared.approx_heap_bytes() / (1024 * 1024);
+        println!("index heap (approx): {} MiB", heap_mib);
+        println!("full build:         {:?}", build_time);
+        println!("append 1000 lines:  {:?}", update_time);
+        println!("Arc snapshot:       {:?}", snapshot_time);
+        println!("deep clone:         {:?}", clone_time);
+    }
 }
@@ -5,11 +5,155 @@
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 
+#[cfg(target_os = "linux")]
+use crate::path_search::find_executable;
+
 /// Escape a string for safe use in shell commands.
 fn shell_escape(s: &str) -> String {
     format!("'{}'", s.replace('\'', "'\\''"))
 }
 
+/// Quote a command argument for the platform shell, leaving plain words
+/// (e.g., "claude", "--continue") as they are.
+fn quote_arg(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+".contains(c));
+    if plain {
+        return arg.to_string();
+    }
+
+    if cfg!(target_os = "windows") {
+        cmd_escape(arg)
+    } else {
+        shell_escape(arg)
+    }
+}
+
+/// Escape an argument for a command line run by `wt ... cmd /c`.
+///
+/// The argument is quoted the way programs split their command line
+/// (backslashes only escape quotes), then every cmd.exe metacharacter,
+/// quotes included, is caret-escaped so cmd never sees an operator or a
+/// `%VAR%`. A `;` would start a new Windows Terminal tab, so it's escaped
+/// for wt.
+fn cmd_escape(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        let escapes = if c == '"' {
+            backslashes * 2 + 1
+        } else {
+            backslashes
+        };
+        quoted.push_str(&"\\".repeat(escapes));
+        quoted.push(c);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+
+    let mut escaped = String::with_capacity(quoted.len() * 2);
+    for c in quoted.chars() {
+        match c {
+            '"' | '^' | '&' | '|' | '<' | '>' | '(' | ')' | '%' | '!' => {
+                escaped.push('^');
+                escaped.push(c);
+            }
+            ';' => escaped.push_str("\\;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// How to start Claude Code.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LaunchOptions {
+    /// Continue the most recent session (`--continue`)
+    pub continue_session: bool,
+    /// Resume a specific session (`--resume <id>`); takes precedence over
+    /// `continue_session`
+    pub resume_session_id: Option<String>,
+    /// Skip permission prompts (`--dangerously-skip-permissions`)
+    pub yolo_mode: bool,
+    /// Initial prompt
+    pub prompt: Option<String>,
+    /// Model alias or name (`--model`)
+    pub model: Option<String>,
+    /// Tools allowed without prompting (`--allowedTools`)
+    pub allowed_tools: Vec<String>,
+    /// Extra arguments passed through verbatim (each is quoted)
+    pub extra_args: Vec<String>,
+}
+
+/// Build the arguments of a `claude` invocation, program first.
+pub fn claude_args(options: &LaunchOptions) -> Result<Vec<String>, String> {
+    let mut args = vec!["claude".to_string()];
+
+    if let Some(id) = options
+        .resume_session_id
+        .as_deref()
+        .filter(|id| !id.is_empty())
+    {
+        if !crate::claude_code::is_uuid_format(id) {
+            return Err(format!("Invalid session ID: {}", id));
+        }
+        args.push("--resume".to_string());
+        args.push(id.to_string());
+    } else if options.continue_session {
+        args.push("--continue".to_string());
+    }
+
+    if options.yolo_mode {
+        args.push("--dangerously-skip-permissions".to_string());
+    }
+
+    if let Some(model) = options.model.as_deref().filter(|m| !m.is_empty()) {
+        args.push("--model".to_string());
+        args.push(model.to_string());
+    }
+
+    let tools: Vec<&str> = options
+        .allowed_tools
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if !tools.is_empty() {
+        args.push("--allowedTools".to_string());
+        args.push(tools.join(","));
+    }
+
+    args.extend(options.extra_args.iter().cloned());
+
+    if let Some(prompt) = options.prompt.as_deref().filter(|p| !p.trim().is_empty()) {
+        // Keep a prompt starting with "-" from being read as an option
+        if prompt.starts_with('-') {
+            args.push("--".to_string());
+        }
+        args.push(prompt.to_string());
+    }
+
+    Ok(args)
+}
+
+/// Build the shell command line of a `claude` invocation.
+pub fn claude_command(options: &LaunchOptions) -> Result<String, String> {
+    let args = claude_args(options)?;
+    Ok(args
+        .iter()
+        .map(|a| quote_arg(a))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
 /// Supported terminal emulators.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
@@ -28,8 +172,38 @@ pub enum TerminalType {
     Konsole,
     /// Alacritty
     Alacritty,
+    /// kitty
+    Kitty,
+    /// WezTerm
+    Wezterm,
+    /// xterm
+    Xterm,
+    /// foot
+    Foot,
+    /// Tilix
+    Tilix,
+    /// New window in the running tmux server
+    TmuxWindow,
+    /// New pane in the current window of the running tmux server
+    TmuxPane,
+    /// User-defined command template with `{cwd}` and `{cmd}` placeholders
+    Custom,
 }
 
+/// Linux terminals detected by their program on PATH.
+#[cfg(target_os = "linux")]
+const LINUX_TERMINALS: [(TerminalType, &str); 9] = [
+    (TerminalType::GnomeTerminal, "gnome-terminal"),
+    (TerminalType::Konsole, "konsole"),
+    (TerminalType::Alacritty, "alacritty"),
+    (TerminalType::Ghostty, "ghostty"),
+    (TerminalType::Kitty, "kitty"),
+    (TerminalType::Wezterm, "wezterm"),
+    (TerminalType::Foot, "foot"),
+    (TerminalType::Tilix, "tilix"),
+    (TerminalType::Xterm, "xterm"),
+];
+
 /// Get available terminals for the current platform.
 pub fn get_available_terminals() -> Vec<TerminalType> {
     #[cfg(target_os = "macos")]
@@ -56,45 +230,19 @@ pub fn get_available_terminals() -> Vec<TerminalType> {
 
     #[cfg(target_os = "linux")]
     {
-        let mut terminals = Vec::new();
-
-        // Check common Linux terminals
-        if Command::new("which")
-            .arg("gnome-terminal")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            terminals.push(TerminalType::GnomeTerminal);
-        }
-
-        if Command::new("which")
-            .arg("konsole")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            terminals.push(TerminalType::Konsole);
-        }
-
-        if Command::new("which")
-            .arg("alacritty")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            terminals.push(TerminalType::Alacritty);
-        }
-
-        if Command::new("which")
-            .arg("ghostty")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            terminals.push(TerminalType::Ghostty);
+        let mut terminals: Vec<TerminalType> = LINUX_TERMINALS
+            .iter()
+            .filter(|(_, program)| find_executable(program).is_some())
+            .map(|(terminal, _)| terminal.clone())
+            .collect();
+
+        // tmux windows and panes need a server to open them in
+        if find_executable("tmux").is_some() && tmux_server_running() {
+            terminals.push(TerminalType::TmuxWindow);
+            terminals.push(TerminalType::TmuxPane);
         }
 
+        terminals.push(TerminalType::Custom);
         terminals
     }
 
@@ -110,11 +258,17 @@ pub fn get_available_terminals() -> Vec<TerminalType> {
 }
 
 /// Launch a terminal with a command in a specific directory.
+///
+/// `custom_command` is the template used for [`TerminalType::Custom`].
 pub fn launch_terminal(
     terminal: &TerminalType,
     cwd: &str,
     command: &str,
+    custom_command: Option<&str>,
 ) -> Result<(), String> {
+    #[cfg(not(target_os = "linux"))]
+    let _ = custom_command;
+
     #[cfg(target_os = "macos")]
     {
         launch_terminal_macos(terminal, cwd, command)
@@ -122,7 +276,7 @@ pub fn launch_terminal(
 
     #[cfg(target_os = "linux")]
     {
-        launch_terminal_linux(terminal, cwd, command)
+        launch_terminal_linux(terminal, cwd, command, custom_command)
     }
 
     #[cfg(target_os = "windows")]
@@ -142,11 +296,9 @@ fn launch_terminal_macos(
     cwd: &str,
     command: &str,
 ) -> Result<(), String> {
-    // Escape single quotes in paths and commands for AppleScript
-    let escaped_cwd = cwd.replace('\\', "\\\\").replace('"', "\\\"");
-    let escaped_cmd = command.replace('\\', "\\\\").replace('"', "\\\"");
-
-    let full_command = format!("cd \"{}\" && {}", escaped_cwd, escaped_cmd);
+    let full_command = format!("cd {} && {}", shell_escape(cwd), command);
+    // Escape for embedding in an AppleScript string
+    let script_command = full_command.replace('\\', "\\\\").replace('"', "\\\"");
 
     match terminal {
         TerminalType::MacosTerminal => {
@@ -155,7 +307,7 @@ fn launch_terminal_macos(
                     activate
                     do script "{}"
                 end tell"#,
-                full_command.replace('"', "\\\"")
+                script_command
             );
 
             Command::new("osascript")
@@ -202,7 +354,7 @@ fn launch_terminal_macos(
                     activate
                     create window with default profile command "{}"
                 end tell"#,
-                full_command.replace('"', "\\\"")
+                script_command
             );
 
             Command::new("osascript")
@@ -236,52 +388,163 @@ fn launch_terminal_linux(
     terminal: &TerminalType,
     cwd: &str,
     command: &str,
+    custom_command: Option<&str>,
 ) -> Result<(), String> {
-    let full_command = format!("cd '{}' && {}", cwd, command);
+    let argv = linux_argv(terminal, cwd, command, custom_command)?;
 
-    match terminal {
-        TerminalType::GnomeTerminal => {
-            Command::new("gnome-terminal")
-                .arg("--")
-                .arg("sh")
-                .arg("-c")
-                .arg(&full_command)
-                .spawn()
-                .map_err(|e| format!("Failed to launch gnome-terminal: {}", e))?;
-        }
-        TerminalType::Konsole => {
-            Command::new("konsole")
-                .arg("-e")
-                .arg("sh")
-                .arg("-c")
-                .arg(&full_command)
-                .spawn()
-                .map_err(|e| format!("Failed to launch konsole: {}", e))?;
-        }
-        TerminalType::Alacritty => {
-            Command::new("alacritty")
-                .arg("-e")
-                .arg("sh")
-                .arg("-c")
-                .arg(&full_command)
-                .spawn()
-                .map_err(|e| format!("Failed to launch alacritty: {}", e))?;
-        }
-        TerminalType::Ghostty => {
-            Command::new("ghostty")
-                .arg("-e")
-                .arg("sh")
-                .arg("-c")
-                .arg(&full_command)
-                .spawn()
-                .map_err(|e| format!("Failed to launch ghostty: {}", e))?;
+    Command::new(&argv[0])
+        .args(&argv[1..])
+        .current_dir(cwd)
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", argv[0], e))?;
+
+    Ok(())
+}
+
+/// Whether a tmux server is running for this user.
+#[cfg(target_os = "linux")]
+fn tmux_server_running() -> bool {
+    Command::new("tmux")
+        .arg("has-session")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Build the argv that opens `terminal` running `command` in `cwd`.
+#[cfg(any(target_os = "linux", test))]
+fn linux_argv(
+    terminal: &TerminalType,
+    cwd: &str,
+    command: &str,
+    custom_command: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let full_command = format!("cd {} && {}", shell_escape(cwd), command);
+    let with_shell = |prefix: &[&str]| -> Vec<String> {
+        prefix
+            .iter()
+            .chain(&["sh", "-c"])
+            .map(|a| a.to_string())
+            .chain(std::iter::once(full_command.clone()))
+            .collect()
+    };
+
+    let argv = match terminal {
+        TerminalType::GnomeTerminal => with_shell(&["gnome-terminal", "--"]),
+        TerminalType::Konsole => with_shell(&["konsole", "-e"]),
+        TerminalType::Alacritty => with_shell(&["alacritty", "-e"]),
+        TerminalType::Ghostty => with_shell(&["ghostty", "-e"]),
+        TerminalType::Kitty => with_shell(&["kitty", "--directory", cwd]),
+        TerminalType::Wezterm => with_shell(&["wezterm", "start", "--cwd", cwd, "--"]),
+        TerminalType::Xterm => with_shell(&["xterm", "-e"]),
+        TerminalType::Foot => with_shell(&["foot", "--working-directory", cwd]),
+        // Tilix parses its -e argument as a command line
+        TerminalType::Tilix => vec![
+            "tilix".to_string(),
+            "--working-directory".to_string(),
+            cwd.to_string(),
+            "-e".to_string(),
+            format!("sh -c {}", shell_escape(&full_command)),
+        ],
+        // tmux runs its command argument through the shell itself
+        TerminalType::TmuxWindow => vec![
+            "tmux".to_string(),
+            "new-window".to_string(),
+            "-c".to_string(),
+            cwd.to_string(),
+            full_command,
+        ],
+        TerminalType::TmuxPane => vec![
+            "tmux".to_string(),
+            "split-window".to_string(),
+            "-c".to_string(),
+            cwd.to_string(),
+            full_command,
+        ],
+        TerminalType::Custom => {
+            let template = custom_command
+                .filter(|t| !t.trim().is_empty())
+                .ok_or("No custom terminal command configured")?;
+            custom_argv(template, cwd, command)?
         }
         _ => {
             return Err(format!("Terminal {:?} not supported on Linux", terminal));
         }
+    };
+
+    Ok(argv)
+}
+
+/// Expand a custom terminal command template into argv.
+///
+/// The template is split into words like a shell would (single and double
+/// quotes, backslash escapes), then `{cwd}` and `{cmd}` are substituted in
+/// each word. Substituted values are never re-split or interpreted, so e.g.
+/// `foot -D {cwd} sh -c {cmd}` is safe for any path.
+#[cfg(any(target_os = "linux", test))]
+fn custom_argv(template: &str, cwd: &str, command: &str) -> Result<Vec<String>, String> {
+    if !template.contains("{cmd}") {
+        return Err("Custom terminal command must contain {cmd}".to_string());
     }
 
-    Ok(())
+    Ok(split_words(template)?
+        .into_iter()
+        .map(|word| word.replace("{cwd}", cwd).replace("{cmd}", command))
+        .collect())
+}
+
+/// Split a command line into words, honoring quotes and backslash escapes.
+#[cfg(any(target_os = "linux", test))]
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                words.extend(word.take());
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated ' in command".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("Unterminated \" in command".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated \" in command".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    Ok(words)
 }
 
 #[cfg(target_os = "windows")]
@@ -320,4 +583,178 @@ mod tests {
         #[cfg(any(target_os = "macos", target_os = "windows"))]
         assert!(!terminals.is_empty());
     }
+
+    #[test]
+    fn test_linux_argv() {
+        let cwd = "/work/it's";
+        let cmd = "claude --continue";
+        let full = r#"cd '/work/it'\''s' && claude --continue"#;
+        let argv = |terminal: TerminalType| linux_argv(&terminal, cwd, cmd, None).unwrap();
+
+        assert_eq!(
+            argv(TerminalType::GnomeTerminal),
+            vec!["gnome-terminal", "--", "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Konsole),
+            vec!["konsole", "-e", "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Alacritty),
+            vec!["alacritty", "-e", "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Ghostty),
+            vec!["ghostty", "-e", "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Kitty),
+            vec!["kitty", "--directory", cwd, "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Wezterm),
+            vec!["wezterm", "start", "--cwd", cwd, "--", "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Xterm),
+            vec!["xterm", "-e", "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Foot),
+            vec!["foot", "--working-directory", cwd, "sh", "-c", full]
+        );
+        assert_eq!(
+            argv(TerminalType::Tilix),
+            vec![
+                "tilix".to_string(),
+                "--working-directory".to_string(),
+                cwd.to_string(),
+                "-e".to_string(),
+                format!("sh -c {}", shell_escape(full)),
+            ]
+        );
+        assert_eq!(
+            argv(TerminalType::TmuxWindow),
+            vec!["tmux", "new-window", "-c", cwd, full]
+        );
+        assert_eq!(
+            argv(TerminalType::TmuxPane),
+            vec!["tmux", "split-window", "-c", cwd, full]
+        );
+        assert!(linux_argv(&TerminalType::Iterm2, cwd, cmd, None).is_err());
+    }
+
+    #[test]
+    fn test_custom_argv() {
+        let cwd = "/work/my app";
+        let cmd = "claude 'fix it'";
+
+        let argv = linux_argv(
+            &TerminalType::Custom,
+            cwd,
+            cmd,
+            Some(r#"st -d {cwd} -T "Claude \"{cwd}\"" -e sh -c {cmd}"#),
+        )
+        .unwrap();
+        assert_eq!(
+            argv,
+            vec![
+                "st",
+                "-d",
+                cwd,
+                "-T",
+                "Claude \"/work/my app\"",
+                "-e",
+                "sh",
+                "-c",
+                cmd
+            ]
+        );
+
+        assert_eq!(
+            custom_argv("term --dir={cwd} '' {cmd}", cwd, cmd).unwrap(),
+            vec!["term", "--dir=/work/my app", "", cmd]
+        );
+        assert!(linux_argv(&TerminalType::Custom, cwd, cmd, None).is_err());
+        assert!(custom_argv("term -e sh", cwd, cmd).is_err());
+        assert!(custom_argv("term 'unterminated {cmd}", cwd, cmd).is_err());
+    }
+
+    #[test]
+    fn test_claude_args() {
+        let id = "0b1c2d3e-1111-2222-3333-444455556666";
+        let options = LaunchOptions {
+            continue_session: true,
+            resume_session_id: Some(id.to_string()),
+            yolo_mode: true,
+            prompt: Some("-fix the tests".to_string()),
+            model: Some("opus".to_string()),
+            allowed_tools: vec!["Read".to_string(), " Bash(git log:*) ".to_string()],
+            extra_args: vec!["--verbose".to_string()],
+        };
+        assert_eq!(
+            claude_args(&options).unwrap(),
+            vec![
+                "claude",
+                "--resume",
+                id,
+                "--dangerously-skip-permissions",
+                "--model",
+                "opus",
+                "--allowedTools",
+                "Read,Bash(git log:*)",
+                "--verbose",
+                "--",
+                "-fix the tests",
+            ]
+        );
+
+        let continued = LaunchOptions {
+            continue_session: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            claude_args(&continued).unwrap(),
+            vec!["claude", "--continue"]
+        );
+
+        let invalid = LaunchOptions {
+            resume_session_id: Some("abc; rm -rf ~".to_string()),
+            ..Default::default()
+        };
+        assert!(claude_args(&invalid).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_claude_command_quoting() {
+        let options = LaunchOptions {
+            prompt: Some("what's $HOME?".to_string()),
+            extra_args: vec!["--add-dir".to_string(), "/tmp/a b".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            claude_command(&options).unwrap(),
+            r#"claude --add-dir '/tmp/a b' 'what'\''s $HOME?'"#
+        );
+        assert_eq!(shell_escape("/it's here"), r#"'/it'\''s here'"#);
+    }
+
+    #[test]
+    fn test_cmd_escape() {
+        assert_eq!(cmd_escape("fix it"), r#"^"fix it^""#);
+        assert_eq!(cmd_escape(""), r#"^"^""#);
+        // Operators and variables stay literal
+        assert_eq!(
+            cmd_escape("a & b | c > d < e ^ (f) %PATH% !x!"),
+            r#"^"a ^& b ^| c ^> d ^< e ^^ ^(f^) ^%PATH^% ^!x^!^""#
+        );
+        // Quotes are escaped for the program, then for cmd
+        assert_eq!(cmd_escape(r#"say "hi""#), r#"^"say \^"hi\^"^""#);
+        // Backslashes double only before a quote
+        assert_eq!(cmd_escape(r"C:\a b\"), r#"^"C:\a b\\^""#);
+        assert_eq!(cmd_escape(r#"a\"b"#), r#"^"a\\\^"b^""#);
+        // `;` doesn't split the wt command line
+        assert_eq!(cmd_escape("a; b"), r#"^"a\; b^""#);
+    }
 }
@@ -5,13 +5,16 @@
 
 use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebouncedEventKind};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 
+use crate::claude_code::get_telemetry_dir;
+use crate::policy::{is_span_file, telemetry_locations, TelemetryStore};
 use crate::session_index::{
-    build_session_index, update_index_incremental, IndexStatus, SessionIndex, UpdateResult,
+    build_session_index, update_index_incremental, IndexCache, IndexStatus, SessionIndex,
+    UpdateResult,
 };
 
 /// Event payload sent to the frontend when a session file changes.
@@ -42,10 +45,13 @@ pub struct IndexReadyPayload {
 /// Global state for managing file watchers and session indices.
 pub struct WatcherState {
     /// Map of "project_path:session_id" -> watcher handle (for cleanup)
-    watchers: Mutex<HashMap<String, WatcherHandle>>,
+    /// Wrapped in Arc so indexing threads can check the session is still watched
+    watchers: Arc<Mutex<HashMap<String, WatcherHandle>>>,
     /// Map of "project_path:session_id" -> session index (for fast lookups)
     /// Wrapped in Arc so it can be shared with background indexing threads
-    indices: Arc<Mutex<HashMap<String, SessionIndex>>>,
+    indices: Arc<Mutex<IndexCache>>,
+    /// Per-project telemetry indices (invalidated by telemetry watchers)
+    telemetry: Arc<TelemetryStore>,
 }
 
 struct WatcherHandle {
@@ -56,27 +62,43 @@ struct WatcherHandle {
 impl WatcherState {
     pub fn new() -> Self {
         Self {
-            watchers: Mutex::new(HashMap::new()),
-            indices: Arc::new(Mutex::new(HashMap::new())),
+            watchers: Arc::new(Mutex::new(HashMap::new())),
+            indices: Arc::new(Mutex::new(IndexCache::new())),
+            telemetry: Arc::new(TelemetryStore::new()),
         }
     }
 
+    /// Get a clone of the watchers Arc for sharing with background threads.
+    fn watchers_arc(&self) -> Arc<Mutex<HashMap<String, WatcherHandle>>> {
+        Arc::clone(&self.watchers)
+    }
+
     /// Get a clone of the indices Arc for sharing with background threads.
-    fn indices_arc(&self) -> Arc<Mutex<HashMap<String, SessionIndex>>> {
+    fn indices_arc(&self) -> Arc<Mutex<IndexCache>> {
         Arc::clone(&self.indices)
     }
 
-    /// Get the index for a session, if it exists.
-    pub fn get_index(&self, project_path: &str, session_id: &str) -> Option<SessionIndex> {
+    /// Get a clone of the telemetry Arc for sharing with blocking tasks.
+    pub fn telemetry_arc(&self) -> Arc<TelemetryStore> {
+        Arc::clone(&self.telemetry)
+    }
+
+    /// Get the telemetry indices for all projects.
+    pub fn telemetry(&self) -> &TelemetryStore {
+        &self.telemetry
+    }
+
+    /// Get a shared snapshot of the index for a session, if it exists.
+    pub fn get_index(&self, project_path: &str, session_id: &str) -> Option<Arc<SessionIndex>> {
         let key = format!("{}:{}", project_path, session_id);
-        let indices = self.indices.lock().ok()?;
-        indices.get(&key).cloned()
+        let mut indices = self.indices.lock().ok()?;
+        indices.get(&key)
     }
 
     /// Get the index status for a session.
     pub fn get_index_status(&self, project_path: &str, session_id: &str) -> IndexStatus {
         let key = format!("{}:{}", project_path, session_id);
-        let indices = match self.indices.lock() {
+        let mut indices = match self.indices.lock() {
             Ok(i) => i,
             Err(_) => return IndexStatus::error("Failed to lock indices"),
         };
@@ -106,7 +128,8 @@ fn get_session_file_path(project_path: &str, session_id: &str) -> Option<PathBuf
 }
 
 /// Start watching a session file for changes.
-/// Spawns a background thread to build the session index, emitting "index-ready" when done.
+/// Spawns a background thread to build the session index (or catch up a cached
+/// one from an earlier visit), emitting "index-ready" when done.
 pub fn watch_session(
     app_handle: AppHandle,
     state: &WatcherState,
@@ -141,7 +164,9 @@ pub fn watch_session(
             if let Ok(events) = result {
                 for event in events {
                     if event.kind == DebouncedEventKind::Any {
-                        // Update the index incrementally
+                        // Update the index incrementally. Appends are small, so
+                        // this runs in place under the lock; the index is only
+                        // copied while a reader still holds a snapshot
                         if let Ok(mut indices) = watcher_indices.lock() {
                             if let Some(index) = indices.get_mut(&watcher_key) {
                                 match update_index_incremental(
@@ -205,6 +230,7 @@ pub fn watch_session(
     }
 
     // Clone data for the background indexing thread
+    let watchers = state.watchers_arc();
     let indices = state.indices_arc();
     let index_app_handle = app_handle;
     let index_project_path = project_path;
@@ -212,8 +238,35 @@ pub fn watch_session(
     let index_session_file = session_file;
     let index_key = key;
 
+    // Reuse the cached index if this session was opened before
+    let cached = {
+        let mut indices = indices.lock().map_err(|e| e.to_string())?;
+        indices.set_watched(&index_key, true)
+    };
+
     // Spawn background thread to build the index
     std::thread::spawn(move || {
+        if cached {
+            let status = catch_up_cached_index(
+                &indices,
+                &index_key,
+                &index_session_file,
+                &index_project_path,
+            );
+            // An index evicted while catching up is built afresh below
+            if let Some(status) = status {
+                let _ = index_app_handle.emit(
+                    "index-ready",
+                    IndexReadyPayload {
+                        project_path: index_project_path,
+                        session_id: index_session_id,
+                        status,
+                    },
+                );
+                return;
+            }
+        }
+
         let status = match build_session_index(&index_session_file, &index_project_path) {
             Ok(index) => {
                 // Log index stats for verification
@@ -227,9 +280,15 @@ pub fn watch_session(
 
                 let status = index.to_status();
 
-                // Store the index
-                if let Ok(mut indices) = indices.lock() {
-                    indices.insert(index_key, index);
+                // Store the index. The session may have been unwatched while
+                // it was building; then it goes in unwatched so the LRU can
+                // evict it. Holding the watchers lock while inserting keeps an
+                // unwatch from slipping in between the check and the insert.
+                if let Ok(watchers) = watchers.lock() {
+                    let watched = watchers.contains_key(&index_key);
+                    if let Ok(mut indices) = indices.lock() {
+                        indices.insert(index_key, index, watched);
+                    }
                 }
 
                 status
@@ -254,7 +313,46 @@ pub fn watch_session(
     Ok(())
 }
 
-/// Stop watching a session file and clean up its index.
+/// Bring a cached index up to date with the session file.
+///
+/// The update runs on a copy outside the cache lock so other sessions aren't
+/// blocked by a large catch-up or rebuild; the copy is swapped in only if the
+/// cached index didn't change meanwhile (a file watcher update already
+/// caught it up otherwise).
+///
+/// Returns None if the index was evicted, so the caller can rebuild it.
+fn catch_up_cached_index(
+    indices: &Mutex<IndexCache>,
+    key: &str,
+    session_file: &Path,
+    project_path: &str,
+) -> Option<IndexStatus> {
+    let snapshot = match indices.lock() {
+        Ok(mut indices) => indices.get(key)?,
+        Err(_) => return Some(IndexStatus::error("Failed to lock indices")),
+    };
+
+    let mut index = (*snapshot).clone();
+    if let Err(err) = update_index_incremental(&mut index, session_file, project_path) {
+        eprintln!("[session_index] Failed to update cached index: {}", err);
+        return Some(IndexStatus::error(err));
+    }
+
+    let mut indices = match indices.lock() {
+        Ok(i) => i,
+        Err(_) => return Some(IndexStatus::error("Failed to lock indices")),
+    };
+    let status = index.to_status();
+    if indices.replace_snapshot(key, &snapshot, index) {
+        return Some(status);
+    }
+    indices.get(key).map(|current| current.to_status())
+}
+
+/// Stop watching a session file.
+///
+/// The index stays cached (subject to LRU eviction) so reopening the session
+/// only needs an incremental update.
 pub fn unwatch_session(
     state: &WatcherState,
     project_path: &str,
@@ -268,10 +366,10 @@ pub fn unwatch_session(
         watchers.remove(&key);
     }
 
-    // Remove the index
+    // Keep the index around for a quick reopen
     {
         let mut indices = state.indices.lock().map_err(|e| e.to_string())?;
-        indices.remove(&key);
+        indices.set_watched(&key, false);
     }
 
     Ok(())
@@ -381,14 +479,7 @@ pub struct TelemetryChangedPayload {
     pub project_path: String,
 }
 
-/// Get the telemetry directory path for a project.
-fn get_telemetry_dir_path(project_path: &str) -> PathBuf {
-    PathBuf::from(project_path)
-        .join(".cupcake")
-        .join("telemetry")
-}
-
-/// Start watching a project's telemetry directory for changes.
+/// Start watching a project's telemetry locations for changes.
 pub fn watch_telemetry(
     app_handle: AppHandle,
     state: &WatcherState,
@@ -404,30 +495,54 @@ pub fn watch_telemetry(
         }
     }
 
-    let telemetry_dir = get_telemetry_dir_path(&project_path);
+    let locations = telemetry_locations(&project_path);
 
-    // Create the directory if it doesn't exist (so we can watch it)
+    // Create the project directory if it doesn't exist (so we can watch it)
+    let telemetry_dir = get_telemetry_dir(&project_path);
     if !telemetry_dir.exists() {
         std::fs::create_dir_all(&telemetry_dir)
             .map_err(|e| format!("Failed to create telemetry dir: {}", e))?;
     }
 
+    // Missing locations are watched through their nearest existing
+    // ancestor, so the watcher can restart once they appear
+    let missing: Vec<(PathBuf, Option<PathBuf>)> = locations
+        .iter()
+        .filter(|l| !l.path.exists())
+        .map(|l| (l.path.clone(), nearest_existing_ancestor(&l.path)))
+        .collect();
+
     let project_path_clone = project_path.clone();
+    let telemetry = Arc::clone(&state.telemetry);
+    let watched_missing = missing.clone();
 
     // Create debounced watcher with 300ms debounce
     let mut debouncer = new_debouncer(
         Duration::from_millis(300),
         move |result: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
             if let Ok(events) = result {
+                // A missing location (or a directory on its way) was created
+                let appeared = watched_missing.iter().any(|(path, ancestor)| {
+                    path.exists() || nearest_existing_ancestor(path) != *ancestor
+                });
+                if appeared {
+                    telemetry.invalidate(&project_path_clone);
+                    let _ = app_handle.emit(
+                        "telemetry-changed",
+                        TelemetryChangedPayload {
+                            project_path: project_path_clone.clone(),
+                        },
+                    );
+                    restart_telemetry_watcher(&app_handle, &project_path_clone);
+                    return;
+                }
+
                 for event in events {
                     if event.kind == DebouncedEventKind::Any {
-                        // Only emit for JSON files
-                        if event
-                            .path
-                            .extension()
-                            .map(|e| e == "json")
-                            .unwrap_or(false)
-                        {
+                        // Only emit for span files
+                        if is_span_file(&event.path) {
+                            // Catch in-place rewrites the directory mtime misses
+                            telemetry.invalidate(&project_path_clone);
                             let _ = app_handle.emit(
                                 "telemetry-changed",
                                 TelemetryChangedPayload {
@@ -443,11 +558,23 @@ pub fn watch_telemetry(
     )
     .map_err(|e| format!("Failed to create watcher: {}", e))?;
 
-    // Watch the telemetry directory
-    debouncer
-        .watcher()
-        .watch(&telemetry_dir, RecursiveMode::NonRecursive)
-        .map_err(|e| format!("Failed to watch telemetry dir: {}", e))?;
+    // Watch every telemetry location that exists
+    for location in locations.iter().filter(|l| l.path.exists()) {
+        debouncer
+            .watcher()
+            .watch(&location.path, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch telemetry dir: {}", e))?;
+    }
+    let mut ancestors: Vec<&PathBuf> = missing.iter().filter_map(|(_, a)| a.as_ref()).collect();
+    ancestors.sort();
+    ancestors.dedup();
+    for ancestor in ancestors {
+        // Best effort: an unwatchable ancestor only delays picking the
+        // location up until watching restarts
+        let _ = debouncer
+            .watcher()
+            .watch(ancestor, RecursiveMode::NonRecursive);
+    }
 
     // Store the watcher handle
     {
@@ -463,7 +590,34 @@ pub fn watch_telemetry(
     Ok(())
 }
 
-/// Stop watching a project's telemetry directory.
+/// Restart a project's telemetry watcher so it watches locations created
+/// since it started. Does nothing if the project is no longer watched.
+fn restart_telemetry_watcher(app_handle: &AppHandle, project_path: &str) {
+    let state = app_handle.state::<WatcherState>();
+    let key = format!("{}:telemetry", project_path);
+    let previous = match state.watchers.lock() {
+        Ok(mut watchers) => watchers.remove(&key),
+        Err(_) => return,
+    };
+    if previous.is_none() {
+        return;
+    }
+    // Dropping the old watcher only signals its thread (this one) to stop
+    drop(previous);
+    if let Err(e) = watch_telemetry(app_handle.clone(), &state, project_path.to_string()) {
+        eprintln!("[telemetry] Failed to restart watcher: {}", e);
+    }
+}
+
+/// The closest ancestor of a path that exists.
+fn nearest_existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|a| a.exists())
+        .map(Path::to_path_buf)
+}
+
+/// Stop watching a project's telemetry locations.
 pub fn unwatch_telemetry(state: &WatcherState, project_path: &str) -> Result<(), String> {
     let key = format!("{}:telemetry", project_path);
 
@@ -0,0 +1,609 @@
+/**
+ * A minimal VT100/xterm screen buffer for embedded terminals.
+ *
+ * Covers what Claude Code's terminal UI uses: cursor movement, erasing,
+ * scroll regions, insert/delete, SGR colors (16, 256 and true color), the
+ * alternate screen and bracketed paste. Every character is one cell wide.
+ */
+
+/** Text attributes of a cell. Shared between cells, never mutated. */
+export interface CellStyle {
+  fg: string | null;
+  bg: string | null;
+  bold: boolean;
+  dim: boolean;
+  italic: boolean;
+  underline: boolean;
+  inverse: boolean;
+}
+
+interface Cell {
+  ch: string;
+  style: CellStyle;
+}
+
+/** A run of cells with the same style, for rendering. */
+export interface StyledRun {
+  text: string;
+  style: CellStyle;
+}
+
+const DEFAULT_STYLE: CellStyle = {
+  fg: null,
+  bg: null,
+  bold: false,
+  dim: false,
+  italic: false,
+  underline: false,
+  inverse: false,
+};
+
+/** Lines kept above the screen once they scroll off. */
+const MAX_SCROLLBACK = 2000;
+
+const TAB_WIDTH = 8;
+
+/** xterm's default 16-color palette. */
+const ANSI_COLORS = [
+  "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+  "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+/** Color `n` of the 256-color palette. */
+function paletteColor(n: number): string | null {
+  if (n < 0 || n > 255) return null;
+  if (n < 16) return ANSI_COLORS[n];
+  if (n < 232) {
+    const levels = [0, 95, 135, 175, 215, 255];
+    const i = n - 16;
+    return rgb(levels[Math.floor(i / 36)], levels[Math.floor(i / 6) % 6], levels[i % 6]);
+  }
+  const gray = 8 + (n - 232) * 10;
+  return rgb(gray, gray, gray);
+}
+
+function rgb(r: number, g: number, b: number): string {
+  return `rgb(${r}, ${g}, ${b})`;
+}
+
+function blankLine(cols: number, style: CellStyle = DEFAULT_STYLE): Cell[] {
+  return Array.from({ length: cols }, () => ({ ch: " ", style }));
+}
+
+type ParseState = "ground" | "escape" | "csi" | "osc" | "oscEscape" | "charset";
+
+export class TerminalScreen {
+  cols: number;
+  rows: number;
+  /** Whether the program asked for the cursor to be shown */
+  cursorVisible = true;
+  /** Arrow keys send `ESC O x` instead of `ESC [ x` */
+  applicationCursorKeys = false;
+  /** Pasted text should be wrapped in `ESC [200~` ... `ESC [201~` */
+  bracketedPaste = false;
+
+  private lines: Cell[][];
+  private scrollback: Cell[][] = [];
+  /** Main screen while the alternate screen is shown */
+  private savedMain: { lines: Cell[][]; x: number; y: number } | null = null;
+  private x = 0;
+  private y = 0;
+  /** The last write filled the last column; the next character wraps */
+  private wrapPending = false;
+  private saved = { x: 0, y: 0, style: DEFAULT_STYLE };
+  private style: CellStyle = DEFAULT_STYLE;
+  private scrollTop = 0;
+  private scrollBottom: number;
+
+  private state: ParseState = "ground";
+  private params = "";
+  private respond: (data: string) => void;
+
+  /** `respond` receives replies to status queries (e.g. cursor position). */
+  constructor(cols: number, rows: number, respond: (data: string) => void = () => {}) {
+    this.cols = Math.max(1, cols);
+    this.rows = Math.max(1, rows);
+    this.scrollBottom = this.rows - 1;
+    this.lines = Array.from({ length: this.rows }, () => blankLine(this.cols));
+    this.respond = respond;
+  }
+
+  /** Cursor position on the screen (row, column). */
+  get cursor(): { row: number; col: number } {
+    return { row: this.y, col: Math.min(this.x, this.cols - 1) };
+  }
+
+  /** Lines that scrolled off the top, oldest first. */
+  get scrollbackLength(): number {
+    return this.scrollback.length;
+  }
+
+  /** Feed output from the program. */
+  write(data: string) {
+    for (const ch of data) {
+      this.feed(ch);
+    }
+  }
+
+  /** Scrollback followed by the screen, as styled runs per line. */
+  render(): StyledRun[][] {
+    return [...this.scrollback, ...this.lines].map(toRuns);
+  }
+
+  resize(cols: number, rows: number) {
+    cols = Math.max(1, cols);
+    rows = Math.max(1, rows);
+    if (cols === this.cols && rows === this.rows) return;
+
+    const fit = (line: Cell[]) =>
+      line.length >= cols
+        ? line.slice(0, cols)
+        : [...line, ...blankLine(cols - line.length)];
+    this.lines = this.lines.map(fit);
+    // Drop lines below the cursor first, then move top lines into scrollback
+    while (this.lines.length > rows) {
+      if (this.y < rows) {
+        this.lines.pop();
+        continue;
+      }
+      const line = this.lines.shift()!;
+      if (!this.savedMain) this.pushScrollback(line);
+      this.y--;
+    }
+    while (this.lines.length < rows) {
+      this.lines.push(blankLine(cols));
+    }
+    if (this.savedMain) {
+      this.savedMain.lines = this.savedMain.lines.slice(0, rows).map(fit);
+      while (this.savedMain.lines.length < rows) {
+        this.savedMain.lines.push(blankLine(cols));
+      }
+    }
+
+    this.cols = cols;
+    this.rows = rows;
+    this.scrollTop = 0;
+    this.scrollBottom = rows - 1;
+    this.x = Math.min(this.x, cols - 1);
+    this.y = Math.min(this.y, rows - 1);
+    this.wrapPending = false;
+  }
+
+  private feed(ch: string) {
+    switch (this.state) {
+      case "ground":
+        this.ground(ch);
+        return;
+      case "escape":
+        this.escape(ch);
+        return;
+      case "csi":
+        if (ch >= "@" && ch <= "~") {
+          this.state = "ground";
+          this.csi(this.params, ch);
+        } else {
+          this.params += ch;
+        }
+        return;
+      case "osc":
+        // Titles and hyperlinks; ended by BEL or ST (ESC \)
+        if (ch === "\x07") this.state = "ground";
+        else if (ch === "\x1b") this.state = "oscEscape";
+        return;
+      case "oscEscape":
+        this.state = ch === "\\" ? "ground" : "osc";
+        return;
+      case "charset":
+        // The character set designated by ESC ( x is ignored
+        this.state = "ground";
+        return;
+    }
+  }
+
+  private ground(ch: string) {
+    switch (ch) {
+      case "\x1b":
+        this.state = "escape";
+        return;
+      case "\r":
+        this.x = 0;
+        this.wrapPending = false;
+        return;
+      case "\n":
+      case "\x0b":
+      case "\x0c":
+        this.lineFeed();
+        return;
+      case "\b":
+        this.x = Math.max(0, Math.min(this.x, this.cols - 1) - 1);
+        this.wrapPending = false;
+        return;
+      case "\t":
+        this.x = Math.min(this.cols - 1, (Math.floor(this.x / TAB_WIDTH) + 1) * TAB_WIDTH);
+        return;
+    }
+    if (ch < " " || ch === "\x7f") return;
+    this.print(ch);
+  }
+
+  private escape(ch: string) {
+    this.state = "ground";
+    switch (ch) {
+      case "[":
+        this.state = "csi";
+        this.params = "";
+        return;
+      case "]":
+        this.state = "osc";
+        return;
+      case "(":
+      case ")":
+      case "*":
+      case "+":
+        this.state = "charset";
+        return;
+      case "7":
+        this.saveCursor();
+        return;
+      case "8":
+        this.restoreCursor();
+        return;
+      case "D":
+        this.lineFeed();
+        return;
+      case "E":
+        this.x = 0;
+        this.lineFeed();
+        return;
+      case "M":
+        this.reverseIndex();
+        return;
+      case "c":
+        this.reset();
+        return;
+    }
+  }
+
+  private csi(raw: string, final: string) {
+    const isPrivate = raw.startsWith("?");
+    const body = raw.replace(/^[?>=]/, "");
+    const params = body.split(";").map((p) => (p === "" ? NaN : parseInt(p, 10)));
+    // Parameter `i`, with 0 or missing meaning `fallback`
+    const n = (i: number, fallback = 1) => (params[i] > 0 ? params[i] : fallback);
+
+    if (isPrivate) {
+      if (final === "h" || final === "l") this.setPrivateModes(params, final === "h");
+      return;
+    }
+    if (raw.startsWith(">") || raw.startsWith("=")) return;
+
+    switch (final) {
+      case "A":
+        this.moveTo(this.x, Math.max(this.y - n(0), this.cursorTopLimit()));
+        return;
+      case "B":
+        this.moveTo(this.x, Math.min(this.y + n(0), this.cursorBottomLimit()));
+        return;
+      case "C":
+        this.moveTo(this.x + n(0), this.y);
+        return;
+      case "D":
+        this.moveTo(Math.min(this.x, this.cols - 1) - n(0), this.y);
+        return;
+      case "E":
+        this.moveTo(0, Math.min(this.y + n(0), this.cursorBottomLimit()));
+        return;
+      case "F":
+        this.moveTo(0, Math.max(this.y - n(0), this.cursorTopLimit()));
+        return;
+      case "G":
+      case "`":
+        this.moveTo(n(0) - 1, this.y);
+        return;
+      case "d":
+        this.moveTo(this.x, n(0) - 1);
+        return;
+      case "H":
+      case "f":
+        this.moveTo(n(1) - 1, n(0) - 1);
+        return;
+      case "J":
+        this.eraseDisplay(n(0, 0));
+        return;
+      case "K":
+        this.eraseLine(n(0, 0));
+        return;
+      case "L":
+        this.insertLines(n(0));
+        return;
+      case "M":
+        this.deleteLines(n(0));
+        return;
+      case "@":
+        this.insertChars(n(0));
+        return;
+      case "P":
+        this.deleteChars(n(0));
+        return;
+      case "X":
+        this.eraseChars(n(0));
+        return;
+      case "S":
+        for (let i = 0; i < n(0); i++) this.scrollUp();
+        return;
+      case "T":
+        for (let i = 0; i < n(0); i++) this.scrollDown();
+        return;
+      case "m":
+        this.sgr(body === "" ? [0] : params.map((p) => (Number.isNaN(p) ? 0 : p)));
+        return;
+      case "r":
+        this.setScrollRegion(n(0) - 1, n(1, this.rows) - 1);
+        return;
+      case "s":
+        this.saveCursor();
+        return;
+      case "u":
+        this.restoreCursor();
+        return;
+      case "n":
+        if (n(0, 0) === 6) this.respond(`\x1b[${this.y + 1};${Math.min(this.x, this.cols - 1) + 1}R`);
+        return;
+    }
+  }
+
+  private setPrivateModes(modes: number[], on: boolean) {
+    for (const mode of modes) {
+      switch (mode) {
+        case 1:
+          this.applicationCursorKeys = on;
+          break;
+        case 25:
+          this.cursorVisible = on;
+          break;
+        case 47:
+        case 1047:
+        case 1049:
+          this.setAlternateScreen(on);
+          break;
+        case 2004:
+          this.bracketedPaste = on;
+          break;
+      }
+    }
+  }
+
+  private setAlternateScreen(on: boolean) {
+    if (on && !this.savedMain) {
+      this.savedMain = { lines: this.lines, x: this.x, y: this.y };
+      this.lines = Array.from({ length: this.rows }, () => blankLine(this.cols));
+      this.moveTo(0, 0);
+    } else if (!on && this.savedMain) {
+      this.lines = this.savedMain.lines;
+      this.moveTo(this.savedMain.x, this.savedMain.y);
+      this.savedMain = null;
+    }
+  }
+
+  private sgr(params: number[]) {
+    let style = { ...this.style };
+    for (let i = 0; i < params.length; i++) {
+      const p = params[i];
+      if (p === 0) style = { ...DEFAULT_STYLE };
+      else if (p === 1) style.bold = true;
+      else if (p === 2) style.dim = true;
+      else if (p === 3) style.italic = true;
+      else if (p === 4) style.underline = true;
+      else if (p === 7) style.inverse = true;
+      else if (p === 22) style.bold = style.dim = false;
+      else if (p === 23) style.italic = false;
+      else if (p === 24) style.underline = false;
+      else if (p === 27) style.inverse = false;
+      else if (p >= 30 && p <= 37) style.fg = ANSI_COLORS[p - 30];
+      else if (p === 39) style.fg = null;
+      else if (p >= 40 && p <= 47) style.bg = ANSI_COLORS[p - 40];
+      else if (p === 49) style.bg = null;
+      else if (p >= 90 && p <= 97) style.fg = ANSI_COLORS[p - 90 + 8];
+      else if (p >= 100 && p <= 107) style.bg = ANSI_COLORS[p - 100 + 8];
+      else if (p === 38 || p === 48) {
+        let color: string | null = null;
+        if (params[i + 1] === 5) {
+          color = paletteColor(params[i + 2]);
+          i += 2;
+        } else if (params[i + 1] === 2) {
+          color = rgb(params[i + 2] ?? 0, params[i + 3] ?? 0, params[i + 4] ?? 0);
+          i += 4;
+        }
+        if (p === 38) style.fg = color;
+        else style.bg = color;
+      }
+    }
+    this.style = style;
+  }
+
+  private print(ch: string) {
+    if (this.wrapPending) {
+      this.x = 0;
+      this.lineFeed();
+    }
+    this.lines[this.y][this.x] = { ch, style: this.style };
+    if (this.x === this.cols - 1) {
+      this.wrapPending = true;
+    } else {
+      this.x++;
+    }
+  }
+
+  private moveTo(x: number, y: number) {
+    this.x = Math.max(0, Math.min(x, this.cols - 1));
+    this.y = Math.max(0, Math.min(y, this.rows - 1));
+    this.wrapPending = false;
+  }
+
+  /** Rows the cursor can move up to without leaving the scroll region. */
+  private cursorTopLimit(): number {
+    return this.y >= this.scrollTop ? this.scrollTop : 0;
+  }
+
+  private cursorBottomLimit(): number {
+    return this.y <= this.scrollBottom ? this.scrollBottom : this.rows - 1;
+  }
+
+  private lineFeed() {
+    this.wrapPending = false;
+    if (this.y === this.scrollBottom) {
+      this.scrollUp();
+    } else if (this.y < this.rows - 1) {
+      this.y++;
+    }
+  }
+
+  private reverseIndex() {
+    this.wrapPending = false;
+    if (this.y === this.scrollTop) {
+      this.scrollDown();
+    } else if (this.y > 0) {
+      this.y--;
+    }
+  }
+
+  /** Scroll the scroll region up one line. */
+  private scrollUp() {
+    const [removed] = this.lines.splice(this.scrollTop, 1);
+    this.lines.splice(this.scrollBottom, 0, blankLine(this.cols, this.blankStyle()));
+    // Only full-screen scrolling on the main screen feeds the scrollback
+    if (this.scrollTop === 0 && !this.savedMain) {
+      this.pushScrollback(removed);
+    }
+  }
+
+  private scrollDown() {
+    this.lines.splice(this.scrollBottom, 1);
+    this.lines.splice(this.scrollTop, 0, blankLine(this.cols, this.blankStyle()));
+  }
+
+  private pushScrollback(line: Cell[]) {
+    this.scrollback.push(line);
+    if (this.scrollback.length > MAX_SCROLLBACK) {
+      this.scrollback.shift();
+    }
+  }
+
+  private setScrollRegion(top: number, bottom: number) {
+    if (top < 0 || bottom >= this.rows || top >= bottom) {
+      top = 0;
+      bottom = this.rows - 1;
+    }
+    this.scrollTop = top;
+    this.scrollBottom = bottom;
+    this.moveTo(0, 0);
+  }
+
+  /** Erased cells keep the current background color. */
+  private blankStyle(): CellStyle {
+    return this.style.bg ? { ...DEFAULT_STYLE, bg: this.style.bg } : DEFAULT_STYLE;
+  }
+
+  private eraseDisplay(mode: number) {
+    const blank = this.blankStyle();
+    if (mode === 0) {
+      this.eraseLine(0);
+      for (let y = this.y + 1; y < this.rows; y++) this.lines[y] = blankLine(this.cols, blank);
+    } else if (mode === 1) {
+      this.eraseLine(1);
+      for (let y = 0; y < this.y; y++) this.lines[y] = blankLine(this.cols, blank);
+    } else if (mode === 2) {
+      this.lines = this.lines.map(() => blankLine(this.cols, blank));
+    } else if (mode === 3) {
+      this.scrollback = [];
+    }
+  }
+
+  private eraseLine(mode: number) {
+    const line = this.lines[this.y];
+    const x = Math.min(this.x, this.cols - 1);
+    const [from, to] = mode === 0 ? [x, this.cols] : mode === 1 ? [0, x + 1] : [0, this.cols];
+    const blank = this.blankStyle();
+    for (let i = from; i < to; i++) line[i] = { ch: " ", style: blank };
+  }
+
+  private eraseChars(count: number) {
+    const line = this.lines[this.y];
+    const blank = this.blankStyle();
+    const x = Math.min(this.x, this.cols - 1);
+    for (let i = x; i < Math.min(this.cols, x + count); i++) line[i] = { ch: " ", style: blank };
+  }
+
+  private insertChars(count: number) {
+    const line = this.lines[this.y];
+    const x = Math.min(this.x, this.cols - 1);
+    line.splice(x, 0, ...blankLine(Math.min(count, this.cols - x), this.blankStyle()));
+    line.length = this.cols;
+  }
+
+  private deleteChars(count: number) {
+    const line = this.lines[this.y];
+    const x = Math.min(this.x, this.cols - 1);
+    line.splice(x, Math.min(count, this.cols - x));
+    while (line.length < this.cols) line.push({ ch: " ", style: this.blankStyle() });
+  }
+
+  private insertLines(count: number) {
+    if (this.y < this.scrollTop || this.y > this.scrollBottom) return;
+    count = Math.min(count, this.scrollBottom - this.y + 1);
+    this.lines.splice(this.scrollBottom + 1 - count, count);
+    this.lines.splice(this.y, 0, ...Array.from({ length: count }, () => blankLine(this.cols, this.blankStyle())));
+    this.x = 0;
+    this.wrapPending = false;
+  }
+
+  private deleteLines(count: number) {
+    if (this.y < this.scrollTop || this.y > this.scrollBottom) return;
+    count = Math.min(count, this.scrollBottom - this.y + 1);
+    this.lines.splice(this.y, count);
+    this.lines.splice(this.scrollBottom + 1 - count, 0, ...Array.from({ length: count }, () => blankLine(this.cols, this.blankStyle())));
+    this.x = 0;
+    this.wrapPending = false;
+  }
+
+  private saveCursor() {
+    this.saved = { x: this.x, y: this.y, style: this.style };
+  }
+
+  private restoreCursor() {
+    this.moveTo(this.saved.x, this.saved.y);
+    this.style = this.saved.style;
+  }
+
+  private reset() {
+    this.lines = Array.from({ length: this.rows }, () => blankLine(this.cols));
+    this.scrollback = [];
+    this.savedMain = null;
+    this.style = DEFAULT_STYLE;
+    this.scrollTop = 0;
+    this.scrollBottom = this.rows - 1;
+    this.cursorVisible = true;
+    this.applicationCursorKeys = false;
+    this.bracketedPaste = false;
+    this.moveTo(0, 0);
+  }
+}
+
+/** Merge a line's cells into runs of the same style, dropping trailing blanks. */
+function toRuns(line: Cell[]): StyledRun[] {
+  let end = line.length;
+  while (end > 0 && line[end - 1].ch === " " && line[end - 1].style.bg === null && !line[end - 1].style.inverse) {
+    end--;
+  }
+  const runs: StyledRun[] = [];
+  for (let i = 0; i < end; i++) {
+    const cell = line[i];
+    const last = runs[runs.length - 1];
+    if (last && last.style === cell.style) {
+      last.text += cell.ch;
+    } else {
+      runs.push({ text: cell.ch, style: cell.style });
+    }
+  }
+  return runs;
+}
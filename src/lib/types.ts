@@ -43,11 +43,30 @@ export interface Project {
   sessions: Session[];
 }
 
+/** A running Claude Code process (matches Rust ActiveSession) */
+export interface ActiveSession {
+  pid: number;
+  /** Working directory of the process (the project path) */
+  cwd: string;
+  /** Session the process is writing to, when it can be determined */
+  sessionId: string | null;
+  /** Session file the process has open */
+  sessionFile: string | null;
+  /** Process start time (ISO 8601) */
+  startedAt: string | null;
+  /** Average CPU usage over the process lifetime, in percent of one core */
+  cpuPercent: number | null;
+  /** Resident memory */
+  memoryBytes: number | null;
+}
+
 export interface ActiveSessionsResult {
   /** Whether this feature is supported on the current platform */
   supported: boolean;
   /** Set of project paths with active Claude sessions */
   activePaths: string[];
+  /** Every detected Claude process */
+  sessions: ActiveSession[];
 }
 
 export type TerminalType =
@@ -57,7 +76,63 @@ export type TerminalType =
   | "windows-terminal"
   | "gnome-terminal"
   | "konsole"
-  | "alacritty";
+  | "alacritty"
+  | "kitty"
+  | "wezterm"
+  | "xterm"
+  | "foot"
+  | "tilix"
+  | "tmux-window"
+  | "tmux-pane"
+  | "custom";
+
+/** How to start Claude Code (matches Rust LaunchOptions) */
+export interface LaunchOptions {
+  /** Continue the most recent session */
+  continueSession?: boolean;
+  /** Resume a specific session; takes precedence over continueSession */
+  resumeSessionId?: string | null;
+  /** Skip permission prompts */
+  yoloMode?: boolean;
+  /** Initial prompt */
+  prompt?: string | null;
+  /** Model alias or name */
+  model?: string | null;
+  /** Tools allowed without prompting */
+  allowedTools?: string[];
+  /** Extra arguments passed through verbatim */
+  extraArgs?: string[];
+}
+
+/** An embedded terminal (matches Rust PtyInfo) */
+export interface PtyInfo {
+  id: number;
+  pid: number;
+  projectPath: string;
+  /** Session the process is writing to, once known */
+  sessionId: string | null;
+  exited: boolean;
+  exitCode: number | null;
+}
+
+/** "pty-output" event payload */
+export interface PtyOutputPayload {
+  id: number;
+  data: string;
+}
+
+/** "pty-session" event payload */
+export interface PtySessionPayload {
+  id: number;
+  projectPath: string;
+  sessionId: string;
+}
+
+/** "pty-exit" event payload */
+export interface PtyExitPayload {
+  id: number;
+  exitCode: number | null;
+}
 
 export const terminalDisplayNames: Record<TerminalType, string> = {
   "macos-terminal": "Terminal",
@@ -67,6 +142,14 @@ export const terminalDisplayNames: Record<TerminalType, string> = {
   "gnome-terminal": "GNOME Terminal",
   konsole: "Konsole",
   alacritty: "Alacritty",
+  kitty: "kitty",
+  wezterm: "WezTerm",
+  xterm: "xterm",
+  foot: "foot",
+  tilix: "Tilix",
+  "tmux-window": "tmux (new window)",
+  "tmux-pane": "tmux (new pane)",
+  custom: "Custom command",
 };
 
 // File edit types - matches Rust structs in claude_code.rs
@@ -79,6 +162,10 @@ export interface FileEdit {
   editType: FileEditType;
   /** Timestamp of the last edit to this file (ISO 8601) */
   lastEditedAt: string | null;
+  /** Lines added over all edits (a Write counts as all added) */
+  linesAdded: number;
+  /** Lines removed over all edits */
+  linesRemoved: number;
 }
 
 export interface FileDiff {
@@ -175,9 +262,12 @@ export interface SessionEventsResponse {
 // Policy Evaluation / CupcakeSpan Types
 // =============================================================================
 
+/** Where a telemetry span was read from (matches Rust TelemetrySource) */
+export type TelemetrySource = "project" | "global" | "configured";
+
 /** Summary of a policy evaluation for list display */
 export interface PolicyEvaluation {
-  /** Filename of the telemetry file */
+  /** Filename of the telemetry file ("<file>#<line>" for spans in a JSONL stream) */
   filename: string;
   /** Timestamp (ISO 8601) */
   timestamp: string;
@@ -191,6 +281,220 @@ export interface PolicyEvaluation {
   durationMs: number;
   /** Trace ID */
   traceId: string;
+  /** Claude Code session that triggered the evaluation */
+  sessionId: string | null;
+  /** tool_use id of the tool call being evaluated */
+  toolUseId: string | null;
+  /** Reason attached to the final decision (e.g., why a call was blocked) */
+  reason: string | null;
+  /** Kind of telemetry location the span was read from */
+  source: TelemetrySource;
+  /** Path of the file holding the span */
+  path: string;
+  /** Line of the span within a JSONL stream (1-based; null for .json files) */
+  line: number | null;
+}
+
+/** Sort keys for query_policy_evaluations */
+export type EvaluationSortKey = "timestamp" | "durationMs" | "toolName" | "decision";
+
+/** Paging, filters and sort for query_policy_evaluations (matches Rust EvaluationQuery) */
+export interface EvaluationQuery {
+  /** Page start (number of evaluations to skip) */
+  offset?: number;
+  /** Page size (defaults to 200) */
+  limit?: number;
+  /** Only include this final decision (e.g., "Deny") */
+  decision?: string;
+  /** Only include this tool (e.g., "Bash") */
+  toolName?: string;
+  /** Only include this hook event type (e.g., "PreToolUse") */
+  eventType?: string;
+  /** Only include evaluations from this Claude Code session */
+  sessionId?: string;
+  /** Only include evaluations at or after this time (RFC 3339 or YYYY-MM-DD) */
+  since?: string;
+  /** Only include evaluations at or before this time (RFC 3339 or YYYY-MM-DD) */
+  until?: string;
+  /** Sort key (defaults to timestamp) */
+  sortBy?: EvaluationSortKey;
+  /** Sort ascending instead of descending (newest first) */
+  ascending?: boolean;
+}
+
+/** A page of policy evaluations (matches Rust EvaluationPage) */
+export interface EvaluationPage {
+  evaluations: PolicyEvaluation[];
+  /** Number of evaluations matching the filters */
+  totalCount: number;
+  /** Offset of this page */
+  offset: number;
+  /** Whether there are more evaluations after this page */
+  hasMore: boolean;
+}
+
+/** A policy evaluation linked to the session events it applies to (matches Rust PolicyEventLink) */
+export interface PolicyEventLink {
+  evaluation: PolicyEvaluation;
+  /** Assistant event containing the evaluated tool_use */
+  toolUseEvent: SessionEvent | null;
+  /** User event containing the matching tool_result (null if blocked before running or still running) */
+  toolResultEvent: SessionEvent | null;
+}
+
+/** How well a tool, hook event or command is covered by policies */
+export type CoverageStatus = "neverEvaluated" | "noPolicyMatched" | "covered";
+
+/** Coverage of one tool, hook event type or command pattern (matches Rust CoverageEntry) */
+export interface CoverageEntry {
+  key: string;
+  /** Number of times it occurred in sessions */
+  occurrences: number;
+  /** Occurrences with at least one evaluation */
+  evaluated: number;
+  /** Occurrences where at least one policy matched */
+  matched: number;
+  status: CoverageStatus;
+}
+
+/** Coverage of one session (matches Rust SessionCoverage) */
+export interface SessionCoverage {
+  sessionId: string;
+  toolCalls: number;
+  /** Tool calls with at least one evaluation */
+  evaluatedToolCalls: number;
+  /** Evaluations recorded for the session */
+  evaluations: number;
+  firstTimestamp: string | null;
+  lastTimestamp: string | null;
+  /** The session made tool calls but Cupcake recorded nothing for it */
+  missingTelemetry: boolean;
+}
+
+/** Policy coverage of a project's sessions (matches Rust CoverageReport) */
+export interface CoverageReport {
+  totalToolCalls: number;
+  evaluatedToolCalls: number;
+  matchedToolCalls: number;
+  /** Per tool name, least covered first */
+  tools: CoverageEntry[];
+  /** Per hook event type, least covered first */
+  eventTypes: CoverageEntry[];
+  /** Per Bash command pattern (e.g., "git push"), least covered first */
+  bashCommands: CoverageEntry[];
+  /** Per session, ordered by first activity */
+  sessions: SessionCoverage[];
+  sessionsWithoutTelemetry: number;
+}
+
+/** Where a replayed event came from */
+export type ReplaySource = "telemetry" | "session";
+
+/** Outcome of replaying one event (matches Rust ReplayResult) */
+export interface ReplayResult {
+  source: ReplaySource;
+  /** Telemetry file the event came from */
+  filename: string | null;
+  /** Session line, or line of a JSONL telemetry stream, the event came from */
+  line: number | null;
+  timestamp: string | null;
+  eventType: string | null;
+  toolName: string | null;
+  toolUseId: string | null;
+  /** Decision recorded when the event first happened (null if it was never evaluated) */
+  recordedDecision: string | null;
+  /** Decision from the current policies (null if the replay failed) */
+  replayedDecision: string | null;
+  replayedReason: string | null;
+  /** Whether the current policies decide differently from the recorded decision */
+  changed: boolean;
+  /** Whether the event had no recorded decision and was evaluated for the first time */
+  newlyEvaluated: boolean;
+  error: string | null;
+}
+
+/** Results of replay_policy_evaluations / replay_session_tool_calls (matches Rust ReplayReport) */
+export interface ReplayReport {
+  /** The cupcake binary that was used */
+  cupcakePath: string;
+  total: number;
+  /** Number of events whose decision changed */
+  changed: number;
+  /** Number of events evaluated for the first time */
+  newlyEvaluated: number;
+  /** Number of events that couldn't be replayed */
+  errors: number;
+  results: ReplayResult[];
+}
+
+/** Trend bucket size for policy analytics */
+export type TimeBucket = "hour" | "day" | "week";
+
+/** Filters for get_policy_analytics (matches Rust AnalyticsFilter) */
+export interface AnalyticsFilter {
+  /** Only include evaluations at or after this time (RFC 3339 or YYYY-MM-DD) */
+  since?: string;
+  /** Only include evaluations at or before this time (RFC 3339 or YYYY-MM-DD, a bare date includes the whole day) */
+  until?: string;
+  /** Only include evaluations from this Claude Code session */
+  sessionId?: string;
+  /** Trend bucket size (defaults to day) */
+  bucket?: TimeBucket;
+}
+
+/** Decision counts for one tool, event type or policy */
+export interface DecisionCounts {
+  key: string;
+  total: number;
+  /** Decision → count */
+  decisions: Record<string, number>;
+}
+
+/** Latency distribution in milliseconds */
+export interface LatencyStats {
+  count: number;
+  minMs: number;
+  maxMs: number;
+  meanMs: number;
+  p50Ms: number;
+  p90Ms: number;
+  p95Ms: number;
+  p99Ms: number;
+}
+
+/** Latency of a single evaluation phase */
+export interface PhaseTiming {
+  name: string;
+  latency: LatencyStats;
+}
+
+/** Evaluations within one time bucket */
+export interface TrendBucket {
+  /** Bucket start (RFC 3339, UTC) */
+  start: string;
+  total: number;
+  /** Decision → count */
+  decisions: Record<string, number>;
+}
+
+/** Aggregated statistics over policy evaluations (matches Rust PolicyAnalytics) */
+export interface PolicyAnalytics {
+  /** Number of evaluations that matched the filter */
+  totalEvaluations: number;
+  /** Final decision → count */
+  decisions: Record<string, number>;
+  /** Decision counts per tool name, most evaluated first */
+  byTool: DecisionCounts[];
+  /** Decision counts per hook event type, most evaluated first */
+  byEventType: DecisionCounts[];
+  /** Decision counts per policy (rule) ID, most evaluated first ("NoDecision" when a policy matched without deciding) */
+  byPolicy: DecisionCounts[];
+  /** Latency of whole evaluations */
+  latency: LatencyStats;
+  /** Latency per phase name, sorted by name */
+  phases: PhaseTiming[];
+  /** Evaluation counts per time bucket, oldest first */
+  trend: TrendBucket[];
 }
 
 /** Harness type that generated the event */
@@ -278,6 +582,8 @@ export interface SignalsPhase {
 export interface DecisionResult {
   /** Rule ID that triggered this decision */
   ruleId: string;
+  /** Policy (Rego package) the rule belongs to */
+  policy?: string | null;
   /** Reason for the decision */
   reason: string;
   /** Severity level */
@@ -390,6 +696,14 @@ export interface CupcakeSpan {
   totalDurationMs: number;
 }
 
+/** get_policy_evaluation result (matches Rust LoadedSpan) */
+export interface LoadedSpan {
+  /** Typed span, with Cupcake's snake_case keys */
+  span: unknown;
+  /** The span's JSON as written, including fields the typed span drops */
+  raw: unknown;
+}
+
 // =============================================================================
 // Session Index Types
 // =============================================================================
@@ -418,6 +732,490 @@ export interface EditContext {
   editLine: number;
 }
 
+/** Conversation DAG of a session (matches Rust ConversationGraph in session_index/branches.rs) */
+export interface ConversationGraph {
+  /** UUID of the leaf of the active branch */
+  activeLeaf: string | null;
+  /** Events with more than one child */
+  branchPoints: BranchPoint[];
+  /** One branch per leaf, ordered by leaf line */
+  branches: ConversationBranch[];
+}
+
+/** An event where the conversation forks */
+export interface BranchPoint {
+  uuid: string;
+  line: number;
+  /** Line numbers of the first event of each child branch, ascending */
+  childLines: number[];
+}
+
+/** A root-to-leaf path through the conversation */
+export interface ConversationBranch {
+  leafUuid: string;
+  leafLine: number;
+  /** Line where this branch leaves the active branch (null for the active branch and disconnected roots) */
+  forkLine: number | null;
+  /** First line that belongs only to this branch */
+  firstLine: number;
+  /** Number of events that belong only to this branch */
+  eventCount: number;
+  /** Whether this is the active branch */
+  isActive: boolean;
+  /** Whether this branch was left behind (every branch but the active one) */
+  abandoned: boolean;
+}
+
+export type ToolCallSortKey = "sequence" | "durationMs" | "outputBytes" | "toolName";
+
+/** Paging, filters and sort for get_tool_calls (matches Rust ToolCallQuery in session_index/tool_calls.rs) */
+export interface ToolCallQuery {
+  offset?: number;
+  /** Page size (defaults to 200) */
+  limit?: number;
+  toolName?: string;
+  /** Only include calls whose result was an error */
+  errorsOnly?: boolean;
+  /** Defaults to session order */
+  sortBy?: ToolCallSortKey;
+  descending?: boolean;
+}
+
+/** A tool_use paired with its tool_result (matches Rust ToolCallEntry) */
+export interface ToolCallEntry {
+  toolUseId: string;
+  toolName: string | null;
+  /** Command, file path, pattern... */
+  inputSummary: string;
+  startedAt: string | null;
+  endedAt: string | null;
+  /** Time from tool_use to tool_result (null while pending) */
+  durationMs: number | null;
+  isError: boolean;
+  outputBytes: number;
+  /** Sub-agent launched by the call (Task tool) */
+  agentId: string | null;
+  toolUseLine: number | null;
+  toolResultLine: number | null;
+}
+
+/** Aggregates for one tool (matches Rust ToolStats) */
+export interface ToolStats {
+  toolName: string;
+  calls: number;
+  errors: number;
+  /** Calls without a result yet */
+  pending: number;
+  totalDurationMs: number;
+  avgDurationMs: number | null;
+  maxDurationMs: number | null;
+  totalOutputBytes: number;
+}
+
+/** A page of tool calls (matches Rust ToolCallPage) */
+export interface ToolCallPage {
+  calls: ToolCallEntry[];
+  /** Number of calls matching the filters */
+  totalCount: number;
+  offset: number;
+  hasMore: boolean;
+  /** Aggregates per tool over the whole session, most used first */
+  tools: ToolStats[];
+}
+
+/** A context compaction (matches Rust Compaction) */
+export interface Compaction {
+  /** Line of the compact_boundary entry */
+  line: number;
+  timestamp: string | null;
+  /** "manual" (/compact) or "auto" */
+  trigger: string | null;
+  /** Context size in tokens before compacting */
+  preTokens: number | null;
+}
+
+/** API token usage (matches Rust TokenUsage) */
+export interface TokenUsage {
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+}
+
+/** Summary of a session (matches Rust SessionStats) */
+export interface SessionStats {
+  startedAt: string | null;
+  endedAt: string | null;
+  /** Wall-clock time from the first to the last event */
+  durationMs: number;
+  /** Time between events, excluding gaps longer than idleThresholdMs */
+  activeMs: number;
+  idleThresholdMs: number;
+  humanPrompts: number;
+  assistantTurns: number;
+  toolCalls: number;
+  /** Aggregates per tool, most used first */
+  tools: ToolStats[];
+  /** Sub-agents launched (Task calls) */
+  subAgents: number;
+  compactions: Compaction[];
+  toolErrors: number;
+  apiErrors: number;
+  /** Token usage summed over assistant turns */
+  tokens: TokenUsage;
+  /** Files edited or written */
+  filesTouched: number;
+  linesAdded: number;
+  linesRemoved: number;
+}
+
+/** A session being compared (matches Rust ComparedSession) */
+export interface ComparedSession {
+  projectPath: string;
+  sessionId: string;
+  stats: SessionStats;
+}
+
+/** A human prompt and the tools called in response (matches Rust PromptTurn) */
+export interface PromptTurn {
+  line: number;
+  /** Prompt text, whitespace-normalized */
+  text: string;
+  /** Names of the tools called before the next prompt, in order */
+  toolCalls: string[];
+}
+
+/** Prompts aligned across two sessions (matches Rust PromptPair) */
+export interface PromptPair {
+  left: PromptTurn | null;
+  right: PromptTurn | null;
+  samePrompt: boolean;
+  /** Whether both sides called the same tools in the same order */
+  sameToolCalls: boolean;
+}
+
+/** Calls of one tool on each side (matches Rust ToolCountDiff) */
+export interface ToolCountDiff {
+  toolName: string;
+  left: number;
+  right: number;
+}
+
+/** How a file ended up on each side (matches Rust FileStatus) */
+export type FileStatus = "identical" | "different" | "leftOnly" | "rightOnly" | "unknown";
+
+/** A file edited by either session (matches Rust FileComparison) */
+export interface FileComparison {
+  /** Path relative to its project */
+  path: string;
+  status: FileStatus;
+  leftEdits: number;
+  rightEdits: number;
+  /** Content after the left session's edits (null if it couldn't be replayed) */
+  leftContent: string | null;
+  rightContent: string | null;
+  /** Lines the right side has that the left doesn't */
+  linesAdded: number;
+  /** Lines the left side has that the right doesn't */
+  linesRemoved: number;
+}
+
+/** Structured diff of two sessions (matches Rust SessionComparison) */
+export interface SessionComparison {
+  left: ComparedSession;
+  right: ComparedSession;
+  prompts: PromptPair[];
+  /** Every tool either side called, by name */
+  tools: ToolCountDiff[];
+  /** Every file either side edited, by path */
+  files: FileComparison[];
+}
+
+/** A run of events in a context window (matches Rust ContextSegment) */
+export interface ContextSegment {
+  /** Lines of the events, in conversation order */
+  lines: number[];
+  estimatedTokens: number;
+}
+
+/** A compaction with token counts before and after (matches Rust CompactionBoundary) */
+export interface CompactionBoundary {
+  line: number;
+  timestamp: string | null;
+  /** "manual" (/compact) or "auto" */
+  trigger: string | null;
+  /** Context size before compacting, as reported by Claude Code */
+  preTokens: number | null;
+  estimatedBeforeTokens: number;
+  /** Line of the compact summary message */
+  summaryLine: number | null;
+  estimatedAfterTokens: number;
+}
+
+/** The context window the model had at an event (matches Rust ContextWindow) */
+export interface ContextWindow {
+  line: number;
+  /** Compaction boundary the window starts at (null if not compacted yet) */
+  boundaryLine: number | null;
+  /** The compact summary the window starts with */
+  summary: ContextSegment | null;
+  /** Events since the boundary (or since the start of the session) */
+  events: ContextSegment;
+  estimatedTokens: number;
+  /** Context size reported by the API for the latest assistant message */
+  reportedTokens: number | null;
+  reportedLine: number | null;
+  /** Every compaction in the session, in file order */
+  compactions: CompactionBoundary[];
+}
+
+/** Severity of a dangerous-command rule (matches Rust Severity) */
+export type Severity = "low" | "medium" | "high";
+
+/** A dangerous-command rule that flagged a command (matches Rust RuleMatch) */
+export interface RuleMatch {
+  ruleId: string;
+  description: string;
+  severity: Severity;
+}
+
+/** A Bash tool call (matches Rust BashCommand) */
+export interface BashCommand {
+  toolUseId: string;
+  command: string;
+  /** Description the model gave for the command */
+  description: string | null;
+  cwd: string | null;
+  startedAt: string | null;
+  durationMs: number | null;
+  /** 0 for successful results, parsed from errors, null when unknown */
+  exitCode: number | null;
+  isError: boolean;
+  interrupted: boolean;
+  /** Output (stdout then stderr), truncated */
+  output: string;
+  /** Programs run by the command */
+  executables: string[];
+  flags: RuleMatch[];
+  toolUseLine: number;
+  toolResultLine: number | null;
+}
+
+/** Aggregates for one executable (matches Rust ExecutableStats) */
+export interface ExecutableStats {
+  executable: string;
+  commands: number;
+  errors: number;
+  totalDurationMs: number;
+}
+
+/** Commands flagged by a rule (matches Rust RuleCount) */
+export interface RuleCount {
+  ruleId: string;
+  description: string;
+  severity: Severity;
+  count: number;
+}
+
+/** Bash history of a session (matches Rust BashHistory) */
+export interface BashHistory {
+  /** Commands in session order */
+  commands: BashCommand[];
+  /** Aggregates per executable, most used first */
+  executables: ExecutableStats[];
+  /** Rules that flagged at least one command, most severe first */
+  flagged: RuleCount[];
+}
+
+/** An Edit or Write tool call, or one edit of a MultiEdit (matches Rust EditRecord) */
+export interface EditRecord {
+  /** Path relative to the project */
+  path: string;
+  /** Line of the assistant message containing the tool_use */
+  line: number;
+  toolUseId: string | null;
+  /** "Edit", "MultiEdit" or "Write" */
+  toolName: string;
+  timestamp: string | null;
+  /** The text that was replaced (empty for Write) */
+  oldString: string;
+  newString: string;
+  /** The Edit replaces every occurrence of oldString, not just the first */
+  replaceAll: boolean;
+  linesAdded: number;
+  linesRemoved: number;
+  /** Line of the human prompt the edit answers */
+  promptLine: number | null;
+  /** Text of that prompt, whitespace-normalized and truncated */
+  prompt: string | null;
+}
+
+/** Kind of a line in a diff hunk (matches Rust LineKind) */
+export type LineKind = "context" | "added" | "removed";
+
+/** A line in a diff hunk (matches Rust HunkLine) */
+export interface HunkLine {
+  kind: LineKind;
+  /** Line number before the edit (null for added lines) */
+  oldLine: number | null;
+  /** Line number after the edit (null for removed lines) */
+  newLine: number | null;
+  /** Text without the trailing newline */
+  text: string;
+}
+
+/** A run of changed lines with surrounding context (matches Rust DiffHunk) */
+export interface DiffHunk {
+  oldStart: number;
+  oldLines: number;
+  newStart: number;
+  newLines: number;
+  lines: HunkLine[];
+}
+
+/** Hunks of one Edit or Write (matches Rust EditHunks) */
+export interface EditHunks {
+  /** Line of the assistant message containing the tool_use */
+  line: number;
+  /** "Edit", "MultiEdit" or "Write" */
+  toolName: string;
+  timestamp: string | null;
+  linesAdded: number;
+  linesRemoved: number;
+  /** Whether line numbers are in the reconstructed file (otherwise relative to the edit's text) */
+  located: boolean;
+  hunks: DiffHunk[];
+}
+
+/** Hunks of every edit to a file in a session (matches Rust FileHunks) */
+export interface FileHunks {
+  /** Path relative to the project */
+  path: string;
+  /** Edits in session order */
+  edits: EditHunks[];
+  linesAdded: number;
+  linesRemoved: number;
+}
+
+/** Which edits of a session to export (matches Rust EditSelection) */
+export type EditSelection =
+  | { kind: "all" }
+  | { kind: "prompt"; promptLine: number }
+  | { kind: "edits"; toolUseIds: string[] };
+
+/** An edit left out of an export (matches Rust SkippedEdit) */
+export interface SkippedEdit {
+  toolUseId: string | null;
+  path: string;
+  reason: string;
+}
+
+/** A commit created on the export branch (matches Rust ExportedCommit) */
+export interface ExportedCommit {
+  sha: string;
+  summary: string;
+  /** Line of the human prompt the commit's edits answer */
+  promptLine: number | null;
+  edits: number;
+}
+
+/** Result of exporting a session's edits (matches Rust EditExport) */
+export interface EditExport {
+  /** Unified diff against HEAD (empty if nothing changed) */
+  patch: string;
+  /** Files changed by the patch, relative to the repository root */
+  files: string[];
+  /** Edits included in the patch */
+  edits: number;
+  skipped: SkippedEdit[];
+  /** Branch created, if one was requested */
+  branch: string | null;
+  commits: ExportedCommit[];
+}
+
+/** An edit in the project-wide history of a file (matches Rust FileHistoryEntry) */
+export interface FileHistoryEntry extends EditRecord {
+  sessionId: string;
+  /** Sub-agent that made the edit (null for the main session) */
+  agentId: string | null;
+}
+
+/** Every agent edit of a file across a project (matches Rust FileEditHistory) */
+export interface FileEditHistory {
+  /** Path relative to the project */
+  path: string;
+  /** Edits in timestamp order */
+  edits: FileHistoryEntry[];
+  /** Sessions that edited the file */
+  sessions: number;
+  linesAdded: number;
+  linesRemoved: number;
+}
+
+/** A file ranked by agent churn (matches Rust HotFile) */
+export interface HotFile {
+  /** Path relative to the project */
+  path: string;
+  edits: number;
+  /** Sessions that edited the file (sub-agents count toward their session) */
+  sessions: number;
+  linesAdded: number;
+  linesRemoved: number;
+  /** Lines added plus lines removed */
+  churn: number;
+  lastEditedAt: string | null;
+}
+
+// =============================================================================
+// Dashboard Types
+// =============================================================================
+
+/** Range and timezone for the activity dashboard (matches Rust DashboardQuery) */
+export interface DashboardQuery {
+  /** First day (YYYY-MM-DD, defaults to six days before until) */
+  since?: string;
+  /** Last day, inclusive (YYYY-MM-DD, defaults to today) */
+  until?: string;
+  /** "local" (default), "UTC", or a fixed offset like "+05:30" */
+  timezone?: string;
+}
+
+/** Activity counters for a period (matches Rust ActivityCounts) */
+export interface ActivityCounts {
+  sessionsStarted: number;
+  /** Active time, excluding idle gaps */
+  activeMs: number;
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  /** Distinct files edited or written */
+  filesEdited: number;
+  commits: number;
+  /** Policy evaluations that denied, blocked or halted */
+  policyDenials: number;
+}
+
+/** Activity of one project (matches Rust ProjectActivity) */
+export interface ProjectActivity {
+  projectPath: string;
+  projectName: string;
+  totals: ActivityCounts;
+  /** One entry per day of the range */
+  days: ActivityCounts[];
+}
+
+/** Cross-project activity time series (matches Rust Dashboard) */
+export interface Dashboard {
+  timezone: string;
+  /** Days of the range (YYYY-MM-DD) */
+  days: string[];
+  /** All projects, one entry per day */
+  totals: ActivityCounts[];
+  /** Projects with activity in the range, most active first */
+  projects: ProjectActivity[];
+}
+
 // =============================================================================
 // Search Types
 // =============================================================================
@@ -0,0 +1,158 @@
+import { useState, useEffect, useCallback, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type {
+  LaunchOptions,
+  PtyExitPayload,
+  PtyInfo,
+  PtyOutputPayload,
+  PtySessionPayload,
+} from "./types";
+
+interface UseEmbeddedTerminalResult {
+  /** The running (or exited) terminal, if one was started */
+  terminal: PtyInfo | null;
+  /** Start Claude in an embedded terminal */
+  start: (options: LaunchOptions, cols: number, rows: number) => Promise<void>;
+  /** Send keyboard input */
+  write: (data: string) => void;
+  /** Resize the terminal */
+  resize: (cols: number, rows: number) => void;
+  /** Close the terminal, hanging up Claude if it's still running */
+  close: () => void;
+}
+
+/** A terminal event, as received from the backend */
+type TerminalEvent =
+  | { kind: "output"; payload: PtyOutputPayload }
+  | { kind: "session"; payload: PtySessionPayload }
+  | { kind: "exit"; payload: PtyExitPayload };
+
+function closeTerminal(id: number) {
+  invoke("close_terminal", { id }).catch((err) =>
+    console.error("Failed to close terminal:", err)
+  );
+}
+
+/**
+ * Hook to run Claude in an embedded terminal for a project.
+ *
+ * Output is passed to `onOutput` as it arrives; `terminal.sessionId` follows
+ * the session the process is writing to. The terminal is closed when the
+ * component using the hook unmounts.
+ */
+export function useEmbeddedTerminal(
+  projectPath: string,
+  onOutput: (data: string) => void
+): UseEmbeddedTerminalResult {
+  const [terminal, setTerminal] = useState<PtyInfo | null>(null);
+  const terminalIdRef = useRef<number | null>(null);
+  const mountedRef = useRef(false);
+  // Events received while spawn_terminal is in flight (null otherwise)
+  const pendingRef = useRef<TerminalEvent[] | null>(null);
+
+  // Keep the latest callback without re-subscribing
+  const onOutputRef = useRef(onOutput);
+  onOutputRef.current = onOutput;
+
+  const handle = useCallback((event: TerminalEvent) => {
+    if (event.payload.id !== terminalIdRef.current) {
+      // The process can print (or even exit) before its id reaches us
+      pendingRef.current?.push(event);
+      return;
+    }
+    switch (event.kind) {
+      case "output":
+        onOutputRef.current(event.payload.data);
+        break;
+      case "session": {
+        const { sessionId } = event.payload;
+        setTerminal((t) => t && { ...t, sessionId });
+        break;
+      }
+      case "exit": {
+        const { exitCode } = event.payload;
+        setTerminal((t) => t && { ...t, exited: true, exitCode });
+        break;
+      }
+    }
+  }, []);
+
+  useEffect(() => {
+    mountedRef.current = true;
+    const unlisteners = [
+      listen<PtyOutputPayload>("pty-output", (event) =>
+        handle({ kind: "output", payload: event.payload })
+      ),
+      listen<PtySessionPayload>("pty-session", (event) =>
+        handle({ kind: "session", payload: event.payload })
+      ),
+      listen<PtyExitPayload>("pty-exit", (event) =>
+        handle({ kind: "exit", payload: event.payload })
+      ),
+    ];
+
+    return () => {
+      mountedRef.current = false;
+      unlisteners.forEach((p) => p.then((unlisten) => unlisten()));
+      if (terminalIdRef.current !== null) {
+        closeTerminal(terminalIdRef.current);
+        terminalIdRef.current = null;
+      }
+    };
+  }, [handle]);
+
+  const start = useCallback(
+    async (options: LaunchOptions, cols: number, rows: number) => {
+      pendingRef.current = [];
+      let info: PtyInfo;
+      try {
+        info = await invoke<PtyInfo>("spawn_terminal", {
+          projectPath,
+          options,
+          cols,
+          rows,
+        });
+      } catch (err) {
+        pendingRef.current = null;
+        throw err;
+      }
+      const pending = pendingRef.current ?? [];
+      pendingRef.current = null;
+
+      if (!mountedRef.current) {
+        closeTerminal(info.id);
+        return;
+      }
+      terminalIdRef.current = info.id;
+      setTerminal(info);
+      pending.forEach(handle);
+    },
+    [projectPath, handle]
+  );
+
+  const write = useCallback((data: string) => {
+    const id = terminalIdRef.current;
+    if (id === null) return;
+    invoke("write_terminal", { id, data }).catch((err) =>
+      console.error("Failed to write to terminal:", err)
+    );
+  }, []);
+
+  const resize = useCallback((cols: number, rows: number) => {
+    const id = terminalIdRef.current;
+    if (id === null) return;
+    invoke("resize_terminal", { id, cols, rows }).catch((err) =>
+      console.error("Failed to resize terminal:", err)
+    );
+  }, []);
+
+  const close = useCallback(() => {
+    if (terminalIdRef.current === null) return;
+    closeTerminal(terminalIdRef.current);
+    terminalIdRef.current = null;
+    setTerminal(null);
+  }, []);
+
+  return { terminal, start, write, resize, close };
+}
@@ -26,6 +26,7 @@ import {
   formatTimestamp,
   getFileEditIcon,
   getLanguageFromPath,
+  getLineChangeBadge,
   useMonacoTheme,
 } from "../utils";
 import { TreeNodeItem } from "./tree-node";
@@ -305,6 +306,7 @@ export function EditViewer({
                               <span className="text-muted-foreground"> | {dirPath}</span>
                             )}
                           </span>
+                          {getLineChangeBadge(edit.linesAdded, edit.linesRemoved)}
                           {edit.lastEditedAt && (
                             <span className="shrink-0 text-[0.65rem] text-muted-foreground">
                               {formatTimestamp(edit.lastEditedAt)}
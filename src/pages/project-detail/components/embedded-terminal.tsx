@@ -0,0 +1,284 @@
+import { useCallback, useEffect, useLayoutEffect, useRef, useState } from "react";
+import { IconLoader2, IconTerminal, IconX } from "@tabler/icons-react";
+import { Button } from "@/components/ui/button";
+import { TerminalScreen, type CellStyle } from "@/lib/terminal-screen";
+import { useEmbeddedTerminal } from "@/lib/use-embedded-terminal";
+import { truncateUuid } from "../utils";
+import type { EmbeddedTerminalProps } from "../types";
+
+/** Bytes a key press sends, or null to let the browser handle it. */
+function keySequence(e: React.KeyboardEvent, applicationCursorKeys: boolean): string | null {
+  const cursor = (code: string) => (applicationCursorKeys ? `\x1bO${code}` : `\x1b[${code}`);
+  if (e.metaKey) return null;
+
+  switch (e.key) {
+    case "Enter":
+      return e.altKey || e.shiftKey ? "\x1b\r" : "\r";
+    case "Backspace":
+      return e.altKey ? "\x1b\x7f" : "\x7f";
+    case "Tab":
+      return e.shiftKey ? "\x1b[Z" : "\t";
+    case "Escape":
+      return "\x1b";
+    case "ArrowUp":
+      return cursor("A");
+    case "ArrowDown":
+      return cursor("B");
+    case "ArrowRight":
+      return cursor("C");
+    case "ArrowLeft":
+      return cursor("D");
+    case "Home":
+      return cursor("H");
+    case "End":
+      return cursor("F");
+    case "Insert":
+      return "\x1b[2~";
+    case "Delete":
+      return "\x1b[3~";
+    case "PageUp":
+      return "\x1b[5~";
+    case "PageDown":
+      return "\x1b[6~";
+  }
+
+  if (e.ctrlKey && !e.altKey && e.key.length === 1) {
+    if (e.key === " ") return "\x00";
+    const code = e.key.toUpperCase().charCodeAt(0);
+    // Ctrl+@, Ctrl+A..Z, Ctrl+[, \, ], ^, _
+    if (code >= 64 && code <= 95) return String.fromCharCode(code - 64);
+  }
+  // Alt as Meta; use the physical key since macOS turns Option+key into symbols
+  if (e.altKey && !e.ctrlKey && /^Key[A-Z]$/.test(e.code)) {
+    const letter = e.code.slice(3);
+    return "\x1b" + (e.shiftKey ? letter : letter.toLowerCase());
+  }
+  return null;
+}
+
+function runStyle(style: CellStyle): React.CSSProperties {
+  const fg = style.inverse ? (style.bg ?? "var(--background)") : style.fg;
+  const bg = style.inverse ? (style.fg ?? "var(--foreground)") : style.bg;
+  return {
+    color: fg ?? undefined,
+    backgroundColor: bg ?? undefined,
+    fontWeight: style.bold ? 600 : undefined,
+    fontStyle: style.italic ? "italic" : undefined,
+    textDecoration: style.underline ? "underline" : undefined,
+    opacity: style.dim ? 0.6 : undefined,
+  };
+}
+
+/** Claude running in a terminal inside the app. */
+export function EmbeddedTerminal({ projectPath, onClose }: EmbeddedTerminalProps) {
+  const viewportRef = useRef<HTMLDivElement>(null);
+  const measureRef = useRef<HTMLSpanElement>(null);
+  const inputRef = useRef<HTMLTextAreaElement>(null);
+  const startedRef = useRef(false);
+  // Follow new output unless the user scrolled up
+  const stickToBottomRef = useRef(true);
+  const frameRef = useRef<number | null>(null);
+  const [, setVersion] = useState(0);
+  const [cell, setCell] = useState({ width: 7, height: 16 });
+  const [error, setError] = useState<string | null>(null);
+
+  // Replies to status queries go back through `write`, which isn't known yet
+  const writeRef = useRef<(data: string) => void>(() => {});
+  const screenRef = useRef<TerminalScreen | null>(null);
+  if (screenRef.current === null) {
+    screenRef.current = new TerminalScreen(80, 24, (data) => writeRef.current(data));
+  }
+  const screen = screenRef.current;
+
+  // Re-render at most once per frame however output is chunked
+  const scheduleRender = useCallback(() => {
+    if (frameRef.current !== null) return;
+    frameRef.current = requestAnimationFrame(() => {
+      frameRef.current = null;
+      setVersion((v) => v + 1);
+    });
+  }, []);
+  useEffect(
+    () => () => {
+      if (frameRef.current !== null) cancelAnimationFrame(frameRef.current);
+    },
+    []
+  );
+
+  const onOutput = useCallback(
+    (data: string) => {
+      screen.write(data);
+      scheduleRender();
+    },
+    [screen, scheduleRender]
+  );
+  const { terminal, start, write, resize, close } = useEmbeddedTerminal(projectPath, onOutput);
+  writeRef.current = write;
+
+  // Size the screen to the panel and start Claude once
+  useEffect(() => {
+    const viewport = viewportRef.current;
+    const measure = measureRef.current;
+    if (!viewport || !measure) return;
+
+    const rect = measure.getBoundingClientRect();
+    const size = { width: rect.width / 10 || 7, height: rect.height || 16 };
+    setCell(size);
+
+    const fit = () => {
+      const cols = Math.max(20, Math.floor(viewport.clientWidth / size.width));
+      const rows = Math.max(5, Math.floor(viewport.clientHeight / size.height));
+      return { cols, rows };
+    };
+
+    if (!startedRef.current) {
+      startedRef.current = true;
+      const { cols, rows } = fit();
+      screen.resize(cols, rows);
+      start({}, cols, rows).catch((err) => setError(String(err)));
+      inputRef.current?.focus();
+    }
+
+    const observer = new ResizeObserver(() => {
+      const { cols, rows } = fit();
+      if (cols === screen.cols && rows === screen.rows) return;
+      screen.resize(cols, rows);
+      resize(cols, rows);
+      scheduleRender();
+    });
+    observer.observe(viewport);
+    return () => observer.disconnect();
+  }, [screen, start, resize, scheduleRender]);
+
+  useLayoutEffect(() => {
+    const viewport = viewportRef.current;
+    if (viewport && stickToBottomRef.current) {
+      viewport.scrollTop = viewport.scrollHeight;
+    }
+  });
+
+  const handleScroll = () => {
+    const viewport = viewportRef.current;
+    if (!viewport) return;
+    stickToBottomRef.current =
+      viewport.scrollTop + viewport.clientHeight >= viewport.scrollHeight - cell.height;
+  };
+
+  const handleKeyDown = (e: React.KeyboardEvent<HTMLTextAreaElement>) => {
+    if (e.nativeEvent.isComposing) return;
+    const data = keySequence(e, screen.applicationCursorKeys);
+    if (data === null) return;
+    e.preventDefault();
+    stickToBottomRef.current = true;
+    write(data);
+  };
+
+  // Typed text arrives through the hidden textarea; composed (IME) text once
+  // composition ends
+  const sendInput = (input: HTMLTextAreaElement) => {
+    const text = input.value;
+    input.value = "";
+    if (text) write(text.replace(/\r?\n/g, "\r"));
+  };
+  const handleInput = (e: React.FormEvent<HTMLTextAreaElement>) => {
+    if (!(e.nativeEvent as InputEvent).isComposing) sendInput(e.currentTarget);
+  };
+
+  const handlePaste = (e: React.ClipboardEvent<HTMLTextAreaElement>) => {
+    e.preventDefault();
+    const text = e.clipboardData.getData("text/plain").replace(/\r?\n/g, "\r");
+    if (!text) return;
+    write(screen.bracketedPaste ? `\x1b[200~${text}\x1b[201~` : text);
+  };
+
+  const handleClose = () => {
+    close();
+    onClose();
+  };
+
+  const lines = screen.render();
+  const cursor = screen.cursor;
+  const cursorRow = screen.scrollbackLength + cursor.row;
+
+  return (
+    <div className="h-full flex flex-col bg-background">
+      <div className="shrink-0 px-3 py-1 flex items-center gap-2 border-b border-border text-xs">
+        <IconTerminal className="size-3.5 text-muted-foreground" />
+        <span className="font-medium">Claude</span>
+        {error ? (
+          <span className="text-red-500 truncate">{error}</span>
+        ) : !terminal ? (
+          <IconLoader2 className="size-3 animate-spin text-muted-foreground" />
+        ) : terminal.exited ? (
+          <span className="text-muted-foreground">
+            Exited{terminal.exitCode !== null && ` (${terminal.exitCode})`}
+          </span>
+        ) : (
+          <span className="size-2 rounded-full bg-green-500 shrink-0" />
+        )}
+        {terminal?.sessionId && (
+          <span className="font-mono text-muted-foreground">
+            {truncateUuid(terminal.sessionId)}
+          </span>
+        )}
+        <Button
+          variant="ghost"
+          size="icon-xs"
+          className="ml-auto"
+          onClick={handleClose}
+          title="Close terminal"
+        >
+          <IconX className="size-3.5" />
+        </Button>
+      </div>
+
+      <div
+        ref={viewportRef}
+        onScroll={handleScroll}
+        onMouseUp={() => {
+          // Keep text selectable; only grab focus on a plain click
+          if (!window.getSelection()?.toString()) inputRef.current?.focus();
+        }}
+        className="relative flex-1 overflow-y-auto overflow-x-hidden px-1 font-mono text-xs leading-4 cursor-text"
+      >
+        <span ref={measureRef} className="invisible absolute whitespace-pre" aria-hidden>
+          MMMMMMMMMM
+        </span>
+        {lines.map((runs, i) => (
+          <div key={i} className="h-4 whitespace-pre">
+            {runs.map((run, j) => (
+              <span key={j} style={runStyle(run.style)}>
+                {run.text}
+              </span>
+            ))}
+          </div>
+        ))}
+        {screen.cursorVisible && terminal && !terminal.exited && (
+          <div
+            className="absolute bg-foreground/60 pointer-events-none"
+            style={{
+              left: `calc(0.25rem + ${cursor.col * cell.width}px)`,
+              top: cursorRow * cell.height,
+              width: cell.width,
+              height: cell.height,
+            }}
+          />
+        )}
+        <textarea
+          ref={inputRef}
+          onKeyDown={handleKeyDown}
+          onInput={handleInput}
+          onCompositionEnd={(e) => sendInput(e.currentTarget)}
+          onPaste={handlePaste}
+          className="absolute size-0 opacity-0 resize-none"
+          style={{ top: cursorRow * cell.height }}
+          autoCapitalize="off"
+          autoComplete="off"
+          autoCorrect="off"
+          spellCheck={false}
+          aria-label="Terminal input"
+        />
+      </div>
+    </div>
+  );
+}
@@ -19,12 +19,19 @@ import {
   DropdownMenuItem,
   DropdownMenuTrigger,
 } from "@/components/ui/dropdown-menu";
-import type { SessionEventsResponse } from "@/lib/types";
+import type { ConversationBranch, SessionEventsResponse } from "@/lib/types";
 import { getEventBadgeClass } from "../utils";
 import type { EventLogViewerProps, EventRowBaseProps } from "../types";
 import { EventRowComponent } from "./event-row";
 import { JsonViewerRoot } from "./json-viewer";
 
+/** Dropdown label for a branch (null = every event) */
+function branchLabel(branch: ConversationBranch | null): string {
+  if (!branch) return "All branches";
+  if (branch.isActive) return "Active branch";
+  return `Abandoned at line ${branch.forkLine ?? branch.firstLine} (${branch.eventCount})`;
+}
+
 export function EventLogViewer({
   events,
   loading,
@@ -42,6 +49,9 @@ export function EventLogViewer({
   sessionId,
   selectedSubagentId,
   onSelectSubagent,
+  branches,
+  branchLeaf,
+  onBranchLeafChange,
   searchQuery,
   onSearchChange,
   searchLoading,
@@ -415,6 +425,32 @@ export function EventLogViewer({
                 {searchResults.matches.length.toLocaleString()}{searchResults.truncated && "+"}
               </span>
             )}
+            {/* Branch dropdown (only when the conversation forks) */}
+            {branches.length > 1 && (
+              <DropdownMenu>
+                <DropdownMenuTrigger className="flex items-center gap-1 px-2 py-0.5 rounded text-[0.65rem] font-medium bg-muted text-muted-foreground hover:text-foreground transition-colors whitespace-nowrap">
+                  {branchLabel(branches.find((b) => b.leafUuid === branchLeaf) ?? null)}
+                  <IconChevronDown className="size-3" />
+                </DropdownMenuTrigger>
+                <DropdownMenuContent align="start">
+                  <DropdownMenuItem
+                    onClick={() => onBranchLeafChange(null)}
+                    className={cn(branchLeaf === null && "bg-accent")}
+                  >
+                    {branchLabel(null)}
+                  </DropdownMenuItem>
+                  {branches.map((branch) => (
+                    <DropdownMenuItem
+                      key={branch.leafUuid}
+                      onClick={() => onBranchLeafChange(branch.leafUuid)}
+                      className={cn(branchLeaf === branch.leafUuid && "bg-accent")}
+                    >
+                      {branchLabel(branch)}
+                    </DropdownMenuItem>
+                  ))}
+                </DropdownMenuContent>
+              </DropdownMenu>
+            )}
             {/* Mode dropdown */}
             <DropdownMenu>
               <DropdownMenuTrigger className="flex items-center gap-1 px-2 py-0.5 rounded text-[0.65rem] font-medium bg-muted text-muted-foreground hover:text-foreground transition-colors whitespace-nowrap">
@@ -6,9 +6,11 @@ import { JsonViewer } from "./json-viewer";
 interface PolicySpanDetailsProps {
   span: TraceSpan | null;
   cupcakeSpan: CupcakeSpan | null;
+  /** The span's JSON as written, including fields the typed span drops */
+  rawSpan: unknown;
 }
 
-export function PolicySpanDetails({ span, cupcakeSpan }: PolicySpanDetailsProps) {
+export function PolicySpanDetails({ span, cupcakeSpan, rawSpan }: PolicySpanDetailsProps) {
   if (!span) {
     return (
       <div className="h-full flex items-center justify-center text-muted-foreground p-4 border-t border-border bg-background">
@@ -67,7 +69,7 @@ export function PolicySpanDetails({ span, cupcakeSpan }: PolicySpanDetailsProps)
 
         {/* Phase-specific details */}
         {span.serviceName === "Root" && data && (
-          <RootDetails data={data} cupcakeSpan={cupcakeSpan} />
+          <RootDetails data={data} cupcakeSpan={cupcakeSpan} rawSpan={rawSpan} />
         )}
 
         {span.serviceName === "Enrich" && data && (
@@ -98,9 +100,11 @@ export function PolicySpanDetails({ span, cupcakeSpan }: PolicySpanDetailsProps)
 function RootDetails({
   data,
   cupcakeSpan,
+  rawSpan,
 }: {
   data: Record<string, unknown>;
   cupcakeSpan: CupcakeSpan | null;
+  rawSpan: unknown;
 }) {
   const rawEvent = data.rawEvent as Record<string, unknown> | undefined;
   const response = data.response as Record<string, unknown> | undefined;
@@ -154,6 +158,16 @@ function RootDetails({
           </p>
         </div>
       )}
+
+      {/* Raw span, including fields this version doesn't display */}
+      {rawSpan != null && (
+        <div className="space-y-2">
+          <p className="text-xs font-medium text-muted-foreground">Raw Span</p>
+          <div className="bg-muted/30 p-2 rounded text-xs">
+            <JsonViewer data={rawSpan} isRoot />
+          </div>
+        </div>
+      )}
     </>
   );
 }
@@ -17,12 +17,13 @@ import {
   TooltipTrigger,
 } from "@/components/ui/tooltip";
 import { cn } from "@/lib/utils";
-import type { PolicyEvaluation, CupcakeSpan } from "@/lib/types";
+import type { PolicyEvaluation, CupcakeSpan, LoadedSpan } from "@/lib/types";
 import { formatRelativeTime } from "../utils";
 import {
   flattenCupcakeSpan,
   getDecisionBadgeClass,
   formatDuration,
+  evaluationKey,
   snakeToCamelKeys,
   type TraceSpan,
 } from "../policy-utils";
@@ -39,6 +40,7 @@ export function PolicyViewer({ projectPath }: PolicyViewerProps) {
   const [selectedEvaluation, setSelectedEvaluation] =
     useState<PolicyEvaluation | null>(null);
   const [cupcakeSpan, setCupcakeSpan] = useState<CupcakeSpan | null>(null);
+  const [rawSpan, setRawSpan] = useState<unknown>(null);
   const [spanLoading, setSpanLoading] = useState(false);
   const [selectedSpan, setSelectedSpan] = useState<TraceSpan | null>(null);
   const [sidebarCollapsed, setSidebarCollapsed] = useState(false);
@@ -61,10 +63,10 @@ export function PolicyViewer({ projectPath }: PolicyViewerProps) {
       const currentTimestamps = new Map<string, string>();
 
       for (const e of evals) {
-        currentTimestamps.set(e.filename, e.timestamp);
-        const prevTs = prevTimestampsRef.current.get(e.filename);
+        currentTimestamps.set(evaluationKey(e), e.timestamp);
+        const prevTs = prevTimestampsRef.current.get(evaluationKey(e));
         if (prevTs === undefined && prevTimestampsRef.current.size > 0) {
-          newFlashing.add(e.filename);
+          newFlashing.add(evaluationKey(e));
         }
       }
 
@@ -122,6 +124,7 @@ export function PolicyViewer({ projectPath }: PolicyViewerProps) {
   useEffect(() => {
     if (!selectedEvaluation) {
       setCupcakeSpan(null);
+      setRawSpan(null);
       setSelectedSpan(null);
       return;
     }
@@ -130,20 +133,23 @@ export function PolicyViewer({ projectPath }: PolicyViewerProps) {
       setSpanLoading(true);
       setSelectedSpan(null);
       try {
-        const rawJson = await invoke<string | null>("get_policy_evaluation", {
+        // The backend returns the typed span with Cupcake's snake_case keys
+        const loaded = await invoke<LoadedSpan | null>("get_policy_evaluation", {
           projectPath,
           filename: selectedEvaluation!.filename,
+          path: selectedEvaluation!.path,
         });
-        if (rawJson) {
-          const rawSpan = JSON.parse(rawJson);
-          const span = snakeToCamelKeys<CupcakeSpan>(rawSpan);
-          setCupcakeSpan(span);
+        if (loaded) {
+          setCupcakeSpan(snakeToCamelKeys<CupcakeSpan>(loaded.span));
+          setRawSpan(loaded.raw);
         } else {
           setCupcakeSpan(null);
+          setRawSpan(null);
         }
       } catch (err) {
         console.error("Failed to load span:", err);
         setCupcakeSpan(null);
+        setRawSpan(null);
       } finally {
         setSpanLoading(false);
       }
@@ -187,16 +193,17 @@ export function PolicyViewer({ projectPath }: PolicyViewerProps) {
               </div>
               <div className="flex-1 overflow-auto py-1">
                 {evaluations.map((e) => (
-                  <Tooltip key={e.filename}>
+                  <Tooltip key={evaluationKey(e)}>
                     <TooltipTrigger asChild>
                       <button
                         onClick={() => setSelectedEvaluation(e)}
                         className={cn(
                           "w-full flex items-center justify-center py-1.5 transition-colors",
-                          selectedEvaluation?.filename === e.filename
+                          selectedEvaluation &&
+                            evaluationKey(selectedEvaluation) === evaluationKey(e)
                             ? "bg-accent text-accent-foreground"
                             : "hover:bg-muted",
-                          flashingEvaluations.has(e.filename) && "animate-flash"
+                          flashingEvaluations.has(evaluationKey(e)) && "animate-flash"
                         )}
                       >
                         <span
@@ -257,14 +264,15 @@ export function PolicyViewer({ projectPath }: PolicyViewerProps) {
                   <div className="py-1">
                     {evaluations.map((e) => (
                       <button
-                        key={e.filename}
+                        key={evaluationKey(e)}
                         onClick={() => setSelectedEvaluation(e)}
                         className={cn(
                           "w-full flex flex-col gap-1 px-3 py-2 text-xs transition-colors border-b border-border/50",
-                          selectedEvaluation?.filename === e.filename
+                          selectedEvaluation &&
+                            evaluationKey(selectedEvaluation) === evaluationKey(e)
                             ? "bg-accent text-accent-foreground"
                             : "hover:bg-muted",
-                          flashingEvaluations.has(e.filename) && "animate-flash"
+                          flashingEvaluations.has(evaluationKey(e)) && "animate-flash"
                         )}
                       >
                         <div className="flex items-center justify-between gap-2">
@@ -338,6 +346,7 @@ export function PolicyViewer({ projectPath }: PolicyViewerProps) {
               <PolicySpanDetails
                 span={selectedSpan}
                 cupcakeSpan={cupcakeSpan}
+                rawSpan={rawSpan}
               />
             </Panel>
           </PanelGroup>
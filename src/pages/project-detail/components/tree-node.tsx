@@ -5,7 +5,7 @@ import {
   IconFolderOpen,
 } from "@tabler/icons-react";
 import { cn } from "@/lib/utils";
-import { getFileEditIcon } from "../utils";
+import { getFileEditIcon, getLineChangeBadge } from "../utils";
 import type { TreeNodeItemProps } from "../types";
 
 export function TreeNodeItem({
@@ -67,7 +67,8 @@ export function TreeNodeItem({
       style={{ paddingLeft: paddingLeft + 16 }}
     >
       {node.editType && getFileEditIcon(node.editType)}
-      <span className="truncate">{node.name}</span>
+      <span className="truncate flex-1 text-left">{node.name}</span>
+      <span className="pr-2">{getLineChangeBadge(node.linesAdded ?? 0, node.linesRemoved ?? 0)}</span>
     </button>
   );
 }
@@ -1,9 +1,12 @@
 import { useState, useEffect, useCallback, useMemo } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import {
   IconCheck,
   IconChevronDown,
   IconLoader2,
+  IconTerminal,
+  IconTerminal2,
 } from "@tabler/icons-react";
 import {
   DropdownMenu,
@@ -14,12 +17,14 @@ import {
 import { Button } from "@/components/ui/button";
 import { cn } from "@/lib/utils";
 import { useSessionIndex } from "@/lib/use-session-index";
-import type { Session, ActiveSessionsResult, FileEdit, FileDiff, SessionEvent, SessionEventsResponse, SearchResponse } from "@/lib/types";
+import type { ConversationGraph, Session, ActiveSessionsResult, LaunchOptions, PtySessionPayload, TerminalType, FileEdit, FileDiff, SessionEvent, SessionEventsResponse, SearchResponse } from "@/lib/types";
 import { formatRelativeTime, truncateUuid } from "./utils";
 import { EditViewer } from "./components/edit-viewer";
+import { EmbeddedTerminal } from "./components/embedded-terminal";
 import { EventLogViewer } from "./components/event-log-viewer";
 import { PolicyViewer } from "./components/policy-viewer";
 import type { ProjectDetailPageProps, TabId, EventFilterMode } from "./types";
+import { CUSTOM_TERMINAL_STORAGE_KEY, TERMINAL_STORAGE_KEY } from "@/pages/settings";
 
 export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
   const projectName = projectPath.split("/").pop() || projectPath;
@@ -31,6 +36,7 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
   const [loading, setLoading] = useState(true);
   const [activeTab, setActiveTab] = useState<TabId>("events");
   const [selectedFile, setSelectedFile] = useState<string | null>(null);
+  const [showTerminal, setShowTerminal] = useState(false);
 
   // File edits state
   const [fileEdits, setFileEdits] = useState<FileEdit[]>([]);
@@ -50,6 +56,10 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
   const [eventFilterMode, setEventFilterMode] = useState<EventFilterMode>("filter");
   const [selectedSubagentId, setSelectedSubagentId] = useState<string | null>(null);
 
+  // Conversation branches (branchLeaf null = every event in file order)
+  const [conversationGraph, setConversationGraph] = useState<ConversationGraph | null>(null);
+  const [branchLeaf, setBranchLeaf] = useState<string | null>(null);
+
   // Search state
   const [searchQuery, setSearchQuery] = useState("");
   const [searchResults, setSearchResults] = useState<SearchResponse | null>(null);
@@ -84,6 +94,26 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
     loadData();
   }, [projectPath]);
 
+  // Follow sessions started by embedded terminals in this project
+  useEffect(() => {
+    const unlisten = listen<PtySessionPayload>("pty-session", async (event) => {
+      if (event.payload.projectPath !== projectPath) return;
+      try {
+        const sessionList = await invoke<Session[]>("get_project_sessions", {
+          projectPath,
+        });
+        setSessions(sessionList);
+        setSelectedSessionId(event.payload.sessionId);
+      } catch (err) {
+        console.error("Failed to load sessions:", err);
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [projectPath]);
+
   // Load file edits function (reusable for initial load and refresh)
   // Uses indexed command for O(1) lookup when index is available
   const loadFileEdits = useCallback(async () => {
@@ -164,6 +194,7 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
         sessionId: selectedSessionId,
         offset: 0,
         limit: 200,
+        branchLeaf,
       });
       setEvents(response.events);
       setEventsTotalCount(response.totalCount);
@@ -176,27 +207,61 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
     } finally {
       setEventsLoading(false);
     }
+  }, [projectPath, selectedSessionId, branchLeaf]);
+
+  // Load the conversation graph (needs the session index)
+  const loadConversationGraph = useCallback(async () => {
+    if (!selectedSessionId) {
+      setConversationGraph(null);
+      return;
+    }
+    try {
+      const graph = await invoke<ConversationGraph>("get_conversation_graph", {
+        projectPath,
+        sessionId: selectedSessionId,
+      });
+      setConversationGraph(graph);
+    } catch (err) {
+      console.error("Failed to load conversation graph:", err);
+      setConversationGraph(null);
+    }
   }, [projectPath, selectedSessionId]);
 
   // Unified session change handler - refreshes all views that need updating
   const handleSessionChanged = useCallback(() => {
     // Always refresh file edits (for Edits tab)
     loadFileEdits();
+    loadConversationGraph();
 
     // Refresh events if they've been loaded (for Events tab)
     // Since events are in descending order, this gets the latest at the top
     if (events.length > 0 || activeTab === "events") {
       loadEvents();
     }
-  }, [loadFileEdits, loadEvents, events.length, activeTab]);
+  }, [loadFileEdits, loadConversationGraph, loadEvents, events.length, activeTab]);
 
   // Session index hook - manages indexing, file watching, and change notifications
-  const { isIndexing, error: indexError } = useSessionIndex(
+  const { isIndexing, isReady, error: indexError } = useSessionIndex(
     projectPath,
     selectedSessionId,
     handleSessionChanged
   );
 
+  // Load branches once the index is ready
+  useEffect(() => {
+    if (isReady) {
+      loadConversationGraph();
+    }
+  }, [isReady, loadConversationGraph]);
+
+  // Switching branch pages events along the chosen branch from the start
+  const handleBranchLeafChange = useCallback((leafUuid: string | null) => {
+    setBranchLeaf(leafUuid);
+    setEvents([]);
+    setEventsTotalCount(0);
+    setEventsHasMore(false);
+  }, []);
+
   // Load more events for infinite scrolling
   // Uses indexed command for O(k) seeks
   const loadMoreEvents = useCallback(async () => {
@@ -211,6 +276,7 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
         sessionId: selectedSessionId,
         offset: events.length,
         limit: 200,
+        branchLeaf,
       });
       setEvents((prev) => [...prev, ...response.events]);
       setEventsHasMore(response.hasMore);
@@ -219,7 +285,7 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
     } finally {
       setEventsLoadingMore(false);
     }
-  }, [projectPath, selectedSessionId, events.length, eventsLoadingMore, eventsHasMore]);
+  }, [projectPath, selectedSessionId, branchLeaf, events.length, eventsLoadingMore, eventsHasMore]);
 
   // Load events when tab switches to events
   useEffect(() => {
@@ -233,6 +299,8 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
     setEvents([]);
     setEventsTotalCount(0);
     setEventsHasMore(false);
+    setConversationGraph(null);
+    setBranchLeaf(null);
     setSearchQuery("");
     setSearchResults(null);
     setSearchEvents([]);
@@ -382,6 +450,26 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
     return map;
   }, [events]);
 
+  // Resume the selected session in the default terminal
+  const resumeSession = useCallback(async () => {
+    if (!selectedSessionId) return;
+    try {
+      const terminals = await invoke<TerminalType[]>("get_available_terminals");
+      const saved = localStorage.getItem(TERMINAL_STORAGE_KEY) as TerminalType | null;
+      const terminalType = saved && terminals.includes(saved) ? saved : terminals[0];
+      if (!terminalType) return;
+      const options: LaunchOptions = { resumeSessionId: selectedSessionId };
+      await invoke("launch_claude", {
+        terminalType,
+        projectPath,
+        options,
+        customCommand: localStorage.getItem(CUSTOM_TERMINAL_STORAGE_KEY),
+      });
+    } catch (err) {
+      console.error("Failed to resume session:", err);
+    }
+  }, [projectPath, selectedSessionId]);
+
   return (
     <div className="h-full flex flex-col overflow-hidden">
       {/* Compact Header */}
@@ -395,7 +483,26 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
         </div>
 
         {/* Right: Session selector */}
-        <div className="shrink-0">
+        <div className="shrink-0 flex items-center gap-1">
+          <Button
+            variant="ghost"
+            size="icon-xs"
+            onClick={() => setShowTerminal((show) => !show)}
+            className={cn(showTerminal && "bg-muted")}
+            title={showTerminal ? "Hide the embedded terminal" : "Run Claude in the app"}
+          >
+            <IconTerminal className="size-3.5" />
+          </Button>
+          {selectedSessionId && (
+            <Button
+              variant="ghost"
+              size="icon-xs"
+              onClick={resumeSession}
+              title="Resume this session in a terminal"
+            >
+              <IconTerminal2 className="size-3.5" />
+            </Button>
+          )}
           {loading ? (
             <IconLoader2 className="size-4 animate-spin text-muted-foreground" />
           ) : sessions.length > 0 ? (
@@ -527,6 +634,9 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
             sessionId={selectedSessionId ?? ""}
             selectedSubagentId={selectedSubagentId}
             onSelectSubagent={setSelectedSubagentId}
+            branches={conversationGraph?.branches ?? []}
+            branchLeaf={branchLeaf}
+            onBranchLeafChange={handleBranchLeafChange}
             searchQuery={searchQuery}
             onSearchChange={setSearchQuery}
             searchLoading={searchLoading}
@@ -550,6 +660,13 @@ export function ProjectDetailPage({ projectPath }: ProjectDetailPageProps) {
           <PolicyViewer projectPath={projectPath} />
         )}
       </div>
+
+      {/* Embedded terminal */}
+      {showTerminal && (
+        <div className="shrink-0 h-80 border-t border-border">
+          <EmbeddedTerminal projectPath={projectPath} onClose={() => setShowTerminal(false)} />
+        </div>
+      )}
     </div>
   );
 }
@@ -3,7 +3,7 @@
  * Converts CupcakeSpan data to a flat span format for D3 visualization.
  */
 
-import type { CupcakeSpan, FinalDecision } from "@/lib/types";
+import type { CupcakeSpan, FinalDecision, PolicyEvaluation } from "@/lib/types";
 import { getFinalDecisionType } from "@/lib/types";
 
 /** Flat span representation for D3 visualization */
@@ -291,6 +291,11 @@ export function getDecisionBadgeClass(decision: string | null): string {
   }
 }
 
+/** Unique key for an evaluation (filenames can repeat across telemetry locations) */
+export function evaluationKey(evaluation: PolicyEvaluation): string {
+  return `${evaluation.path}:${evaluation.filename}`;
+}
+
 /** Format duration for display */
 export function formatDuration(ms: number): string {
   if (ms < 1) return "<1ms";
@@ -1,4 +1,4 @@
-import type { FileEdit, FileDiff, FileEditType, SessionEvent, SearchResponse } from "@/lib/types";
+import type { ConversationBranch, FileEdit, FileDiff, FileEditType, SessionEvent, SearchResponse } from "@/lib/types";
 
 export type TabId = "events" | "edits" | "policies";
 export type DiffViewMode = "split" | "unified";
@@ -12,6 +12,8 @@ export interface TreeNode {
   path: string;
   type: "file" | "folder";
   editType?: FileEditType;
+  linesAdded?: number;
+  linesRemoved?: number;
   children: TreeNode[];
 }
 
@@ -56,6 +58,10 @@ export interface EventLogViewerProps {
   sessionId: string;
   selectedSubagentId: string | null;
   onSelectSubagent: (agentId: string | null) => void;
+  // Branch props (branchLeaf null = every event in file order)
+  branches: ConversationBranch[];
+  branchLeaf: string | null;
+  onBranchLeafChange: (leafUuid: string | null) => void;
   // Search props
   searchQuery: string;
   onSearchChange: (query: string) => void;
@@ -66,6 +72,12 @@ export interface EventLogViewerProps {
   searchEventsLoading?: boolean;
 }
 
+export interface EmbeddedTerminalProps {
+  projectPath: string;
+  /** Called after the terminal is closed from its header */
+  onClose: () => void;
+}
+
 export interface JsonViewerProps {
   data: unknown;
   label?: string;
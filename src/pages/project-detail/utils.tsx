@@ -74,6 +74,8 @@ export function buildFileTree(files: FileEdit[]): TreeNode[] {
           path: currentPath,
           type: isFile ? "file" : "folder",
           editType: isFile ? file.editType : undefined,
+          linesAdded: isFile ? file.linesAdded : undefined,
+          linesRemoved: isFile ? file.linesRemoved : undefined,
           children: [],
         };
         currentLevel.push(existing);
@@ -140,6 +142,17 @@ export function getFileEditIcon(editType: FileEditType) {
   }
 }
 
+// "+12 −3" badge for the lines an edit added and removed
+export function getLineChangeBadge(linesAdded: number, linesRemoved: number) {
+  if (linesAdded === 0 && linesRemoved === 0) return null;
+  return (
+    <span className="shrink-0 text-[0.65rem] tabular-nums">
+      <span className="text-green-500">+{linesAdded}</span>{" "}
+      <span className="text-red-500">−{linesRemoved}</span>
+    </span>
+  );
+}
+
 // Resolve theme to Monaco theme
 export function useMonacoTheme(): "vs" | "vs-dark" {
   const { theme } = useTheme();
@@ -26,8 +26,8 @@ import {
 import { invoke } from "@tauri-apps/api/core";
 import { useProjects } from "@/lib/use-projects";
 import { useActiveSessions } from "@/lib/use-active-sessions";
-import type { AgentType, TerminalType } from "@/lib/types";
-import { TERMINAL_STORAGE_KEY } from "@/pages/settings";
+import type { AgentType, LaunchOptions, TerminalType } from "@/lib/types";
+import { CUSTOM_TERMINAL_STORAGE_KEY, TERMINAL_STORAGE_KEY } from "@/pages/settings";
 import { terminalDisplayNames } from "@/lib/types";
 
 const INITIAL_DISPLAY_COUNT = 8;
@@ -105,11 +105,12 @@ export function SessionsPage({ onSelectProject }: SessionsPageProps) {
   ) => {
     if (!selectedTerminal) return;
     try {
+      const options: LaunchOptions = { continueSession, yoloMode };
       await invoke("launch_claude", {
         terminalType: selectedTerminal,
         projectPath,
-        continueSession,
-        yoloMode,
+        options,
+        customCommand: localStorage.getItem(CUSTOM_TERMINAL_STORAGE_KEY),
       });
     } catch (err) {
       console.error("Failed to launch Claude:", err);
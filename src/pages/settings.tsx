@@ -1,6 +1,7 @@
 import { useState, useEffect } from "react";
 import { cn } from "@/lib/utils";
 import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
 import {
   Card,
   CardContent,
@@ -26,6 +27,7 @@ import { terminalDisplayNames } from "@/lib/types";
 type SettingsSection = "appearance" | "terminal" | "about";
 
 export const TERMINAL_STORAGE_KEY = "agent-console:default-terminal";
+export const CUSTOM_TERMINAL_STORAGE_KEY = "agent-console:custom-terminal-command";
 
 interface SettingsPageProps {
   onBack: () => void;
@@ -207,6 +209,15 @@ function TerminalSection() {
     localStorage.setItem(TERMINAL_STORAGE_KEY, terminal);
   };
 
+  const [customCommand, setCustomCommand] = useState(
+    () => localStorage.getItem(CUSTOM_TERMINAL_STORAGE_KEY) ?? ""
+  );
+
+  const handleCustomCommandChange = (command: string) => {
+    setCustomCommand(command);
+    localStorage.setItem(CUSTOM_TERMINAL_STORAGE_KEY, command);
+  };
+
   return (
     <div className="space-y-6">
       <div>
@@ -271,6 +282,28 @@ function TerminalSection() {
           )}
         </CardContent>
       </Card>
+
+      {selectedTerminal === "custom" && (
+        <Card>
+          <CardHeader>
+            <CardTitle>Custom Command</CardTitle>
+            <CardDescription>
+              Command that opens a terminal. <code>{"{cwd}"}</code> is replaced
+              with the project directory and <code>{"{cmd}"}</code> with the
+              command to run, e.g.{" "}
+              <code>{"urxvt -cd {cwd} -e sh -c {cmd}"}</code>.
+            </CardDescription>
+          </CardHeader>
+          <CardContent>
+            <Input
+              value={customCommand}
+              onChange={(e) => handleCustomCommandChange(e.target.value)}
+              placeholder="urxvt -cd {cwd} -e sh -c {cmd}"
+              className="font-mono text-xs"
+            />
+          </CardContent>
+        </Card>
+      )}
     </div>
   );
 }